@@ -1,4 +1,5 @@
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bijection(
     // The indices of this vector are the new (output side) indexes.
     // The values are the old (input side) indexes to pull from.
@@ -27,6 +28,16 @@ impl Bijection {
     pub fn identity(count: usize) -> Bijection {
         Bijection((0..count).collect())
     }
+    /// Whether this maps each of the indexes up to `count` to exactly one of them
+    #[cfg(feature = "serialize")]
+    pub fn is_permutation_of(&self, count: usize) -> bool {
+        let mut seen = vec![false; count];
+        self.0.len() == count
+            && self
+                .0
+                .iter()
+                .all(|&index| index < count && !std::mem::replace(&mut seen[index], true))
+    }
     pub fn is_inverse_of(&self, other: &Bijection) -> bool {
         for (i, val) in self.0.iter().enumerate() {
             if other.0[*val] != i {
@@ -0,0 +1,528 @@
+//! Compares the solvers on the same seeded scrambles.
+//!
+//! Usage: bench-solvers [--scrambles N] [--depth N] [--seed N] [--timeout SECS]
+//!                      [--max-turns N] [--solvers NAME,...] [--json] [PUZZLE...]
+//!
+//! Each solver runs in a child process, so that a solve which takes longer than the timeout
+//! can be killed. The scrambles are generated from the seed, so every solver
+//! (and every run with the same options) gets the same ones.
+//! "States" is the number of states each solve derived with `TwistyPuzzle::get_derived_state`,
+//! which is only counted when built with `--features count-states`.
+
+use std::io::{BufRead, BufReader};
+use std::process::{self, Child, Command, Stdio};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use twisty_puzzles::puzzles;
+use twisty_puzzles::solver::{solver_by_name, AnySolver, ALL_SOLVERS};
+use twisty_puzzles::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+
+const DEFAULT_PUZZLES: [&str; 2] = ["2x2", "3x3"];
+
+// Marks the worker's own lines on stdout,
+// so that anything else that gets printed there isn't mistaken for a result
+const WORKER_PREFIX: &str = "@bench ";
+
+struct Args {
+    num_scrambles: usize,
+    depth: u64,
+    seed: u64,
+    timeout: Duration,
+    max_turns: usize,
+    solver_names: Vec<String>,
+    json: bool,
+    puzzle_names: Vec<String>,
+    // Set in the child processes: the puzzle, the solver, and the first scramble to solve
+    worker: Option<(String, String, usize)>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        num_scrambles: 10,
+        depth: 20,
+        seed: 0,
+        timeout: Duration::from_secs(60),
+        max_turns: 1000,
+        solver_names: ALL_SOLVERS.iter().map(|name| name.to_string()).collect(),
+        json: false,
+        puzzle_names: vec![],
+        worker: None,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("missing value for {}", name));
+        match arg.as_str() {
+            "--scrambles" => args.num_scrambles = parse_number(&value(&arg)?)?,
+            "--depth" => args.depth = parse_number(&value(&arg)?)?,
+            "--seed" => args.seed = parse_number(&value(&arg)?)?,
+            "--timeout" => {
+                let seconds = value(&arg)?;
+                args.timeout = Duration::try_from_secs_f64(parse_positive(&arg, &seconds)?)
+                    .map_err(|_| format!("{} is too long: {}", arg, seconds))?
+            }
+            "--max-turns" => args.max_turns = parse_number(&value(&arg)?)?,
+            "--solvers" => {
+                args.solver_names = value(&arg)?.split(',').map(String::from).collect();
+                if let Some(name) = args
+                    .solver_names
+                    .iter()
+                    .find(|name| !ALL_SOLVERS.contains(&name.as_str()))
+                {
+                    return Err(format!(
+                        "unknown solver {} (expected one of {})",
+                        name,
+                        ALL_SOLVERS.join(", ")
+                    ));
+                }
+            }
+            "--json" => args.json = true,
+            "--worker" => {
+                let puzzle_name = value(&arg)?;
+                let solver_name = value(&arg)?;
+                let first_scramble = parse_number(&value(&arg)?)?;
+                args.worker = Some((puzzle_name, solver_name, first_scramble));
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => args.puzzle_names.push(arg),
+        }
+    }
+    Ok(args)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {}", value))
+}
+
+/// Parses a number that has to be more than zero (and finite), like a timeout
+fn parse_positive(name: &str, value: &str) -> Result<f64, String> {
+    let number: f64 = parse_number(value)?;
+    if number > 0.0 && number.is_finite() {
+        Ok(number)
+    } else {
+        Err(format!("{} must be more than 0, not {}", name, value))
+    }
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(1);
+    });
+
+    if let Some((puzzle_name, solver_name, first_scramble)) = &args.worker {
+        run_worker(&args, puzzle_name, solver_name, *first_scramble);
+        return;
+    }
+
+    let puzzle_names: Vec<&str> = if args.puzzle_names.is_empty() {
+        DEFAULT_PUZZLES.to_vec()
+    } else {
+        args.puzzle_names.iter().map(|name| name.as_str()).collect()
+    };
+    if let Some(name) = puzzle_names
+        .iter()
+        .find(|name| puzzles::by_name(name).is_none())
+    {
+        eprintln!("unknown puzzle {}", name);
+        process::exit(1);
+    }
+
+    let mut reports = vec![];
+    for puzzle_name in &puzzle_names {
+        for solver_name in &args.solver_names {
+            eprintln!("running {} on {}", solver_name, puzzle_name);
+            let outcomes = run_benchmark(&args, puzzle_name, solver_name);
+            reports.push(Report::new(
+                puzzle_name,
+                solver_name,
+                args.num_scrambles,
+                &outcomes,
+            ));
+        }
+    }
+
+    if args.json {
+        print_json(&args, &reports);
+    } else {
+        print_table(&reports);
+    }
+}
+
+enum Outcome {
+    Setup { seconds: f64 },
+    SetupFailed,
+    Finished(Solve),
+    TimedOut,
+    // The worker exited without reporting a result, probably because the solver panicked
+    Crashed,
+}
+
+struct Solve {
+    solved: bool,
+    num_turns: usize,
+    seconds: f64,
+    // None unless the states are counted
+    num_states: Option<usize>,
+}
+
+/// Runs the solver on every scramble, starting a new worker after each timeout or crash
+fn run_benchmark(args: &Args, puzzle_name: &str, solver_name: &str) -> Vec<Outcome> {
+    let mut outcomes = vec![];
+    let mut next_scramble = 0;
+    while next_scramble < args.num_scrambles {
+        let (mut child, lines) = spawn_worker(args, puzzle_name, solver_name, next_scramble);
+
+        // Discovering metamoves can take a while too, so it gets its own timeout
+        match lines.recv_timeout(args.timeout) {
+            Ok(line) => match parse_worker_line(&line) {
+                Some(outcome @ Outcome::Setup { .. }) => outcomes.push(outcome),
+                _ => {
+                    outcomes.push(Outcome::SetupFailed);
+                    kill(&mut child);
+                    break;
+                }
+            },
+            Err(_) => {
+                outcomes.push(Outcome::SetupFailed);
+                kill(&mut child);
+                break;
+            }
+        }
+
+        while next_scramble < args.num_scrambles {
+            let outcome = match lines.recv_timeout(args.timeout) {
+                Ok(line) => parse_worker_line(&line).unwrap_or(Outcome::Crashed),
+                Err(RecvTimeoutError::Timeout) => Outcome::TimedOut,
+                Err(RecvTimeoutError::Disconnected) => Outcome::Crashed,
+            };
+            next_scramble += 1;
+            let restart = !matches!(outcome, Outcome::Finished(_));
+            outcomes.push(outcome);
+            if restart {
+                break;
+            }
+        }
+        kill(&mut child);
+    }
+    outcomes
+}
+
+/// Starts a worker process, and forwards the lines that it reports through a channel
+fn spawn_worker(
+    args: &Args,
+    puzzle_name: &str,
+    solver_name: &str,
+    first_scramble: usize,
+) -> (Child, Receiver<String>) {
+    let exe = std::env::current_exe().expect("failed to find the current executable");
+    let mut child = Command::new(exe)
+        .args(["--worker", puzzle_name, solver_name])
+        .arg(first_scramble.to_string())
+        .args(["--scrambles", &args.num_scrambles.to_string()])
+        .args(["--depth", &args.depth.to_string()])
+        .args(["--seed", &args.seed.to_string()])
+        .args(["--max-turns", &args.max_turns.to_string()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .expect("failed to start worker");
+
+    let stdout = child.stdout.take().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            if let Some(line) = line.strip_prefix(WORKER_PREFIX) {
+                if sender.send(line.to_string()).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    (child, receiver)
+}
+
+fn kill(child: &mut Child) {
+    // The worker may have already exited
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn parse_worker_line(line: &str) -> Option<Outcome> {
+    let mut words = line.split_whitespace();
+    match words.next()? {
+        "setup" => Some(Outcome::Setup {
+            seconds: words.next()?.parse().ok()?,
+        }),
+        "setup-failed" => Some(Outcome::SetupFailed),
+        "result" => Some(Outcome::Finished(Solve {
+            solved: words.next()? == "1",
+            num_turns: words.next()?.parse().ok()?,
+            seconds: words.next()?.parse().ok()?,
+            num_states: words.next()?.parse().ok(),
+        })),
+        _ => None,
+    }
+}
+
+fn solve(
+    solver: &dyn AnySolver,
+    puzzle: &TwistyPuzzle,
+    state: PuzzleState,
+    max_turns: usize,
+) -> Solve {
+    #[cfg(feature = "count-states")]
+    puzzle.reset_num_derived_states();
+    let start = Instant::now();
+    let mut solver = solver.solve(state).expect("scrambles are valid states");
+    // Some solvers never stop if they get stuck
+    let num_turns = solver.by_ref().take(max_turns).count();
+    Solve {
+        solved: puzzle.is_solved(solver.get_state()),
+        num_turns,
+        seconds: start.elapsed().as_secs_f64(),
+        num_states: num_derived_states(puzzle),
+    }
+}
+
+#[cfg(feature = "count-states")]
+fn num_derived_states(puzzle: &TwistyPuzzle) -> Option<usize> {
+    Some(puzzle.num_derived_states())
+}
+
+#[cfg(not(feature = "count-states"))]
+fn num_derived_states(_puzzle: &TwistyPuzzle) -> Option<usize> {
+    None
+}
+
+/// Solves the scrambles from `first_scramble` onwards, reporting each result on its own line
+fn run_worker(args: &Args, puzzle_name: &str, solver_name: &str, first_scramble: usize) {
+    let puzzle = Rc::new(puzzles::by_name(puzzle_name).expect("unknown puzzle"));
+    let mut rng = ChaCha8Rng::seed_from_u64(args.seed);
+    let scrambles: Vec<PuzzleState> = (0..args.num_scrambles)
+        .map(|_| puzzle.scramble(&puzzle.get_initial_state(), args.depth, &mut rng))
+        .collect();
+
+    let start = Instant::now();
+    let solver = match solver_by_name(solver_name, puzzle_name, &puzzle)
+        .expect("solver names are checked when parsing the arguments")
+    {
+        Ok(solver) => solver,
+        Err(error) => {
+            eprintln!("{}: {}", solver_name, error);
+            println!("{}setup-failed", WORKER_PREFIX);
+            return;
+        }
+    };
+    println!("{}setup {}", WORKER_PREFIX, start.elapsed().as_secs_f64());
+
+    for state in scrambles.into_iter().skip(first_scramble) {
+        let result = solve(solver.as_ref(), &puzzle, state, args.max_turns);
+        println!(
+            "{}result {} {} {} {}",
+            WORKER_PREFIX,
+            if result.solved { 1 } else { 0 },
+            result.num_turns,
+            result.seconds,
+            result
+                .num_states
+                .map_or("-".to_string(), |num_states| num_states.to_string())
+        );
+    }
+}
+
+struct Report {
+    puzzle_name: String,
+    solver_name: String,
+    // Scrambles that weren't reached because the setup failed count as unsolved
+    num_scrambles: usize,
+    num_solved: usize,
+    num_timed_out: usize,
+    num_crashed: usize,
+    setup_failed: bool,
+    // The slowest setup, since it runs again whenever a worker is restarted
+    setup_seconds: f64,
+    // Over the solved scrambles
+    mean_turns: Option<f64>,
+    // Over the scrambles that finished, whether or not they were solved
+    mean_seconds: Option<f64>,
+    max_seconds: Option<f64>,
+    mean_states: Option<f64>,
+    peak_states: Option<usize>,
+}
+
+impl Report {
+    fn new(
+        puzzle_name: &str,
+        solver_name: &str,
+        num_scrambles: usize,
+        outcomes: &[Outcome],
+    ) -> Self {
+        let solves: Vec<&Solve> = outcomes
+            .iter()
+            .filter_map(|outcome| match outcome {
+                Outcome::Finished(solve) => Some(solve),
+                _ => None,
+            })
+            .collect();
+        let solved: Vec<&&Solve> = solves.iter().filter(|solve| solve.solved).collect();
+        let count = |f: fn(&Outcome) -> bool| outcomes.iter().filter(|o| f(o)).count();
+        let mean = |values: Vec<f64>| {
+            if values.is_empty() {
+                None
+            } else {
+                Some(values.iter().sum::<f64>() / values.len() as f64)
+            }
+        };
+        Self {
+            puzzle_name: puzzle_name.to_string(),
+            solver_name: solver_name.to_string(),
+            num_scrambles,
+            num_solved: solved.len(),
+            num_timed_out: count(|o| matches!(o, Outcome::TimedOut)),
+            num_crashed: count(|o| matches!(o, Outcome::Crashed)),
+            setup_failed: count(|o| matches!(o, Outcome::SetupFailed)) > 0,
+            setup_seconds: outcomes
+                .iter()
+                .filter_map(|outcome| match outcome {
+                    Outcome::Setup { seconds } => Some(*seconds),
+                    _ => None,
+                })
+                .fold(0.0, f64::max),
+            mean_turns: mean(solved.iter().map(|s| s.num_turns as f64).collect()),
+            mean_seconds: mean(solves.iter().map(|s| s.seconds).collect()),
+            max_seconds: solves.iter().map(|s| s.seconds).reduce(f64::max),
+            mean_states: mean(
+                solves
+                    .iter()
+                    .filter_map(|s| s.num_states)
+                    .map(|num_states| num_states as f64)
+                    .collect(),
+            ),
+            peak_states: solves.iter().filter_map(|s| s.num_states).max(),
+        }
+    }
+
+    fn success_rate(&self) -> f64 {
+        if self.num_scrambles == 0 {
+            0.0
+        } else {
+            self.num_solved as f64 / self.num_scrambles as f64
+        }
+    }
+}
+
+fn print_table(reports: &[Report]) {
+    println!(
+        "{:<14} {:<12} {:>9} {:>8} {:>9} {:>10} {:>10} {:>12} {:>12} {:>9} {:>9}",
+        "puzzle",
+        "solver",
+        "solved",
+        "success",
+        "turns",
+        "mean ms",
+        "max ms",
+        "mean states",
+        "peak states",
+        "timeouts",
+        "setup s"
+    );
+    let format_option = |value: Option<f64>, scale: f64| match value {
+        Some(value) => format!("{:.1}", value * scale),
+        None => "-".to_string(),
+    };
+    for report in reports {
+        if report.setup_failed && report.mean_seconds.is_none() {
+            println!(
+                "{:<14} {:<12} setup failed or timed out",
+                report.puzzle_name, report.solver_name
+            );
+            continue;
+        }
+        println!(
+            "{:<14} {:<12} {:>9} {:>7.0}% {:>9} {:>10} {:>10} {:>12} {:>12} {:>9} {:>9.1}",
+            report.puzzle_name,
+            report.solver_name,
+            format!("{}/{}", report.num_solved, report.num_scrambles),
+            report.success_rate() * 100.0,
+            format_option(report.mean_turns, 1.0),
+            format_option(report.mean_seconds, 1000.0),
+            format_option(report.max_seconds, 1000.0),
+            format_option(report.mean_states, 1.0),
+            report
+                .peak_states
+                .map_or("-".to_string(), |peak| peak.to_string()),
+            report.num_timed_out + report.num_crashed,
+            report.setup_seconds,
+        );
+    }
+}
+
+fn print_json(args: &Args, reports: &[Report]) {
+    let json_option = |value: Option<f64>| match value {
+        Some(value) => format!("{}", value),
+        None => "null".to_string(),
+    };
+    let results: Vec<String> = reports
+        .iter()
+        .map(|report| {
+            format!(
+                concat!(
+                    "{{\"puzzle\":{},\"solver\":{},\"scrambles\":{},\"solved\":{},",
+                    "\"success_rate\":{},\"timed_out\":{},\"crashed\":{},\"setup_failed\":{},",
+                    "\"setup_seconds\":{},\"mean_turns\":{},\"mean_seconds\":{},",
+                    "\"max_seconds\":{},\"mean_states\":{},\"peak_states\":{}}}"
+                ),
+                json_string(&report.puzzle_name),
+                json_string(&report.solver_name),
+                report.num_scrambles,
+                report.num_solved,
+                report.success_rate(),
+                report.num_timed_out,
+                report.num_crashed,
+                report.setup_failed,
+                report.setup_seconds,
+                json_option(report.mean_turns),
+                json_option(report.mean_seconds),
+                json_option(report.max_seconds),
+                json_option(report.mean_states),
+                json_option(report.peak_states.map(|peak| peak as f64)),
+            )
+        })
+        .collect();
+    println!(
+        "{{\"seed\":{},\"scrambles\":{},\"depth\":{},\"timeout_seconds\":{},\"max_turns\":{},\"results\":[{}]}}",
+        args.seed,
+        args.num_scrambles,
+        args.depth,
+        args.timeout.as_secs_f64(),
+        args.max_turns,
+        results.join(",")
+    );
+}
+
+/// Quotes the string for JSON, escaping the characters that JSON doesn't allow as they are
+/// (Rust's `{:?}` escapes differently, like `\u{1b}` instead of `\u001b`)
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
@@ -0,0 +1,87 @@
+//! Discovers the metamoves the phased solver needs for each puzzle,
+//! and writes them to the catalog that is embedded into the crate.
+//!
+//! Usage: build-catalog [--out FILE] [--all] [PUZZLE...]
+//!
+//! By default, this builds the catalog for the deployed puzzles
+//! and overwrites `catalog/metamoves.bin`.
+//! With `--all`, it also tries every other built-in puzzle,
+//! skipping and logging the ones the phased solver can't find the metamoves for.
+//! If it can't find them for a deployed puzzle, or for one named on the command line,
+//! nothing is written and it exits with an error.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process;
+use std::rc::Rc;
+use std::time::Instant;
+
+use twisty_puzzles::puzzles::{self, ALL_PUZZLES, DEPLOYED_PUZZLES};
+use twisty_puzzles::solver::{discover_phase_catalog, Catalog, PuzzleCatalog};
+
+fn main() {
+    let mut out_path = PathBuf::from("catalog/metamoves.bin");
+    let mut all = false;
+    let mut required_names = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => {
+                out_path = PathBuf::from(args.next().unwrap_or_else(|| {
+                    eprintln!("missing value for --out");
+                    process::exit(1);
+                }))
+            }
+            "--all" => all = true,
+            _ => required_names.push(arg),
+        }
+    }
+    if required_names.is_empty() {
+        required_names = DEPLOYED_PUZZLES
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+    }
+    let mut puzzle_names = required_names.clone();
+    if all {
+        for info in ALL_PUZZLES {
+            if !puzzle_names.iter().any(|name| name == info.name) {
+                puzzle_names.push(info.name.to_string());
+            }
+        }
+    }
+
+    let mut catalog = Catalog { puzzles: vec![] };
+    for name in puzzle_names {
+        let puzzle = Rc::new(puzzles::by_name(&name).unwrap_or_else(|| {
+            eprintln!("unknown puzzle {}", name);
+            process::exit(1);
+        }));
+        let start = Instant::now();
+        match discover_phase_catalog(&puzzle) {
+            Ok(phases) => {
+                eprintln!(
+                    "{}: {} phases in {:.1}s",
+                    name,
+                    phases.len(),
+                    start.elapsed().as_secs_f64()
+                );
+                catalog.puzzles.push(PuzzleCatalog { name, phases });
+            }
+            Err(error)
+                if !required_names.contains(&name) && !DEPLOYED_PUZZLES.contains(&&*name) =>
+            {
+                eprintln!("{}: skipped, {}", name, error);
+            }
+            Err(error) => {
+                eprintln!("{}: {}", name, error);
+                process::exit(1);
+            }
+        }
+    }
+
+    let mut writer = BufWriter::new(File::create(&out_path).expect("failed to create file"));
+    catalog.write(&mut writer).expect("failed to write catalog");
+    eprintln!("catalog written to {}", out_path.display());
+}
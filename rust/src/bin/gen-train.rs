@@ -0,0 +1,102 @@
+//! Generates training data for learned solving heuristics.
+//!
+//! Usage: gen-train [--out DIR] [--scrambles N] [--depth N] [--seed N] [--csv] [PUZZLE...]
+//!
+//! Each puzzle (all of the built-in puzzles by default) is scrambled with random walks,
+//! and every state along each walk is written to `DIR/<puzzle>.bin` (or `.csv`).
+//! Each state is labelled with its depth in the walk and with the turn that leaves it closest
+//! to solved by the pieces' distances from solved (see `TwistyPuzzle::heuristic_distance`).
+//! No solver is run on the states, so the depth is only an upper bound on the solution.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use twisty_puzzles::puzzles;
+use twisty_puzzles::training_data::TrainingData;
+
+struct Args {
+    out_dir: PathBuf,
+    num_scrambles: usize,
+    depth: usize,
+    seed: u64,
+    csv: bool,
+    puzzle_names: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        out_dir: PathBuf::from("training-data"),
+        num_scrambles: 10_000,
+        depth: 20,
+        seed: 0,
+        csv: false,
+        puzzle_names: vec![],
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("missing value for {}", name));
+        match arg.as_str() {
+            "--out" => args.out_dir = PathBuf::from(value(&arg)?),
+            "--scrambles" => args.num_scrambles = parse_number(&value(&arg)?)?,
+            "--depth" => args.depth = parse_number(&value(&arg)?)?,
+            "--seed" => args.seed = parse_number(&value(&arg)?)?,
+            "--csv" => args.csv = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => args.puzzle_names.push(arg),
+        }
+    }
+    Ok(args)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {}", value))
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(1);
+    });
+
+    let puzzle_names: Vec<&str> = if args.puzzle_names.is_empty() {
+        puzzles::ALL_PUZZLES.iter().map(|info| info.name).collect()
+    } else {
+        args.puzzle_names.iter().map(|name| name.as_str()).collect()
+    };
+
+    fs::create_dir_all(&args.out_dir).expect("failed to create output directory");
+
+    for name in puzzle_names {
+        let puzzle = puzzles::by_name(name).unwrap_or_else(|| {
+            eprintln!("unknown puzzle {}", name);
+            process::exit(1);
+        });
+        let mut rng = ChaCha8Rng::seed_from_u64(args.seed);
+        let mut data = TrainingData::new(&puzzle);
+        for _ in 0..args.num_scrambles {
+            data.add_random_walk(&puzzle, args.depth, &mut rng);
+        }
+
+        let extension = if args.csv { "csv" } else { "bin" };
+        let path = args.out_dir.join(format!("{}.{}", name, extension));
+        let mut writer = BufWriter::new(File::create(&path).expect("failed to create file"));
+        if args.csv {
+            data.write_csv(&mut writer)
+        } else {
+            data.write(&mut writer)
+        }
+        .expect("failed to write training data");
+        println!(
+            "{}: {} examples written to {}",
+            name,
+            data.examples.len(),
+            path.display()
+        );
+    }
+}
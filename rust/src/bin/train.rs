@@ -0,0 +1,136 @@
+//! Trains a value/policy network on data from `gen-train`.
+//!
+//! Usage: train DATA [--puzzle NAME] [--out FILE] [--hidden N,N,...] [--epochs N]
+//!              [--batch-size N] [--learning-rate X] [--seed N]
+//!
+//! The puzzle defaults to the name of the data file (e.g. `training-data/3x3.bin`),
+//! and the weights are written next to it as `<puzzle>.weights` by default.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::process;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use twisty_puzzles::network::{train_epoch, Network, Trainer};
+use twisty_puzzles::puzzles;
+use twisty_puzzles::training_data::TrainingData;
+
+struct Args {
+    data_path: PathBuf,
+    puzzle_name: Option<String>,
+    out_path: Option<PathBuf>,
+    hidden_sizes: Vec<usize>,
+    num_epochs: usize,
+    batch_size: usize,
+    learning_rate: f32,
+    seed: u64,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut data_path = None;
+    let mut args = Args {
+        data_path: PathBuf::new(),
+        puzzle_name: None,
+        out_path: None,
+        hidden_sizes: vec![256, 128],
+        num_epochs: 20,
+        batch_size: 256,
+        learning_rate: 0.001,
+        seed: 0,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("missing value for {}", name));
+        match arg.as_str() {
+            "--puzzle" => args.puzzle_name = Some(value(&arg)?),
+            "--out" => args.out_path = Some(PathBuf::from(value(&arg)?)),
+            "--hidden" => {
+                args.hidden_sizes = value(&arg)?
+                    .split(',')
+                    .map(parse_number)
+                    .collect::<Result<_, _>>()?
+            }
+            "--epochs" => args.num_epochs = parse_number(&value(&arg)?)?,
+            "--batch-size" => args.batch_size = parse_positive(&arg, &value(&arg)?)?,
+            "--learning-rate" => args.learning_rate = parse_number(&value(&arg)?)?,
+            "--seed" => args.seed = parse_number(&value(&arg)?)?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => data_path = Some(PathBuf::from(arg)),
+        }
+    }
+    args.data_path = data_path.ok_or("missing training data file")?;
+    Ok(args)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {}", value))
+}
+
+/// Parses a count that has to be more than zero, like a batch size
+fn parse_positive(name: &str, value: &str) -> Result<usize, String> {
+    match parse_number(value)? {
+        0 => Err(format!("{} must be more than 0", name)),
+        number => Ok(number),
+    }
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(1);
+    });
+
+    let puzzle_name = args.puzzle_name.clone().unwrap_or_else(|| {
+        args.data_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_string()
+    });
+    let puzzle = puzzles::by_name(&puzzle_name).unwrap_or_else(|| {
+        eprintln!("unknown puzzle {}", puzzle_name);
+        process::exit(1);
+    });
+
+    let file = File::open(&args.data_path).expect("failed to open training data");
+    let data = TrainingData::read(&mut BufReader::new(file)).expect("failed to read training data");
+    if data.num_faces != puzzle.get_num_faces() || data.num_turns != puzzle.turn_names.len() {
+        eprintln!("training data is not for {}", puzzle_name);
+        process::exit(1);
+    }
+    println!("{}: {} examples", puzzle_name, data.examples.len());
+
+    let mut rng = ChaCha8Rng::seed_from_u64(args.seed);
+    let network = Network::new(&puzzle, &args.hidden_sizes, &mut rng);
+    let mut trainer = Trainer::new(network, args.learning_rate);
+    for epoch in 0..args.num_epochs {
+        let (value_loss, policy_loss) =
+            train_epoch(&mut trainer, &puzzle, &data, args.batch_size, &mut rng).unwrap_or_else(
+                |error| {
+                    eprintln!("{}", error);
+                    process::exit(1);
+                },
+            );
+        println!(
+            "epoch {}: value loss {:.4}, policy loss {:.4}",
+            epoch + 1,
+            value_loss,
+            policy_loss
+        );
+    }
+
+    let out_path = args.out_path.unwrap_or_else(|| {
+        args.data_path
+            .with_file_name(format!("{}.weights", puzzle_name))
+    });
+    let mut writer = BufWriter::new(File::create(&out_path).expect("failed to create file"));
+    trainer
+        .network
+        .write(&mut writer)
+        .expect("failed to write weights");
+    println!("weights written to {}", out_path.display());
+}
@@ -0,0 +1,477 @@
+//! Solves, scrambles, and inspects the built-in puzzles from the command line.
+//!
+//! Usage:
+//!   twisty list
+//!   twisty scramble PUZZLE [--seed N] [--length N] [--svg FILE]
+//!   twisty solve PUZZLE MOVES|STATE [--solver NAME] [--network FILE] [--max-turns N]
+//!   twisty replay PUZZLE MOVES [--solution MOVES] [--tps N] [--solver NAME] [--max-turns N]
+//!   twisty apply PUZZLE MOVES [--state STATE]
+//!   twisty inverse PUZZLE MOVES
+//!   twisty order PUZZLE MOVES
+//!   twisty image PUZZLE MOVES|STATE [--colors COLORS] [--width N]
+//!   twisty model PUZZLE [MOVES|STATE] [--format obj|gltf|stl] [--gap N] [--scale N] [--colors COLORS]
+//!   twisty info PUZZLE
+//!
+//! MOVES are turn names separated by spaces or commas, like "R U R' U'".
+//! A STATE is the color of every face separated by commas, as printed by `apply`.
+//! `replay` plays back a solution to the scrambling MOVES one turn at a time,
+//! at N turns per second if `--tps` is given. Without `--solution`, the solver finds one.
+//! `image` prints an SVG of the puzzle's net in the state, like the ones on scramble sheets,
+//! and `scramble --svg` saves one for the scramble. COLORS are hex colors or names in the
+//! order of the puzzle's colors, separated by commas, like "white,#1976d2,orange".
+//! `model` prints a 3D model of the pieces, colored like the state (OBJ and glTF keep the colors,
+//! STL is for 3D printing). The puzzle is scaled up N times (it is about 1 across to start with),
+//! and the pieces are shrunk to leave a gap of N between them.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::process;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use twisty_puzzles::history::{MoveHistory, Playback};
+use twisty_puzzles::mesh::{piece_meshes, to_gltf, to_obj, to_stl};
+use twisty_puzzles::net::scramble_svg;
+use twisty_puzzles::network::Network;
+use twisty_puzzles::puzzles;
+use twisty_puzzles::render::ColorScheme;
+use twisty_puzzles::solver::{
+    solver_by_name, AnySolver, MetaMove, NetworkSolver, NetworkSolverOpts, Solver, ALL_SOLVERS,
+};
+use twisty_puzzles::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+
+const USAGE: &str =
+    "usage: twisty <list|scramble|solve|replay|apply|inverse|order|image|model|info> [PUZZLE] [ARGS...]";
+// Not one of `ALL_SOLVERS`, since it needs the network passed with --network
+const NETWORK_SOLVER: &str = "network";
+const MODEL_FORMATS: [&str; 3] = ["obj", "gltf", "stl"];
+
+struct Args {
+    command: String,
+    puzzle_name: Option<String>,
+    // The moves or state, joined back together if they were passed as separate arguments
+    input: String,
+    seed: Option<u64>,
+    length: u64,
+    solver_name: String,
+    network_path: Option<String>,
+    max_turns: usize,
+    state: Option<String>,
+    solution: Option<String>,
+    turns_per_second: Option<f64>,
+    colors: Option<String>,
+    width: f64,
+    svg_path: Option<String>,
+    model_format: String,
+    gap: f64,
+    scale: f64,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut iter = std::env::args().skip(1);
+    let command = iter.next().ok_or(USAGE)?;
+    let mut args = Args {
+        command,
+        puzzle_name: None,
+        input: String::new(),
+        seed: None,
+        length: 25,
+        solver_name: "phased".to_string(),
+        network_path: None,
+        max_turns: 1000,
+        state: None,
+        solution: None,
+        turns_per_second: None,
+        colors: None,
+        width: 300.0,
+        svg_path: None,
+        model_format: "obj".to_string(),
+        gap: 0.0,
+        scale: 1.0,
+    };
+    let mut positional = vec![];
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("missing value for {}", name));
+        match arg.as_str() {
+            "--seed" => args.seed = Some(parse_number(&value(&arg)?)?),
+            "--length" => args.length = parse_number(&value(&arg)?)?,
+            "--solver" => {
+                args.solver_name = value(&arg)?;
+                if !ALL_SOLVERS.contains(&args.solver_name.as_str())
+                    && args.solver_name != NETWORK_SOLVER
+                {
+                    return Err(format!(
+                        "unknown solver {} (expected one of {}, {})",
+                        args.solver_name,
+                        ALL_SOLVERS.join(", "),
+                        NETWORK_SOLVER
+                    ));
+                }
+            }
+            "--network" => args.network_path = Some(value(&arg)?),
+            "--max-turns" => args.max_turns = parse_number(&value(&arg)?)?,
+            "--state" => args.state = Some(value(&arg)?),
+            "--solution" => args.solution = Some(value(&arg)?),
+            "--tps" => args.turns_per_second = Some(parse_positive(&arg, &value(&arg)?)?),
+            "--colors" => args.colors = Some(value(&arg)?),
+            "--width" => args.width = parse_positive(&arg, &value(&arg)?)?,
+            "--svg" => args.svg_path = Some(value(&arg)?),
+            "--format" => {
+                args.model_format = value(&arg)?;
+                if !MODEL_FORMATS.contains(&args.model_format.as_str()) {
+                    return Err(format!(
+                        "unknown format {} (expected one of {})",
+                        args.model_format,
+                        MODEL_FORMATS.join(", ")
+                    ));
+                }
+            }
+            "--gap" => args.gap = parse_non_negative(&arg, &value(&arg)?)?,
+            "--scale" => args.scale = parse_positive(&arg, &value(&arg)?)?,
+            // Turn names never start with dashes, so this must be a mistyped option
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    args.puzzle_name = positional.next();
+    args.input = positional.collect::<Vec<_>>().join(" ");
+    Ok(args)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {}", value))
+}
+
+/// Parses a number that has to be more than zero (and finite), like a speed or a size
+fn parse_positive(name: &str, value: &str) -> Result<f64, String> {
+    let number: f64 = parse_number(value)?;
+    if number > 0.0 && number.is_finite() {
+        Ok(number)
+    } else {
+        Err(format!("{} must be more than 0, not {}", name, value))
+    }
+}
+
+/// Parses a number that can't be less than zero (and is finite), like a distance
+fn parse_non_negative(name: &str, value: &str) -> Result<f64, String> {
+    let number: f64 = parse_number(value)?;
+    if number >= 0.0 && number.is_finite() {
+        Ok(number)
+    } else {
+        Err(format!("{} can't be less than 0, not {}", name, value))
+    }
+}
+
+fn main() {
+    let result = parse_args().and_then(|args| run(&args));
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    if args.command == "list" {
+        for info in puzzles::ALL_PUZZLES {
+            println!("{}", info.name);
+        }
+        return Ok(());
+    }
+
+    let puzzle_name = args.puzzle_name.as_deref().ok_or(USAGE)?;
+    let puzzle =
+        Rc::new(puzzles::by_name(puzzle_name).ok_or(format!("unknown puzzle {}", puzzle_name))?);
+
+    match args.command.as_str() {
+        "scramble" => {
+            let mut rng = match args.seed {
+                Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+                None => ChaCha8Rng::from_entropy(),
+            };
+            let scramble = puzzle.scramble_turns(args.length, &mut rng);
+            println!("{}", format_turns(&puzzle, &scramble));
+            if let Some(path) = &args.svg_path {
+                let state = puzzle.get_derived_state_from_turn_sequence(
+                    &puzzle.get_initial_state(),
+                    scramble.into_iter(),
+                );
+                std::fs::write(path, scramble_image(&puzzle, &state, args)?)
+                    .map_err(|error| format!("{}: {}", path, error))?;
+            }
+        }
+        "solve" => {
+            let state = parse_moves_or_state(&puzzle, &args.input)?;
+            let solver = solver(&puzzle, puzzle_name, args)?;
+            let (solution, solved) = find_solution(solver.as_ref(), &puzzle, state, args)?;
+            println!("{}", format_turns(&puzzle, &solution));
+            if !solved {
+                return Err(format!(
+                    "the {} solver did not solve it in {} turns",
+                    args.solver_name,
+                    solution.len()
+                ));
+            }
+        }
+        "replay" => {
+            let scramble = parse_turns(&puzzle, &args.input)?;
+            let solution = match &args.solution {
+                Some(solution) => parse_turns(&puzzle, solution)?,
+                None => {
+                    let state = puzzle.get_derived_state_from_turn_sequence(
+                        &puzzle.get_initial_state(),
+                        scramble.iter().copied(),
+                    );
+                    let solver = solver(&puzzle, puzzle_name, args)?;
+                    find_solution(solver.as_ref(), &puzzle, state, args)?.0
+                }
+            };
+            replay(&puzzle, &scramble, &solution, args.turns_per_second);
+        }
+        "apply" => {
+            let initial_state = match &args.state {
+                Some(state) => parse_state(&puzzle, state)?,
+                None => puzzle.get_initial_state(),
+            };
+            let turns = parse_turns(&puzzle, &args.input)?;
+            let state =
+                puzzle.get_derived_state_from_turn_sequence(&initial_state, turns.into_iter());
+            println!("{}", format_state(&state));
+        }
+        "inverse" => {
+            let turns = parse_turns(&puzzle, &args.input)?;
+            let inverse: Vec<usize> = turns
+                .iter()
+                .rev()
+                .map(|&turn_index| puzzle.inverted_turn_index(turn_index))
+                .collect();
+            println!("{}", format_turns(&puzzle, &inverse));
+        }
+        "order" => {
+            let turns = parse_turns(&puzzle, &args.input)?;
+            let metamove = MetaMove::new_infer_face_map(Rc::clone(&puzzle), turns);
+            // Repeating the moves returns every face to where it started
+            // once the number of repeats is a multiple of every cycle's length
+            let order = metamove
+                .cycles()
+                .iter()
+                .fold(1, |order, cycle| num::integer::lcm(order, cycle.len()));
+            println!("{}", order);
+        }
+        "image" => {
+            let state = parse_moves_or_state(&puzzle, &args.input)?;
+            print!("{}", scramble_image(&puzzle, &state, args)?);
+        }
+        "model" => {
+            let state = parse_moves_or_state(&puzzle, &args.input)?;
+            // The gap is in the units of the model, so it is the same however big the model is
+            let mut meshes = piece_meshes(&puzzle, &state, args.gap / args.scale)
+                .map_err(|error| error.to_string())?;
+            for mesh in &mut meshes {
+                mesh.scale(args.scale);
+            }
+            let model = match args.model_format.as_str() {
+                "obj" => to_obj(&meshes, &color_scheme(args)?),
+                "gltf" => to_gltf(&meshes, &color_scheme(args)?),
+                "stl" => to_stl(&meshes),
+                _ => unreachable!("formats are checked when parsing the arguments"),
+            };
+            print!("{}", model);
+        }
+        "info" => print_info(&puzzle, puzzle_name),
+        _ => return Err(format!("unknown command {}\n{}", args.command, USAGE)),
+    }
+    Ok(())
+}
+
+/// Sets up the solver chosen with --solver, loading its network if it is the network solver
+fn solver(
+    puzzle: &Rc<TwistyPuzzle>,
+    puzzle_name: &str,
+    args: &Args,
+) -> Result<Box<dyn AnySolver>, String> {
+    if args.solver_name != NETWORK_SOLVER {
+        return solver_by_name(&args.solver_name, puzzle_name, puzzle)
+            .expect("solver names are checked when parsing the arguments")
+            .map_err(|error| error.to_string());
+    }
+    let path = args
+        .network_path
+        .as_ref()
+        .ok_or("the network solver needs --network FILE")?;
+    let file = File::open(path).map_err(|error| format!("{}: {}", path, error))?;
+    let network = Network::read(&mut BufReader::new(file), puzzle)
+        .map_err(|error| format!("{}: {}", path, error))?;
+    Ok(Box::new(Solver::<NetworkSolver>::new(
+        Rc::clone(puzzle),
+        NetworkSolverOpts {
+            network: Rc::new(network),
+            weight: 0.6,
+            beam_width: 10_000,
+            max_expansions: 100_000,
+            max_turns_per_state: None,
+        },
+    )))
+}
+
+/// Returns the solver's turns from the state, and whether they solve it
+fn find_solution(
+    solver: &dyn AnySolver,
+    puzzle: &TwistyPuzzle,
+    state: PuzzleState,
+    args: &Args,
+) -> Result<(Vec<usize>, bool), String> {
+    let mut scramble_solver = solver.solve(state).map_err(|error| error.to_string())?;
+    // Some solvers never stop if they get stuck
+    let solution: Vec<usize> = scramble_solver.by_ref().take(args.max_turns).collect();
+    Ok((solution, puzzle.is_solved(scramble_solver.get_state())))
+}
+
+/// Prints each turn of the solution as it is played back, with how many pieces are solved after it
+fn replay(
+    puzzle: &TwistyPuzzle,
+    scramble: &[usize],
+    solution: &[usize],
+    turns_per_second: Option<f64>,
+) {
+    let mut history = MoveHistory::reconstruction(puzzle, scramble, solution);
+    // Without a speed, every turn is due as soon as playback starts
+    let mut playback = Playback::new(turns_per_second.unwrap_or(f64::INFINITY));
+    println!("scramble: {}", format_turns(puzzle, scramble));
+    playback.play();
+    let mut last_time = Instant::now();
+    while playback.is_playing() {
+        thread::sleep(Duration::from_secs_f64(1.0 / playback.turns_per_second()));
+        let time = Instant::now();
+        // Several turns can be due at once, so each one is printed with the state just after it
+        let mut position = history.position();
+        let mut state = history.state().clone();
+        for turn_index in playback.advance(&mut history, (time - last_time).as_secs_f64()) {
+            position += 1;
+            state = puzzle.get_derived_state_turn_index(&state, turn_index);
+            println!(
+                "{}/{} {} ({}/{} pieces solved)",
+                position - scramble.len(),
+                solution.len(),
+                puzzle.turn_names[turn_index],
+                puzzle.get_num_solved_pieces(&state),
+                puzzle.get_num_pieces()
+            );
+        }
+        last_time = time;
+    }
+    if !puzzle.is_solved(history.state()) {
+        println!("not solved");
+    }
+}
+
+fn scramble_image(
+    puzzle: &TwistyPuzzle,
+    state: &PuzzleState,
+    args: &Args,
+) -> Result<String, String> {
+    Ok(scramble_svg(
+        puzzle,
+        state,
+        &color_scheme(args)?,
+        args.width,
+    ))
+}
+
+fn color_scheme(args: &Args) -> Result<ColorScheme, String> {
+    match &args.colors {
+        Some(colors) => ColorScheme::parse(colors).map_err(|error| error.to_string()),
+        None => Ok(ColorScheme::default()),
+    }
+}
+
+fn print_info(puzzle: &TwistyPuzzle, puzzle_name: &str) {
+    println!("puzzle: {}", puzzle_name);
+    println!("faces: {}", puzzle.get_num_faces());
+    println!("pieces: {}", puzzle.get_num_pieces());
+    println!(
+        "turns: {} ({})",
+        puzzle.turn_names.len(),
+        puzzle.turn_names.join(" ")
+    );
+    println!("piece types: {}", puzzle.piece_types.len());
+    for (i, piece_type) in puzzle.piece_types.iter().enumerate() {
+        let num_pieces = puzzle.get_num_pieces_of_type(piece_type);
+        let num_faces = piece_type.face_mask().iter().filter(|&&f| f).count();
+        println!(
+            "  {}: {} pieces with {} faces each",
+            i,
+            num_pieces,
+            num_faces / num_pieces
+        );
+    }
+    println!("symmetries: {}", puzzle.symmetries.len());
+    println!("rotations: {}", puzzle.rotations.len());
+    println!("group size: {}", puzzle.group_order());
+}
+
+fn parse_turns(puzzle: &TwistyPuzzle, input: &str) -> Result<Vec<usize>, String> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            puzzle
+                .turn_names
+                .iter()
+                .position(|turn_name| turn_name == name)
+                .ok_or(format!(
+                    "unknown turn {} (expected one of {})",
+                    name,
+                    puzzle.turn_names.join(" ")
+                ))
+        })
+        .collect()
+}
+
+fn parse_state(puzzle: &TwistyPuzzle, input: &str) -> Result<PuzzleState, String> {
+    let state = input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|value| !value.is_empty())
+        .map(parse_number)
+        .collect::<Result<PuzzleState, _>>()?;
+    puzzle
+        .validate_state(&state)
+        .map_err(|error| error.to_string())?;
+    Ok(state)
+}
+
+/// Reads a state if the input is all numbers, otherwise applies the moves to the solved state
+fn parse_moves_or_state(puzzle: &TwistyPuzzle, input: &str) -> Result<PuzzleState, String> {
+    let is_state = input.chars().any(|c| c.is_ascii_digit())
+        && input
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_whitespace() || c == ',');
+    if is_state {
+        parse_state(puzzle, input)
+    } else {
+        let turns = parse_turns(puzzle, input)?;
+        Ok(puzzle
+            .get_derived_state_from_turn_sequence(&puzzle.get_initial_state(), turns.into_iter()))
+    }
+}
+
+fn format_turns(puzzle: &TwistyPuzzle, turns: &[usize]) -> String {
+    turns
+        .iter()
+        .map(|&turn_index| puzzle.turn_names[turn_index].as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_state(state: &PuzzleState) -> String {
+    state
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+
+/// Everything that can go wrong while building a puzzle, solving it,
+/// or reading settings and saved solves for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The polyhedron or the cuts don't describe a puzzle that can be built
+    InvalidGeometry(String),
+    /// Cutting left nothing of one of the polyhedron's faces,
+    /// usually because a cut lies on the face or squeezes it between two cuts
+    DegeneratePiece { face_index: usize },
+    /// After a turn, a face ended up where no face was before,
+    /// usually because the turn's angle doesn't match the shape of the cut
+    UnmatchedTurnMapping { turn_name: String },
+    /// A point is close to more than one face center, so it isn't clear which face is there
+    AmbiguousMapping(String),
+    /// The solver couldn't find the metamoves that it needs for one of its phases
+    UnsolvablePhase(String),
+    /// The metamoves in a catalog don't fit the puzzle's turns or piece types
+    CatalogMismatch,
+    /// The network was trained for a puzzle with different faces, turns or colors
+    NetworkMismatch,
+    /// Training was asked to use batches with no examples in them
+    InvalidBatchSize,
+    /// The state isn't one that the puzzle can be in
+    InvalidState(String),
+    /// A key binding couldn't be read, or names a turn that the puzzle doesn't have
+    InvalidKeyBinding { line: usize, message: String },
+    /// A saved session of timed solves couldn't be read
+    InvalidSession { line: usize, message: String },
+    /// Saved data couldn't be read from or written to storage
+    StorageFailed(String),
+    /// A color scheme couldn't be read
+    InvalidColorScheme(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidGeometry(message) => write!(f, "invalid geometry: {}", message),
+            Error::DegeneratePiece { face_index } => {
+                write!(
+                    f,
+                    "face {} of the polyhedron has no area after cutting",
+                    face_index
+                )
+            }
+            Error::UnmatchedTurnMapping { turn_name } => write!(
+                f,
+                "turn {} moves a face to a position where there is no face",
+                turn_name
+            ),
+            Error::AmbiguousMapping(message) => write!(f, "ambiguous mapping: {}", message),
+            Error::UnsolvablePhase(message) => write!(f, "unsolvable phase: {}", message),
+            Error::CatalogMismatch => write!(f, "catalog is for a different puzzle"),
+            Error::NetworkMismatch => write!(f, "network was trained for a different puzzle"),
+            Error::InvalidBatchSize => write!(f, "batch size must be more than 0"),
+            Error::InvalidState(message) => write!(f, "invalid state: {}", message),
+            Error::InvalidKeyBinding { line, message } => {
+                write!(f, "invalid key binding on line {}: {}", line, message)
+            }
+            Error::InvalidSession { line, message } => {
+                write!(f, "invalid session on line {}: {}", line, message)
+            }
+            Error::StorageFailed(message) => write!(f, "storage failed: {}", message),
+            Error::InvalidColorScheme(message) => write!(f, "invalid color scheme: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,246 @@
+//! The turns that have been made, so they can be undone, redone, jumped between,
+//! and played back (like a reconstruction of someone's solve).
+//! The browser UI and the `twisty replay` command both use this.
+
+use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+
+/// The turns made from a starting state, and how far through them the puzzle is.
+/// Undoing steps back through the turns without forgetting them, so they can be redone,
+/// until a different turn is made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveHistory {
+    turns: Vec<usize>,
+    // The state after each number of turns, starting with the initial state,
+    // so there is always one more state than there are turns
+    states: Vec<PuzzleState>,
+    position: usize,
+}
+
+impl MoveHistory {
+    pub fn new(initial_state: PuzzleState) -> Self {
+        Self {
+            turns: vec![],
+            states: vec![initial_state],
+            position: 0,
+        }
+    }
+
+    /// A history where the turns have all been made
+    pub fn from_turns(puzzle: &TwistyPuzzle, initial_state: PuzzleState, turns: &[usize]) -> Self {
+        let mut history = Self::new(initial_state);
+        for &turn_index in turns {
+            history.push(puzzle, turn_index);
+        }
+        history
+    }
+
+    /// A scramble from the solved state followed by its solution,
+    /// left just after the scramble so that the solution can be played back
+    pub fn reconstruction(puzzle: &TwistyPuzzle, scramble: &[usize], solution: &[usize]) -> Self {
+        let turns: Vec<usize> = scramble.iter().chain(solution).copied().collect();
+        let mut history = Self::from_turns(puzzle, puzzle.get_initial_state(), &turns);
+        history.jump_to(scramble.len());
+        history
+    }
+
+    /// Makes a turn, forgetting any turns that were undone
+    pub fn push(&mut self, puzzle: &TwistyPuzzle, turn_index: usize) {
+        self.turns.truncate(self.position);
+        self.states.truncate(self.position + 1);
+        let state = puzzle.get_derived_state_turn_index(self.state(), turn_index);
+        self.turns.push(turn_index);
+        self.states.push(state);
+        self.position += 1;
+    }
+
+    /// Steps back, returning the turn that was undone (so its inverse can be shown)
+    pub fn undo(&mut self) -> Option<usize> {
+        if self.position == 0 {
+            return None;
+        }
+        self.position -= 1;
+        Some(self.turns[self.position])
+    }
+
+    /// Steps forward, returning the turn that was redone
+    pub fn redo(&mut self) -> Option<usize> {
+        let turn_index = *self.turns.get(self.position)?;
+        self.position += 1;
+        Some(turn_index)
+    }
+
+    /// Goes to the state after this many turns (or after the last turn, if there aren't that many)
+    pub fn jump_to(&mut self, position: usize) -> &PuzzleState {
+        self.position = position.min(self.turns.len());
+        self.state()
+    }
+
+    /// The state after the turns that have been made
+    pub fn state(&self) -> &PuzzleState {
+        &self.states[self.position]
+    }
+
+    pub fn initial_state(&self) -> &PuzzleState {
+        &self.states[0]
+    }
+
+    /// How many of the turns have been made
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Every turn, including the ones that were undone
+    pub fn turns(&self) -> &[usize] {
+        &self.turns
+    }
+
+    pub fn len(&self) -> usize {
+        self.turns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+}
+
+/// Plays the turns of a history that haven't been made yet, at a steady number of turns per second
+#[derive(Debug, Clone)]
+pub struct Playback {
+    is_playing: bool,
+    turns_per_second: f64,
+    // Time that has passed since the last turn was played
+    elapsed_seconds: f64,
+}
+
+impl Playback {
+    pub fn new(turns_per_second: f64) -> Self {
+        Self {
+            is_playing: false,
+            turns_per_second,
+            elapsed_seconds: 0.0,
+        }
+    }
+
+    pub fn play(&mut self) {
+        self.is_playing = true;
+        self.elapsed_seconds = 0.0;
+    }
+
+    pub fn pause(&mut self) {
+        self.is_playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.is_playing
+    }
+
+    pub fn turns_per_second(&self) -> f64 {
+        self.turns_per_second
+    }
+
+    pub fn set_turns_per_second(&mut self, turns_per_second: f64) {
+        self.turns_per_second = turns_per_second;
+    }
+
+    /// Makes the turns that are due after `seconds` more have passed, and returns them.
+    /// Stops playing at the end of the history.
+    pub fn advance(&mut self, history: &mut MoveHistory, seconds: f64) -> Vec<usize> {
+        if !self.is_playing {
+            return vec![];
+        }
+        self.elapsed_seconds += seconds;
+        let seconds_per_turn = 1.0 / self.turns_per_second;
+        let mut turns = vec![];
+        while self.elapsed_seconds >= seconds_per_turn {
+            self.elapsed_seconds -= seconds_per_turn;
+            match history.redo() {
+                Some(turn_index) => turns.push(turn_index),
+                None => break,
+            }
+        }
+        if history.position() == history.len() {
+            self.pause();
+        }
+        turns
+    }
+
+    /// Pauses, and makes the next turn
+    pub fn step_forward(&mut self, history: &mut MoveHistory) -> Option<usize> {
+        self.pause();
+        history.redo()
+    }
+
+    /// Pauses, and undoes the last turn
+    pub fn step_back(&mut self, history: &mut MoveHistory) -> Option<usize> {
+        self.pause();
+        history.undo()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles;
+
+    #[test]
+    fn test_undo_redo() {
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let initial_state = puzzle.get_initial_state();
+        let mut history = MoveHistory::new(initial_state.clone());
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), None);
+
+        history.push(&puzzle, 0);
+        history.push(&puzzle, 2);
+        let after_both = history.state().clone();
+        assert_eq!(
+            after_both,
+            puzzle.get_derived_state_from_turn_sequence(&initial_state, [0, 2].into_iter())
+        );
+
+        assert_eq!(history.undo(), Some(2));
+        assert_eq!(history.undo(), Some(0));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.state(), &initial_state);
+        assert_eq!(history.redo(), Some(0));
+        assert_eq!(history.redo(), Some(2));
+        assert_eq!(history.state(), &after_both);
+        assert_eq!(history.redo(), None);
+
+        // A new turn replaces the ones that were undone
+        history.undo();
+        history.push(&puzzle, 4);
+        assert_eq!(history.turns(), &[0, 4]);
+        assert_eq!(history.redo(), None);
+
+        assert_eq!(history.jump_to(0), &initial_state);
+        let last_state = history.states.last().unwrap().clone();
+        assert_eq!(history.jump_to(10), &last_state);
+        assert_eq!(history.position(), 2);
+    }
+
+    #[test]
+    fn test_playback() {
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let scramble = [0, 2, 4];
+        let solution = [5, 3, 1];
+        let mut history = MoveHistory::reconstruction(&puzzle, &scramble, &solution);
+        assert_eq!(history.position(), 3);
+        assert!(!puzzle.is_solved(history.state()));
+
+        let mut playback = Playback::new(2.0);
+        assert_eq!(playback.advance(&mut history, 10.0), vec![]);
+        playback.play();
+        assert_eq!(playback.advance(&mut history, 0.25), vec![]);
+        assert_eq!(playback.advance(&mut history, 0.25), vec![5]);
+        assert_eq!(playback.advance(&mut history, 1.0), vec![3, 1]);
+        assert!(!playback.is_playing());
+        assert!(puzzle.is_solved(history.state()));
+
+        assert_eq!(playback.step_back(&mut history), Some(1));
+        assert_eq!(playback.step_forward(&mut history), Some(1));
+        assert_eq!(playback.step_forward(&mut history), None);
+        history.jump_to(0);
+        assert_eq!(history.state(), &puzzle.get_initial_state());
+    }
+}
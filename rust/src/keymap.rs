@@ -0,0 +1,472 @@
+//! Turning the puzzle from the keyboard.
+//! This decides what each key does, and the `web` module passes it the browser's key events.
+//!
+//! Bindings are written one per line, as `key = action`, for example:
+//!
+//! ```text
+//! # Comments start with #
+//! i = R
+//! shift+i = R'
+//! ctrl+i = rotate R
+//! ctrl+z = undo
+//! k = none
+//! ```
+//!
+//! `rotate` turns the whole puzzle the way that the turn goes, `undo` and `redo` step through
+//! the move history, and `none` removes a binding.
+//!
+//! Each puzzle has its own turns, so the bindings that override its defaults are saved to a
+//! `Storage` under the puzzle's name.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::timer::Storage;
+use crate::twisty_puzzle::TwistyPuzzle;
+
+// The keys that each puzzle's overrides are saved under start with this, followed by its name
+const OVERRIDES_KEY_PREFIX: &str = "twisty-puzzles.keymap.";
+
+/// The layout of the cube on established web simulators, which puts each turn and its inverse
+/// on matching fingers of either hand. Turns that the puzzle doesn't have are left out.
+const CUBE_BINDINGS: &[(&str, &str)] = &[
+    ("i", "R"),
+    ("k", "R'"),
+    ("d", "L"),
+    ("e", "L'"),
+    ("j", "U"),
+    ("f", "U'"),
+    ("s", "D"),
+    ("l", "D'"),
+    ("h", "F"),
+    ("g", "F'"),
+    ("w", "B"),
+    ("o", "B'"),
+    ("t", "rotate R"),
+    ("y", "rotate R"),
+    ("b", "rotate R'"),
+    ("n", "rotate R'"),
+    (";", "rotate U"),
+    ("a", "rotate U'"),
+    ("p", "rotate F"),
+    ("q", "rotate F'"),
+];
+
+/// Bound after the turns, so these win if a turn was given the same key
+const HISTORY_BINDINGS: &[(&str, KeyAction)] = &[
+    ("ctrl+z", KeyAction::Undo),
+    ("ctrl+y", KeyAction::Redo),
+    ("ctrl+shift+z", KeyAction::Redo),
+];
+
+// The keys that turns are given when their name isn't free, in the order they are used
+const SPARE_KEYS: &str = "abcdefghijklmnopqrstuvwxyz1234567890";
+
+/// A key, along with the modifier keys that were held down with it
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyPress {
+    /// The key's name (the `key` of a browser key event), in lowercase
+    pub key: String,
+    pub shift: bool,
+    pub ctrl: bool,
+}
+
+impl KeyPress {
+    pub fn new(key: &str, shift: bool, ctrl: bool) -> Self {
+        let key = match key {
+            " " => "space".to_string(),
+            key => key.to_lowercase(),
+        };
+        Self { key, shift, ctrl }
+    }
+
+    /// Reads a key written like `ctrl+shift+k`
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = text.trim().split('+').collect();
+        let key = parts.pop()?;
+        if key.is_empty() {
+            return None;
+        }
+        let mut key_press = Self::new(key, false, false);
+        for modifier in parts {
+            match modifier.trim().to_lowercase().as_str() {
+                "shift" => key_press.shift = true,
+                "ctrl" => key_press.ctrl = true,
+                _ => return None,
+            }
+        }
+        Some(key_press)
+    }
+}
+
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "shift+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// What pressing a key does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Makes the turn with this index
+    Turn(usize),
+    /// Rotates the whole puzzle the way that the turn with this index goes,
+    /// which only changes how it is seen
+    Rotate(usize),
+    /// Undoes the last turn in the move history
+    Undo,
+    /// Makes the next turn in the move history again, after it was undone
+    Redo,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyMap {
+    bindings: BTreeMap<KeyPress, KeyAction>,
+}
+
+impl KeyMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bindings for every turn of the puzzle.
+    /// Puzzles with the cube's turns get the cube's usual layout.
+    /// Every other turn gets the first letter of its name if that is free (or else a spare key),
+    /// with shift for the inverse turn and ctrl to rotate the whole puzzle.
+    /// Undo and redo are on ctrl+z and ctrl+y (or ctrl+shift+z).
+    pub fn default_for(puzzle: &TwistyPuzzle) -> Self {
+        let mut keymap = Self::new();
+        let mut bound_turns = vec![false; puzzle.turn_names.len()];
+        // Puzzles with inferred names can have some of the same letters, for different turns
+        let is_cube_like = ["U", "R", "F"]
+            .iter()
+            .all(|name| puzzle.turn_names.iter().any(|turn_name| turn_name == name));
+        for (key, action) in CUBE_BINDINGS.iter().filter(|_| is_cube_like) {
+            if let Ok(action) = parse_action(puzzle, action) {
+                if let KeyAction::Turn(turn_index) = action {
+                    bound_turns[turn_index] = true;
+                }
+                keymap.bind(KeyPress::new(key, false, false), action);
+            }
+        }
+
+        // Each turn is followed by its inverse, which goes on the same key
+        for turn_index in (0..puzzle.turn_names.len()).step_by(2) {
+            let inverse = puzzle.inverted_turn_index(turn_index);
+            if bound_turns[turn_index] || bound_turns[inverse] {
+                continue;
+            }
+            let is_free = |key: &str| keymap.get(&KeyPress::new(key, false, false)).is_none();
+            let first_letter = puzzle.turn_names[turn_index]
+                .chars()
+                .next()
+                .map(|letter| letter.to_lowercase().to_string());
+            let key = match first_letter {
+                Some(letter) if is_free(&letter) => letter,
+                _ => match SPARE_KEYS
+                    .chars()
+                    .map(|key| key.to_string())
+                    .find(|key| is_free(key))
+                {
+                    Some(key) => key,
+                    // Out of keys, so the rest of the turns only have buttons
+                    None => break,
+                },
+            };
+            keymap.bind(
+                KeyPress::new(&key, false, false),
+                KeyAction::Turn(turn_index),
+            );
+            keymap.bind(KeyPress::new(&key, true, false), KeyAction::Turn(inverse));
+            keymap.bind(
+                KeyPress::new(&key, false, true),
+                KeyAction::Rotate(turn_index),
+            );
+            keymap.bind(KeyPress::new(&key, true, true), KeyAction::Rotate(inverse));
+        }
+        for (key, action) in HISTORY_BINDINGS {
+            keymap.bind(KeyPress::parse(key).unwrap(), *action);
+        }
+        keymap
+    }
+
+    /// Binds the key, returning what it did before
+    pub fn bind(&mut self, key_press: KeyPress, action: KeyAction) -> Option<KeyAction> {
+        self.bindings.insert(key_press, action)
+    }
+
+    /// Removes the key's binding, returning what it did
+    pub fn unbind(&mut self, key_press: &KeyPress) -> Option<KeyAction> {
+        self.bindings.remove(key_press)
+    }
+
+    pub fn get(&self, key_press: &KeyPress) -> Option<KeyAction> {
+        self.bindings.get(key_press).copied()
+    }
+
+    /// Changes the bindings that are written in the text (in the format described at the top of
+    /// this module), and leaves the rest alone.
+    /// Nothing is changed if any line can't be read.
+    pub fn apply_overrides(&mut self, puzzle: &TwistyPuzzle, text: &str) -> Result<()> {
+        let mut overrides = vec![];
+        for (line_index, line) in text.lines().enumerate() {
+            let invalid = |message: String| Error::InvalidKeyBinding {
+                line: line_index + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, action) = line
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected key = action, found {}", line)))?;
+            let key_press = KeyPress::parse(key)
+                .ok_or_else(|| invalid(format!("unknown key {}", key.trim())))?;
+            let action = match action.trim() {
+                "none" => None,
+                action => Some(parse_action(puzzle, action).map_err(invalid)?),
+            };
+            overrides.push((key_press, action));
+        }
+        for (key_press, action) in overrides {
+            match action {
+                Some(action) => self.bind(key_press, action),
+                None => self.unbind(&key_press),
+            };
+        }
+        Ok(())
+    }
+
+    /// The puzzle's default bindings, with the overrides that were saved for it applied
+    pub fn load(storage: &dyn Storage, puzzle_name: &str, puzzle: &TwistyPuzzle) -> Result<Self> {
+        let mut keymap = Self::default_for(puzzle);
+        if let Some(text) = storage.get(&format!("{}{}", OVERRIDES_KEY_PREFIX, puzzle_name))? {
+            keymap.apply_overrides(puzzle, &text)?;
+        }
+        Ok(keymap)
+    }
+
+    /// Saves the overrides for the puzzle, so that `load` applies them from now on
+    pub fn save_overrides(storage: &mut dyn Storage, puzzle_name: &str, text: &str) -> Result<()> {
+        storage.set(&format!("{}{}", OVERRIDES_KEY_PREFIX, puzzle_name), text)
+    }
+
+    /// Writes every binding, in the format that `apply_overrides` reads
+    pub fn to_text(&self, puzzle: &TwistyPuzzle) -> String {
+        self.bindings
+            .iter()
+            .map(|(key_press, action)| {
+                let action = match *action {
+                    KeyAction::Turn(turn_index) => puzzle.turn_names[turn_index].clone(),
+                    KeyAction::Rotate(turn_index) => {
+                        format!("rotate {}", puzzle.turn_names[turn_index])
+                    }
+                    KeyAction::Undo => "undo".to_string(),
+                    KeyAction::Redo => "redo".to_string(),
+                };
+                format!("{} = {}\n", key_press, action)
+            })
+            .collect()
+    }
+}
+
+/// Reads an action like `R'`, `rotate R'` or `undo`.
+/// Turn names aren't always unique, so like elsewhere, the first turn with the name is used.
+fn parse_action(puzzle: &TwistyPuzzle, text: &str) -> std::result::Result<KeyAction, String> {
+    match text {
+        "undo" => return Ok(KeyAction::Undo),
+        "redo" => return Ok(KeyAction::Redo),
+        _ => {}
+    }
+    let (turn_name, is_rotation) = match text.strip_prefix("rotate ") {
+        Some(turn_name) => (turn_name.trim(), true),
+        None => (text, false),
+    };
+    let turn_index = puzzle
+        .turn_names
+        .iter()
+        .position(|name| name == turn_name)
+        .ok_or_else(|| {
+            format!(
+                "unknown turn {} (expected one of {})",
+                turn_name,
+                puzzle.turn_names.join(" ")
+            )
+        })?;
+    Ok(if is_rotation {
+        KeyAction::Rotate(turn_index)
+    } else {
+        KeyAction::Turn(turn_index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles;
+    use crate::timer::MemoryStorage;
+
+    fn turn(puzzle: &TwistyPuzzle, name: &str) -> usize {
+        puzzle.turn_names.iter().position(|n| n == name).unwrap()
+    }
+
+    #[test]
+    fn test_key_press() {
+        let key_press = KeyPress::parse("ctrl+shift+K").unwrap();
+        assert_eq!(key_press, KeyPress::new("k", true, true));
+        assert_eq!(key_press.to_string(), "ctrl+shift+k");
+        assert_eq!(KeyPress::parse(";"), Some(KeyPress::new(";", false, false)));
+        assert_eq!(KeyPress::new(" ", false, false).to_string(), "space");
+        assert_eq!(KeyPress::parse("alt+k"), None);
+        assert_eq!(KeyPress::parse("shift+"), None);
+    }
+
+    #[test]
+    fn test_default_cube_keymap() {
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let keymap = KeyMap::default_for(&puzzle);
+        let get = |key| keymap.get(&KeyPress::parse(key).unwrap());
+        assert_eq!(get("i"), Some(KeyAction::Turn(turn(&puzzle, "R"))));
+        assert_eq!(get("k"), Some(KeyAction::Turn(turn(&puzzle, "R'"))));
+        assert_eq!(get(";"), Some(KeyAction::Rotate(turn(&puzzle, "U"))));
+        assert_eq!(get("shift+i"), None);
+        assert_eq!(get("ctrl+z"), Some(KeyAction::Undo));
+        assert_eq!(get("ctrl+shift+z"), Some(KeyAction::Redo));
+        // Every turn has a key
+        for turn_index in 0..puzzle.turn_names.len() {
+            assert!(keymap
+                .bindings
+                .values()
+                .any(|&action| action == KeyAction::Turn(turn_index)));
+        }
+
+        // The 2x2 only has some of the cube's turns
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let keymap = KeyMap::default_for(&puzzle);
+        assert_eq!(
+            keymap.get(&KeyPress::new("i", false, false)),
+            Some(KeyAction::Turn(turn(&puzzle, "R")))
+        );
+        assert_eq!(keymap.get(&KeyPress::new("d", false, false)), None);
+    }
+
+    #[test]
+    fn test_default_keymap() {
+        // The megaminx's turns are named A to L
+        let puzzle = puzzles::megaminx();
+        let keymap = KeyMap::default_for(&puzzle);
+        let get = |key| keymap.get(&KeyPress::parse(key).unwrap());
+        assert_eq!(get("a"), Some(KeyAction::Turn(turn(&puzzle, "A"))));
+        assert_eq!(get("shift+a"), Some(KeyAction::Turn(turn(&puzzle, "A'"))));
+        assert_eq!(get("ctrl+l"), Some(KeyAction::Rotate(turn(&puzzle, "L"))));
+        assert_eq!(
+            get("ctrl+shift+l"),
+            Some(KeyAction::Rotate(turn(&puzzle, "L'")))
+        );
+
+        // The 4x4's slices are named A to C, but A is already used to rotate like the cube
+        let puzzle = puzzles::rubiks_cube_4x4();
+        let keymap = KeyMap::default_for(&puzzle);
+        for turn_index in 0..puzzle.turn_names.len() {
+            assert!(keymap
+                .bindings
+                .values()
+                .any(|&action| action == KeyAction::Turn(turn_index)));
+        }
+        assert_eq!(
+            keymap.get(&KeyPress::new("a", false, false)),
+            Some(KeyAction::Rotate(turn(&puzzle, "U'")))
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let mut keymap = KeyMap::default_for(&puzzle);
+        keymap
+            .apply_overrides(
+                &puzzle,
+                "# Swap R and R'\ni = R'\nk = R  # trailing comment\n\nshift+x = rotate D\nj = none\nctrl+u = undo\n",
+            )
+            .unwrap();
+        let get = |keymap: &KeyMap, key| keymap.get(&KeyPress::parse(key).unwrap());
+        assert_eq!(
+            get(&keymap, "i"),
+            Some(KeyAction::Turn(turn(&puzzle, "R'")))
+        );
+        assert_eq!(get(&keymap, "k"), Some(KeyAction::Turn(turn(&puzzle, "R"))));
+        assert_eq!(
+            get(&keymap, "shift+x"),
+            Some(KeyAction::Rotate(turn(&puzzle, "D")))
+        );
+        assert_eq!(get(&keymap, "j"), None);
+        assert_eq!(get(&keymap, "ctrl+u"), Some(KeyAction::Undo));
+        assert_eq!(
+            get(&keymap, "f"),
+            Some(KeyAction::Turn(turn(&puzzle, "U'")))
+        );
+
+        // A bad line leaves everything as it was
+        let before = keymap.clone();
+        assert_eq!(
+            keymap.apply_overrides(&puzzle, "i = R\nk = X"),
+            Err(Error::InvalidKeyBinding {
+                line: 2,
+                message: format!(
+                    "unknown turn X (expected one of {})",
+                    puzzle.turn_names.join(" ")
+                ),
+            })
+        );
+        assert!(matches!(
+            keymap.apply_overrides(&puzzle, "i R"),
+            Err(Error::InvalidKeyBinding { line: 1, .. })
+        ));
+        assert_eq!(keymap, before);
+
+        // Writing the bindings out and reading them back gives the same bindings
+        let mut read_back = KeyMap::new();
+        read_back
+            .apply_overrides(&puzzle, &keymap.to_text(&puzzle))
+            .unwrap();
+        assert_eq!(read_back, keymap);
+    }
+
+    #[test]
+    fn test_saved_overrides() {
+        let cube = puzzles::rubiks_cube_3x3();
+        let megaminx = puzzles::megaminx();
+        let mut storage = MemoryStorage::new();
+        assert_eq!(
+            KeyMap::load(&storage, "3x3", &cube).unwrap(),
+            KeyMap::default_for(&cube)
+        );
+
+        let overrides = "i = R'\nj = none\n";
+        let mut keymap = KeyMap::default_for(&cube);
+        keymap.apply_overrides(&cube, overrides).unwrap();
+        KeyMap::save_overrides(&mut storage, "3x3", overrides).unwrap();
+
+        // Switching to another puzzle doesn't bring the overrides along,
+        // and switching back brings them back
+        assert_eq!(
+            KeyMap::load(&storage, "megaminx", &megaminx).unwrap(),
+            KeyMap::default_for(&megaminx)
+        );
+        assert_eq!(KeyMap::load(&storage, "3x3", &cube).unwrap(), keymap);
+
+        KeyMap::save_overrides(&mut storage, "3x3", "i = X").unwrap();
+        assert!(matches!(
+            KeyMap::load(&storage, "3x3", &cube),
+            Err(Error::InvalidKeyBinding { line: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,594 @@
+//! Solid models of the pieces, for looking at in 3D modeling software and for 3D printing.
+//!
+//! The stickers only cover the outside of the puzzle, so the pieces' insides are made separately:
+//! each piece is the part of the polyhedron that is on the piece's side of every cut,
+//! so its mesh is the polyhedron clipped by one half-space per cut, with the hole that each cut
+//! leaves closed by a face on the cut. The polyhedron and the half-spaces are convex,
+//! so the pieces are too, which is what keeps the clipping simple.
+//!
+//! Models are written as OBJ and glTF, which keep the stickers' colors,
+//! and as STL, which is only the shape (which is all that slicers for 3D printers need).
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::error::{Error, Result};
+use crate::plane::Plane;
+use crate::polyhedron::Face;
+use crate::render::{rgb, ColorScheme, INTERIOR_COLOR};
+use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+use crate::vector3d::Vector3D;
+
+// Vertices closer to a cut than this are on the cut
+const TOLERANCE: f64 = 1e-9;
+// Decimal places of the numbers in the text formats, which is far finer than printers can print
+const DECIMAL_PLACES: i32 = 6;
+
+/// A face of a piece's mesh
+#[derive(Debug, Clone)]
+pub struct MeshFace {
+    /// Counter-clockwise as seen from outside the piece
+    pub face: Face,
+    /// The color of the sticker on this face, or None if the face is inside the puzzle
+    pub color_index: Option<usize>,
+}
+
+/// The closed surface of one piece
+#[derive(Debug, Clone)]
+pub struct PieceMesh {
+    pub faces: Vec<MeshFace>,
+}
+
+impl PieceMesh {
+    /// Makes the piece bigger or smaller, about the center of the puzzle
+    pub fn scale(&mut self, factor: f64) {
+        for vertex in self
+            .faces
+            .iter_mut()
+            .flat_map(|mesh_face| &mut mesh_face.face.vertices)
+        {
+            *vertex = &*vertex * factor;
+        }
+    }
+}
+
+/// The mesh of each piece, colored like the state. The pieces are in order of their face indexes,
+/// so that the same puzzle always makes the same model. Both sides of every cut are moved
+/// `gap / 2` into the pieces next to it, so that there is space between the pieces,
+/// which printed pieces need to be able to turn.
+/// Fails if the gap is so wide that nothing is left of one of the pieces.
+pub fn piece_meshes(
+    puzzle: &TwistyPuzzle,
+    puzzle_state: &PuzzleState,
+    gap: f64,
+) -> Result<Vec<PieceMesh>> {
+    let cut_planes = puzzle.cut_planes();
+    let stickers = puzzle.faces(puzzle_state);
+    let polyhedron_faces = &puzzle.polyhedron.faces;
+    let mut pieces: Vec<&Vec<usize>> = puzzle.pieces().iter().collect();
+    pieces.sort_unstable();
+    pieces
+        .into_iter()
+        .map(|piece| {
+            // Every face of a piece is turned by the same turns,
+            // which are the turns of the cuts that the piece is on the turning side of
+            let piece_face = &puzzle.faces[piece[0]];
+            let mut polygons: Vec<(Face, Option<usize>)> = polyhedron_faces
+                .iter()
+                .cloned()
+                .enumerate()
+                .map(|(polyhedron_face_index, face)| (face, Some(polyhedron_face_index)))
+                .collect();
+            for (turn_index, cut_plane) in &cut_planes {
+                // Facing away from the piece, so that clipping keeps the piece's side
+                let normal = if piece_face.is_turned_by(*turn_index) {
+                    -1.0 * cut_plane.normal
+                } else {
+                    cut_plane.normal
+                };
+                let boundary = Plane {
+                    normal,
+                    ..*cut_plane
+                }
+                .offset(-gap / 2.0);
+                polygons = clip_solid(polygons, &boundary);
+            }
+            if polygons.is_empty() {
+                return Err(Error::InvalidGeometry(format!(
+                    "a gap of {} leaves nothing of the piece with face {}",
+                    gap, piece[0]
+                )));
+            }
+            let faces = polygons
+                .into_iter()
+                .map(|(face, polyhedron_face_index)| {
+                    // The color of the piece's sticker on the same side of the polyhedron
+                    let color_index = polyhedron_face_index.and_then(|polyhedron_face_index| {
+                        let normal = polyhedron_faces[polyhedron_face_index]
+                            .normal()
+                            .to_unit_vector();
+                        piece
+                            .iter()
+                            .find(|&&face_index| {
+                                puzzle.faces[face_index]
+                                    .face
+                                    .normal()
+                                    .to_unit_vector()
+                                    .approx_equals(&normal)
+                            })
+                            .map(|&face_index| stickers[face_index].color_index)
+                    });
+                    MeshFace { face, color_index }
+                })
+                .collect();
+            Ok(PieceMesh { faces })
+        })
+        .collect()
+}
+
+/// Keeps the part of the convex solid with these faces that is on the opposite side of the plane
+/// to its normal, and closes the hole that leaves with a face on the plane (which isn't on the
+/// polyhedron, so it has no polyhedron face index)
+fn clip_solid(polygons: Vec<(Face, Option<usize>)>, plane: &Plane) -> Vec<(Face, Option<usize>)> {
+    let vertices = || polygons.iter().flat_map(|(face, _)| &face.vertices);
+    if vertices().all(|vertex| plane.distance_to(vertex) <= TOLERANCE) {
+        return polygons;
+    }
+    if vertices().all(|vertex| plane.distance_to(vertex) >= -TOLERANCE) {
+        return vec![];
+    }
+
+    let mut cap_vertices: Vec<Vector3D> = vec![];
+    let mut add_cap_vertex = |vertex: Vector3D| {
+        if !cap_vertices
+            .iter()
+            .any(|other| other.approx_equals(&vertex))
+        {
+            cap_vertices.push(vertex);
+        }
+    };
+    let mut clipped = vec![];
+    for (face, polyhedron_face_index) in polygons {
+        let distances: Vec<f64> = face
+            .vertices
+            .iter()
+            .map(|vertex| plane.distance_to(vertex))
+            .collect();
+        let mut vertices = vec![];
+        for i in 0..face.vertices.len() {
+            let j = (i + 1) % face.vertices.len();
+            let (vertex_a, vertex_b) = (face.vertices[i], face.vertices[j]);
+            let (distance_a, distance_b) = (distances[i], distances[j]);
+            if distance_a <= TOLERANCE {
+                vertices.push(vertex_a);
+                if distance_a >= -TOLERANCE {
+                    add_cap_vertex(vertex_a);
+                }
+            }
+            if distance_a.abs() > TOLERANCE
+                && distance_b.abs() > TOLERANCE
+                && distance_a.signum() != distance_b.signum()
+            {
+                // This edge crosses the plane. The crossing is worked out from the inside end,
+                // so that the faces on both sides of the edge get exactly the same point.
+                let ((inside, inside_distance), (outside, outside_distance)) =
+                    if distance_a < distance_b {
+                        ((vertex_a, distance_a), (vertex_b, distance_b))
+                    } else {
+                        ((vertex_b, distance_b), (vertex_a, distance_a))
+                    };
+                let fraction = inside_distance / (inside_distance - outside_distance);
+                let crossing = &inside + &(&(outside - &inside) * fraction);
+                vertices.push(crossing);
+                add_cap_vertex(crossing);
+            }
+        }
+        if vertices.len() > 2 {
+            clipped.push((Face { vertices }, polyhedron_face_index));
+        }
+    }
+
+    if cap_vertices.len() > 2 {
+        // Counter-clockwise around the middle, as seen from the side the normal points to
+        let center = Vector3D::from_average(&cap_vertices);
+        let u = (cap_vertices[0] - &center).to_unit_vector();
+        let v = plane.normal.to_unit_vector().cross(&u);
+        let angle = |vertex: &Vector3D| {
+            let offset = vertex - center;
+            offset.dot(&v).atan2(offset.dot(&u))
+        };
+        cap_vertices.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+        clipped.push((
+            Face {
+                vertices: cap_vertices,
+            },
+            None,
+        ));
+    }
+    clipped
+}
+
+/// Splits the (convex) face into triangles that all share its first vertex
+fn triangles(face: &Face) -> impl Iterator<Item = [&Vector3D; 3]> + '_ {
+    face.vertices[1..]
+        .windows(2)
+        .map(|pair| [&face.vertices[0], &pair[0], &pair[1]])
+}
+
+/// Rounds off the floating-point noise, and never writes -0
+fn format_number(number: f64) -> String {
+    let factor = 10f64.powi(DECIMAL_PLACES);
+    format!("{}", (number * factor).round() / factor + 0.0)
+}
+
+fn format_vector(vector: &Vector3D) -> String {
+    format!(
+        "{} {} {}",
+        format_number(vector.x),
+        format_number(vector.y),
+        format_number(vector.z)
+    )
+}
+
+fn face_color<'a>(mesh_face: &MeshFace, color_scheme: &'a ColorScheme) -> &'a str {
+    match mesh_face.color_index {
+        Some(color_index) => color_scheme.color(color_index),
+        None => INTERIOR_COLOR,
+    }
+}
+
+/// A Wavefront OBJ model with an object for each piece.
+/// The colors are written after the vertices, which most programs that read OBJ understand.
+pub fn to_obj(meshes: &[PieceMesh], color_scheme: &ColorScheme) -> String {
+    let mut obj = String::new();
+    let mut num_vertices = 0;
+    for (piece_index, mesh) in meshes.iter().enumerate() {
+        writeln!(obj, "o piece_{}", piece_index + 1).unwrap();
+        for mesh_face in &mesh.faces {
+            let [r, g, b] = rgb(face_color(mesh_face, color_scheme));
+            // Each face has its own vertices, so that they can have the face's color
+            for vertex in &mesh_face.face.vertices {
+                writeln!(
+                    obj,
+                    "v {} {} {} {}",
+                    format_vector(vertex),
+                    format_number(r),
+                    format_number(g),
+                    format_number(b)
+                )
+                .unwrap();
+            }
+            let indices: Vec<String> = (1..=mesh_face.face.vertices.len())
+                .map(|i| (num_vertices + i).to_string())
+                .collect();
+            writeln!(obj, "f {}", indices.join(" ")).unwrap();
+            num_vertices += mesh_face.face.vertices.len();
+        }
+    }
+    obj
+}
+
+/// An ASCII STL model of all the pieces, for 3D printing
+pub fn to_stl(meshes: &[PieceMesh]) -> String {
+    let mut stl = String::from("solid twisty_puzzle\n");
+    for mesh_face in meshes.iter().flat_map(|mesh| &mesh.faces) {
+        let normal = mesh_face.face.normal().to_unit_vector();
+        for triangle in triangles(&mesh_face.face) {
+            writeln!(stl, "  facet normal {}", format_vector(&normal)).unwrap();
+            stl.push_str("    outer loop\n");
+            for vertex in triangle {
+                writeln!(stl, "      vertex {}", format_vector(vertex)).unwrap();
+            }
+            stl.push_str("    endloop\n");
+            stl.push_str("  endfacet\n");
+        }
+    }
+    stl.push_str("endsolid twisty_puzzle\n");
+    stl
+}
+
+/// A glTF model (as JSON, with the binary data embedded in it) with a node for each piece,
+/// so the pieces can be moved around separately. Each color is a material.
+pub fn to_gltf(meshes: &[PieceMesh], color_scheme: &ColorScheme) -> String {
+    let mut buffer: Vec<u8> = vec![];
+    let mut buffer_views: Vec<String> = vec![];
+    let mut accessors: Vec<String> = vec![];
+    let mut materials: Vec<String> = vec![];
+    let mut material_indices: HashMap<&str, usize> = HashMap::new();
+    let mut gltf_meshes: Vec<String> = vec![];
+
+    // Adds a list of 3D vectors to the buffer, and returns the index of the accessor for them
+    let mut add_vectors = |vectors: &[[f32; 3]], with_bounds: bool| {
+        let byte_offset = buffer.len();
+        for value in vectors.iter().flatten() {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":34962}}"#,
+            byte_offset,
+            buffer.len() - byte_offset
+        ));
+        // Positions have to say how far they go in each direction
+        let bounds = if with_bounds {
+            let bound = |fold: fn(f32, f32) -> f32| {
+                (0..3)
+                    .map(|axis| {
+                        let value = vectors.iter().map(|vector| vector[axis]).reduce(fold);
+                        format!("{:?}", value.unwrap_or(0.0))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+            format!(
+                r#","min":[{}],"max":[{}]"#,
+                bound(f32::min),
+                bound(f32::max)
+            )
+        } else {
+            String::new()
+        };
+        accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":5126,"count":{},"type":"VEC3"{}}}"#,
+            buffer_views.len() - 1,
+            vectors.len(),
+            bounds
+        ));
+        accessors.len() - 1
+    };
+
+    for mesh in meshes {
+        // One primitive for the faces of each color, as each primitive has one material
+        let mut faces_by_material: Vec<(usize, Vec<&MeshFace>)> = vec![];
+        for mesh_face in &mesh.faces {
+            let color = face_color(mesh_face, color_scheme);
+            let material_index = *material_indices.entry(color).or_insert_with(|| {
+                // glTF colors are linear, rather than sRGB like the color scheme's
+                let [r, g, b] = rgb(color).map(|channel| {
+                    if channel <= 0.04045 {
+                        channel / 12.92
+                    } else {
+                        ((channel + 0.055) / 1.055).powf(2.4)
+                    }
+                });
+                // Matte plastic, like stickers
+                let base_color = [r, g, b, 1.0].map(format_number).join(",");
+                materials.push(format!(
+                    concat!(
+                        r#"{{"name":"{}","pbrMetallicRoughness":{{"baseColorFactor":[{}],"#,
+                        r#""metallicFactor":0,"roughnessFactor":0.6}}}}"#
+                    ),
+                    color, base_color
+                ));
+                materials.len() - 1
+            });
+            match faces_by_material
+                .iter_mut()
+                .find(|(index, _)| *index == material_index)
+            {
+                Some((_, faces)) => faces.push(mesh_face),
+                None => faces_by_material.push((material_index, vec![mesh_face])),
+            }
+        }
+        let primitives: Vec<String> = faces_by_material
+            .iter()
+            .map(|(material_index, faces)| {
+                let mut positions: Vec<[f32; 3]> = vec![];
+                let mut normals: Vec<[f32; 3]> = vec![];
+                for mesh_face in faces {
+                    let normal = mesh_face.face.normal().to_unit_vector();
+                    for vertex in triangles(&mesh_face.face).flatten() {
+                        positions.push([vertex.x as f32, vertex.y as f32, vertex.z as f32]);
+                        normals.push([normal.x as f32, normal.y as f32, normal.z as f32]);
+                    }
+                }
+                format!(
+                    r#"{{"attributes":{{"POSITION":{},"NORMAL":{}}},"material":{}}}"#,
+                    add_vectors(&positions, true),
+                    add_vectors(&normals, false),
+                    material_index
+                )
+            })
+            .collect();
+        gltf_meshes.push(format!(r#"{{"primitives":[{}]}}"#, primitives.join(",")));
+    }
+
+    let nodes: Vec<String> = (0..meshes.len())
+        .map(|i| format!(r#"{{"name":"piece_{}","mesh":{}}}"#, i + 1, i))
+        .collect();
+    let node_indices: Vec<String> = (0..meshes.len()).map(|i| i.to_string()).collect();
+    format!(
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"twisty-puzzles"}},"#,
+            r#""scene":0,"scenes":[{{"nodes":[{}]}}],"nodes":[{}],"meshes":[{}],"#,
+            r#""materials":[{}],"accessors":[{}],"bufferViews":[{}],"#,
+            r#""buffers":[{{"byteLength":{},"uri":"data:application/octet-stream;base64,{}"}}]}}"#,
+            "\n"
+        ),
+        node_indices.join(","),
+        nodes.join(","),
+        gltf_meshes.join(","),
+        materials.join(","),
+        accessors.join(","),
+        buffer_views.join(","),
+        buffer.len(),
+        base64(&buffer)
+    )
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let byte = |i: usize| *chunk.get(i).unwrap_or(&0) as u32;
+        let bits = (byte(0) << 16) | (byte(1) << 8) | byte(2);
+        // Each 3 bytes are 4 characters, padded with = at the end
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[((bits >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use super::*;
+    use crate::puzzles;
+
+    // By the divergence theorem, which only works if the mesh is closed and faces outwards
+    fn volume(mesh: &PieceMesh) -> f64 {
+        mesh.faces
+            .iter()
+            .flat_map(|mesh_face| triangles(&mesh_face.face))
+            .map(|[a, b, c]| a.dot(&b.cross(c)) / 6.0)
+            .sum()
+    }
+
+    // Whether every edge is on exactly two faces, which go along it in opposite directions
+    fn is_closed(mesh: &PieceMesh) -> bool {
+        let key = |vertex: &Vector3D| {
+            let round = |coordinate: f64| (coordinate * 1e6).round() as i64;
+            (round(vertex.x), round(vertex.y), round(vertex.z))
+        };
+        let mut edges: HashMap<_, i32> = HashMap::new();
+        for mesh_face in &mesh.faces {
+            for edge in mesh_face.face.edges_iter() {
+                *edges.entry((key(&edge.0), key(&edge.1))).or_default() += 1;
+            }
+        }
+        edges
+            .iter()
+            .all(|(&(a, b), &count)| count == 1 && edges.get(&(b, a)) == Some(&1))
+    }
+
+    #[test]
+    fn test_piece_meshes() {
+        for info in puzzles::ALL_PUZZLES {
+            let puzzle = (info.constructor)();
+            let meshes = piece_meshes(&puzzle, &puzzle.get_initial_state(), 0.0).unwrap();
+            assert_eq!(meshes.len(), puzzle.get_num_pieces(), "{}", info.name);
+            for mesh in &meshes {
+                assert!(is_closed(mesh), "a {} piece isn't closed", info.name);
+                assert!(volume(mesh) > 0.0, "a {} piece is inside out", info.name);
+                // Every piece has some of the outside of the puzzle
+                assert!(mesh.faces.iter().any(|face| face.color_index.is_some()));
+            }
+        }
+
+        // The pieces fill up the cube, apart from the 3x3's core (which isn't a piece).
+        // The 3x3's cuts are 0.33 in from the sides of a cube with sides of length 1.
+        let polyhedron_volume = |puzzle: &TwistyPuzzle| {
+            volume(&PieceMesh {
+                faces: puzzle
+                    .polyhedron
+                    .faces
+                    .iter()
+                    .map(|face| MeshFace {
+                        face: face.clone(),
+                        color_index: None,
+                    })
+                    .collect(),
+            })
+        };
+        for (puzzle, fraction) in [
+            (puzzles::rubiks_cube_2x2(), 1.0),
+            (puzzles::rubiks_cube_3x3(), 1.0 - 0.34f64.powi(3)),
+        ] {
+            let meshes = piece_meshes(&puzzle, &puzzle.get_initial_state(), 0.0).unwrap();
+            let total_volume: f64 = meshes.iter().map(volume).sum();
+            assert!((total_volume - fraction * polyhedron_volume(&puzzle)).abs() < 1e-9);
+        }
+
+        // Each side of each cut moves half the gap into the piece
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let gap = 0.01;
+        let meshes = piece_meshes(&puzzle, &puzzle.get_initial_state(), gap).unwrap();
+        let corner = meshes
+            .iter()
+            .find(|mesh| {
+                mesh.faces
+                    .iter()
+                    .filter(|f| f.color_index.is_some())
+                    .count()
+                    == 3
+            })
+            .unwrap();
+        let corner_size = 0.33 - gap / 2.0;
+        assert!((volume(corner) - corner_size.powi(3)).abs() < 1e-9);
+
+        // The 3x3's middle slices are only 0.34 wide, so a wider gap leaves nothing of them
+        assert!(matches!(
+            piece_meshes(&puzzle, &puzzle.get_initial_state(), 0.5),
+            Err(Error::InvalidGeometry(_))
+        ));
+    }
+
+    #[test]
+    fn test_mesh_colors() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let state = puzzle.get_derived_state_turn_index(&puzzle.get_initial_state(), 0);
+        let meshes = piece_meshes(&puzzle, &state, 0.0).unwrap();
+        let mesh_faces: Vec<&MeshFace> = meshes.iter().flat_map(|mesh| &mesh.faces).collect();
+        // The 2x2's stickers are on a quarter of a side each, like the outsides of the pieces
+        // (though they are a little smaller, so that they don't touch the cuts)
+        for (sticker, piece_face) in puzzle.faces(&state).iter().zip(&puzzle.faces) {
+            let center = Vector3D::from_average(&piece_face.face.vertices);
+            let distance = |mesh_face: &&MeshFace| {
+                (Vector3D::from_average(&mesh_face.face.vertices) - &center).magnitude()
+            };
+            let mesh_face = mesh_faces
+                .iter()
+                .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+                .unwrap();
+            assert_eq!(mesh_face.color_index, Some(sticker.color_index));
+        }
+    }
+
+    #[test]
+    fn test_exports() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let state = puzzle.get_derived_state_turn_index(&puzzle.get_initial_state(), 0);
+        let mut meshes = piece_meshes(&puzzle, &state, 0.02).unwrap();
+        for mesh in &mut meshes {
+            mesh.scale(10.0);
+        }
+        let num_triangles: usize = meshes
+            .iter()
+            .flat_map(|mesh| &mesh.faces)
+            .map(|mesh_face| mesh_face.face.vertices.len() - 2)
+            .sum();
+        let color_scheme = ColorScheme::default();
+
+        let obj = to_obj(&meshes, &color_scheme);
+        assert_eq!(obj.matches("\no ").count() + 1, meshes.len());
+        assert_snapshot!(obj);
+
+        let stl = to_stl(&meshes);
+        assert!(stl.starts_with("solid ") && stl.ends_with("endsolid twisty_puzzle\n"));
+        assert_eq!(stl.matches("facet normal").count(), num_triangles);
+
+        let gltf = to_gltf(&meshes, &color_scheme);
+        assert_eq!(gltf.matches(r#""mesh":"#).count(), meshes.len());
+        // 6 colors and the inside of the puzzle
+        assert_eq!(gltf.matches("baseColorFactor").count(), 7);
+        // Each triangle has 3 positions and 3 normals of 3 floats each
+        let byte_length = num_triangles * 3 * 2 * 3 * 4;
+        assert!(gltf.contains(&format!(r#""byteLength":{},"uri""#, byte_length)));
+        let encoded = gltf.split("base64,").nth(1).unwrap().split('"').next();
+        assert_eq!(encoded.unwrap().len(), byte_length.div_ceil(3) * 4);
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar!"), "Zm9vYmFyIQ==");
+    }
+}
@@ -0,0 +1,262 @@
+//! Drawing the puzzle flat, as a net of its polyhedron's faces, so that every sticker can be seen
+//! at once (the 3D view only shows the front).
+//!
+//! The net comes from the same tree of hinges that `Polyhedron::generate` folds the faces up
+//! along, so each sticker is unfolded along with the face of the polyhedron that it is on.
+//! Nets are also exported as SVG scramble images, like the ones on printed scramble sheets.
+
+use crate::render::{draw_seen_faces, ColorScheme, Renderer, SeenFace, SvgRenderer};
+use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+use crate::vector3d::Vector3D;
+
+// Stickers are drawn a little smaller than the faces, so that there are gaps between them
+const STICKER_SCALE: f64 = 0.9;
+// The space left around the net, as a fraction of the space it is drawn in
+const MARGIN: f64 = 0.05;
+// The puzzle's body, which shows in the gaps between the stickers
+const BODY_COLOR: &str = "black";
+// Scramble images are printed, so they go on white paper
+const SCRAMBLE_IMAGE_BACKGROUND_COLOR: &str = "white";
+
+/// Where each of the puzzle's faces is in the net
+#[derive(Debug, Clone)]
+pub struct Net {
+    /// The outline of each of the puzzle's faces (in the same order as `TwistyPuzzle::faces`),
+    /// relative to the center of the net. x goes right and y goes down, like in images,
+    /// and one unit is the length of an edge of the polyhedron.
+    pub polygons: Vec<Vec<(f64, f64)>>,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Net {
+    pub fn new(puzzle: &TwistyPuzzle) -> Self {
+        let polyhedron = &puzzle.polyhedron;
+        let polyhedron_normals: Vec<Vector3D> = polyhedron
+            .faces
+            .iter()
+            .map(|face| face.normal().to_unit_vector())
+            .collect();
+
+        // Axes in the plane of the first face, going counter-clockwise as seen from outside
+        let first_face = &polyhedron.faces[0];
+        let origin = Vector3D::from_average(&first_face.vertices);
+        let u_right = (first_face.vertices[1] - &first_face.vertices[0]).to_unit_vector();
+        let u_up = polyhedron_normals[0].cross(&u_right);
+
+        let mut polygons: Vec<Vec<(f64, f64)>> = puzzle
+            .faces
+            .iter()
+            .map(|piece_face| {
+                // Every sticker is on the face of the polyhedron that points the same way
+                let normal = piece_face.face.normal().to_unit_vector();
+                let face_index = (0..polyhedron_normals.len())
+                    .max_by(|&a, &b| {
+                        normal
+                            .dot(&polyhedron_normals[a])
+                            .total_cmp(&normal.dot(&polyhedron_normals[b]))
+                    })
+                    .unwrap();
+                piece_face
+                    .face
+                    .vertices
+                    .iter()
+                    .map(|vertex| {
+                        let point = polyhedron.unfold_point(face_index, vertex) - &origin;
+                        (point.dot(&u_right), -point.dot(&u_up))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let points = || polygons.iter().flatten();
+        let min_x = points().map(|point| point.0).fold(f64::INFINITY, f64::min);
+        let max_x = points()
+            .map(|point| point.0)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let min_y = points().map(|point| point.1).fold(f64::INFINITY, f64::min);
+        let max_y = points()
+            .map(|point| point.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let center = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        for point in polygons.iter_mut().flatten() {
+            *point = (point.0 - center.0, point.1 - center.1);
+        }
+        Self {
+            polygons,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        }
+    }
+}
+
+/// Draws the net of the puzzle in the given state, as large as it fits in the rectangle
+/// with its top left corner at (`left`, `top`). The background isn't filled in.
+#[allow(clippy::too_many_arguments)]
+pub fn draw_net(
+    renderer: &mut impl Renderer,
+    puzzle: &TwistyPuzzle,
+    puzzle_state: &PuzzleState,
+    net: &Net,
+    color_scheme: &ColorScheme,
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+) {
+    let scale = f64::min(width / net.width, height / net.height) * (1.0 - 2.0 * MARGIN);
+    let body = net.polygons.iter().map(|polygon| SeenFace {
+        points: polygon
+            .iter()
+            .map(|&(x, y)| (x * scale, y * scale))
+            .collect(),
+        color: BODY_COLOR,
+        orientation_marker: false,
+        distance_from_camera: 0.0,
+    });
+    let faces = puzzle.faces(puzzle_state);
+    let stickers = faces
+        .iter()
+        .zip(&net.polygons)
+        .map(|(piece_face, polygon)| {
+            let num_points = polygon.len() as f64;
+            let center_x = polygon.iter().map(|point| point.0).sum::<f64>() / num_points;
+            let center_y = polygon.iter().map(|point| point.1).sum::<f64>() / num_points;
+            SeenFace {
+                points: polygon
+                    .iter()
+                    .map(|&(x, y)| {
+                        let x = center_x + (x - center_x) * STICKER_SCALE;
+                        let y = center_y + (y - center_y) * STICKER_SCALE;
+                        (x * scale, y * scale)
+                    })
+                    .collect(),
+                color: color_scheme.color(piece_face.color_index),
+                orientation_marker: piece_face.orientation_marker,
+                distance_from_camera: 0.0,
+            }
+        });
+    let center = (left + width / 2.0, top + height / 2.0);
+    draw_seen_faces(renderer, body.chain(stickers).collect(), center);
+}
+
+/// An SVG image of the net of the puzzle in the given state, for printing on scramble sheets.
+/// It is `width` pixels wide, and as tall as the net needs.
+pub fn scramble_svg(
+    puzzle: &TwistyPuzzle,
+    puzzle_state: &PuzzleState,
+    color_scheme: &ColorScheme,
+    width: f64,
+) -> String {
+    let net = Net::new(puzzle);
+    let height = (width * net.height / net.width).round();
+    let mut renderer = SvgRenderer::new(width, height);
+    renderer.fill_background(SCRAMBLE_IMAGE_BACKGROUND_COLOR);
+    draw_net(
+        &mut renderer,
+        puzzle,
+        puzzle_state,
+        &net,
+        color_scheme,
+        0.0,
+        0.0,
+        width,
+        height,
+    );
+    renderer.to_svg()
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use super::*;
+    use crate::puzzles;
+    use crate::render::BACKGROUND_COLOR;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn signed_area(polygon: &[(f64, f64)]) -> f64 {
+        polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(p, q)| p.0 * q.1 - q.0 * p.1)
+            .sum::<f64>()
+            / 2.0
+    }
+
+    // Whether two convex polygons overlap, which they don't if the line through an edge of
+    // either one has the other one on its far side (touching along the line doesn't count)
+    fn overlap(a: &[(f64, f64)], b: &[(f64, f64)]) -> bool {
+        let separated_by_edge_of = |a: &[(f64, f64)], b: &[(f64, f64)]| {
+            a.iter().zip(a.iter().cycle().skip(1)).any(|(p, q)| {
+                let side = |r: &(f64, f64)| (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0);
+                let a_side = a.iter().map(side).sum::<f64>();
+                b.iter().all(|r| side(r) * a_side.signum() <= 1e-9)
+            })
+        };
+        !separated_by_edge_of(a, b) && !separated_by_edge_of(b, a)
+    }
+
+    #[test]
+    fn test_net_layout() {
+        for info in puzzles::ALL_PUZZLES {
+            let puzzle = (info.constructor)();
+            let net = Net::new(&puzzle);
+            assert_eq!(net.polygons.len(), puzzle.faces.len());
+            for (piece_face, polygon) in puzzle.faces.iter().zip(&net.polygons) {
+                // Unfolding doesn't stretch the stickers, or turn them over
+                // (y goes down, so counter-clockwise seen from outside is negative)
+                assert!(
+                    (signed_area(polygon) + piece_face.face.area()).abs() < 1e-6,
+                    "{}",
+                    info.name
+                );
+            }
+            for (i, a) in net.polygons.iter().enumerate() {
+                for b in &net.polygons[i + 1..] {
+                    assert!(!overlap(a, b), "stickers overlap in the {} net", info.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_svg_net() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let state = puzzle.get_derived_state_turn_index(&puzzle.get_initial_state(), 0);
+        let mut renderer = SvgRenderer::new(400.0, 300.0);
+        renderer.fill_background(BACKGROUND_COLOR);
+        draw_net(
+            &mut renderer,
+            &puzzle,
+            &state,
+            &Net::new(&puzzle),
+            &ColorScheme::default(),
+            0.0,
+            0.0,
+            400.0,
+            300.0,
+        );
+        let svg = renderer.to_svg();
+        // Each sticker is drawn on top of its part of the body
+        assert_eq!(svg.matches("<polygon").count(), 2 * puzzle.faces.len());
+        assert_snapshot!(svg);
+    }
+
+    #[test]
+    fn test_scramble_images() {
+        let color_scheme = ColorScheme::parse("white #00f #f80 green red yellow").unwrap();
+        for info in puzzles::ALL_PUZZLES {
+            let puzzle = (info.constructor)();
+            let mut rng = ChaCha8Rng::seed_from_u64(0);
+            let scramble = puzzle.scramble_turns(25, &mut rng);
+            let state = puzzle.get_derived_state_from_turn_sequence(
+                &puzzle.get_initial_state(),
+                scramble.into_iter(),
+            );
+            let svg = scramble_svg(&puzzle, &state, &color_scheme, 300.0);
+            assert_snapshot!(format!("scramble_{}", info.name), svg);
+        }
+    }
+}
@@ -0,0 +1,738 @@
+use std::io::{self, Read, Write};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::error::{Error, Result};
+use crate::training_data::TrainingData;
+use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+
+const MAGIC: &[u8; 4] = b"TWNN";
+const VERSION: u16 = 1;
+// Limits on the sizes in a weights file, so that a corrupt one can't make it allocate too much
+const MAX_HIDDEN_LAYERS: usize = 16;
+const MAX_LAYER_WEIGHTS: usize = 1 << 26;
+
+/// A small fully connected network which estimates, for a puzzle state,
+/// the number of turns left to solve it (the value)
+/// and how likely each turn is to be the next turn of a solution (the policy).
+///
+/// The input is a one-hot encoding of the value (color or sticker) of each face.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    num_faces: usize,
+    num_values: usize,
+    num_turns: usize,
+    hidden_layers: Vec<Layer>,
+    value_head: Layer,
+    policy_head: Layer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Layer {
+    num_inputs: usize,
+    num_outputs: usize,
+    // Row-major, one row per output
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+}
+
+impl Layer {
+    fn zeros(num_inputs: usize, num_outputs: usize) -> Self {
+        Self {
+            num_inputs,
+            num_outputs,
+            weights: vec![0.0; num_inputs * num_outputs],
+            biases: vec![0.0; num_outputs],
+        }
+    }
+
+    fn new_random<T: Rng>(num_inputs: usize, num_outputs: usize, rng: &mut T) -> Self {
+        // He initialization, since the hidden layers use ReLU
+        let limit = (6.0 / num_inputs as f32).sqrt();
+        let mut layer = Self::zeros(num_inputs, num_outputs);
+        for weight in &mut layer.weights {
+            *weight = rng.gen_range(-limit..limit);
+        }
+        layer
+    }
+
+    fn params_mut(&mut self) -> impl Iterator<Item = &mut f32> {
+        self.weights.iter_mut().chain(self.biases.iter_mut())
+    }
+
+    fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut outputs = self.biases.clone();
+        linalg::mat_vec(
+            &self.weights,
+            self.num_outputs,
+            self.num_inputs,
+            inputs,
+            &mut outputs,
+        );
+        outputs
+    }
+
+    /// Same as `forward`, but for one-hot inputs, given the indices of the ones
+    fn forward_one_hot(&self, active_inputs: &[usize]) -> Vec<f32> {
+        let mut outputs = self.biases.clone();
+        for (output, row) in outputs
+            .iter_mut()
+            .zip(self.weights.chunks_exact(self.num_inputs))
+        {
+            for &i in active_inputs {
+                *output += row[i];
+            }
+        }
+        outputs
+    }
+}
+
+impl Network {
+    pub fn new<T: Rng>(puzzle: &TwistyPuzzle, hidden_sizes: &[usize], rng: &mut T) -> Self {
+        let num_faces = puzzle.get_num_faces();
+        let num_values = num_values(puzzle);
+        let num_turns = puzzle.turn_names.len();
+        let mut num_inputs = num_faces * num_values;
+        let mut hidden_layers = vec![];
+        for &size in hidden_sizes {
+            hidden_layers.push(Layer::new_random(num_inputs, size, rng));
+            num_inputs = size;
+        }
+        Self {
+            num_faces,
+            num_values,
+            num_turns,
+            hidden_layers,
+            value_head: Layer::new_random(num_inputs, 1, rng),
+            policy_head: Layer::new_random(num_inputs, num_turns, rng),
+        }
+    }
+
+    /// Whether the network's inputs and outputs fit the puzzle
+    pub fn matches(&self, puzzle: &TwistyPuzzle) -> bool {
+        self.num_faces == puzzle.get_num_faces()
+            && self.num_turns == puzzle.turn_names.len()
+            && puzzle
+                .get_initial_state()
+                .iter()
+                .all(|&value| value < self.num_values)
+    }
+
+    fn zeros_like(&self) -> Self {
+        let zeros = |layer: &Layer| Layer::zeros(layer.num_inputs, layer.num_outputs);
+        Self {
+            num_faces: self.num_faces,
+            num_values: self.num_values,
+            num_turns: self.num_turns,
+            hidden_layers: self.hidden_layers.iter().map(zeros).collect(),
+            value_head: zeros(&self.value_head),
+            policy_head: zeros(&self.policy_head),
+        }
+    }
+
+    fn layers_mut(&mut self) -> impl Iterator<Item = &mut Layer> {
+        self.hidden_layers
+            .iter_mut()
+            .chain([&mut self.value_head, &mut self.policy_head])
+    }
+
+    fn params_mut(&mut self) -> impl Iterator<Item = &mut f32> {
+        self.layers_mut().flat_map(|layer| layer.params_mut())
+    }
+
+    fn encode(&self, puzzle_state: &PuzzleState) -> Vec<usize> {
+        puzzle_state
+            .iter()
+            .enumerate()
+            .map(|(face_index, &value)| face_index * self.num_values + value)
+            .collect()
+    }
+
+    /// The activations of each hidden layer, after ReLU
+    fn hidden_activations(&self, puzzle_state: &PuzzleState) -> Vec<Vec<f32>> {
+        let active_inputs = self.encode(puzzle_state);
+        let mut activations: Vec<Vec<f32>> = vec![];
+        for (i, layer) in self.hidden_layers.iter().enumerate() {
+            let mut outputs = if i == 0 {
+                layer.forward_one_hot(&active_inputs)
+            } else {
+                layer.forward(&activations[i - 1])
+            };
+            for output in &mut outputs {
+                *output = output.max(0.0);
+            }
+            activations.push(outputs);
+        }
+        activations
+    }
+
+    fn last_hidden(&self, activations: &[Vec<f32>], puzzle_state: &PuzzleState) -> Vec<f32> {
+        match activations.last() {
+            Some(last) => last.clone(),
+            // No hidden layers, so the heads use the one-hot input directly
+            None => {
+                let mut inputs = vec![0.0; self.num_faces * self.num_values];
+                for i in self.encode(puzzle_state) {
+                    inputs[i] = 1.0;
+                }
+                inputs
+            }
+        }
+    }
+
+    /// The estimated number of turns left to solve the state
+    pub fn value(&self, puzzle_state: &PuzzleState) -> f32 {
+        let activations = self.hidden_activations(puzzle_state);
+        let last = self.last_hidden(&activations, puzzle_state);
+        self.value_head.forward(&last)[0]
+    }
+
+    /// The estimated number of turns left, along with the probability of each turn being next
+    pub fn evaluate(&self, puzzle_state: &PuzzleState) -> (f32, Vec<f32>) {
+        let activations = self.hidden_activations(puzzle_state);
+        let last = self.last_hidden(&activations, puzzle_state);
+        let value = self.value_head.forward(&last)[0];
+        let policy = softmax(&self.policy_head.forward(&last));
+        (value, policy)
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        for count in [
+            self.num_faces,
+            self.num_values,
+            self.num_turns,
+            self.hidden_layers.len(),
+        ] {
+            writer.write_all(&(count as u32).to_le_bytes())?;
+        }
+        for layer in &self.hidden_layers {
+            writer.write_all(&(layer.num_outputs as u32).to_le_bytes())?;
+        }
+        for layer in self
+            .hidden_layers
+            .iter()
+            .chain([&self.value_head, &self.policy_head])
+        {
+            for param in layer.weights.iter().chain(&layer.biases) {
+                writer.write_all(&param.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the network, which has to have been trained for the puzzle
+    pub fn read<R: Read>(reader: &mut R, puzzle: &TwistyPuzzle) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a network weights file"));
+        }
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        if u16::from_le_bytes(version) != VERSION {
+            return Err(invalid_data("unsupported network weights version"));
+        }
+        let mut read_u32 = || -> io::Result<usize> {
+            let mut bytes = [0; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes) as usize)
+        };
+        let num_faces = read_u32()?;
+        let num_values = read_u32()?;
+        let num_turns = read_u32()?;
+        if num_faces != puzzle.get_num_faces()
+            || num_values != self::num_values(puzzle)
+            || num_turns != puzzle.turn_names.len()
+        {
+            return Err(invalid_data("network was trained for a different puzzle"));
+        }
+        let num_hidden_layers = read_u32()?;
+        if num_hidden_layers > MAX_HIDDEN_LAYERS {
+            return Err(invalid_data("network has too many layers"));
+        }
+        let hidden_sizes = (0..num_hidden_layers)
+            .map(|_| read_u32())
+            .collect::<io::Result<Vec<usize>>>()?;
+
+        // Checked before allocating, since the sizes come from the file
+        let layer =
+            |num_inputs: usize, num_outputs: usize| match num_inputs.checked_mul(num_outputs) {
+                Some(num_weights) if num_weights <= MAX_LAYER_WEIGHTS => {
+                    Ok(Layer::zeros(num_inputs, num_outputs))
+                }
+                _ => Err(invalid_data("network layer is too big")),
+            };
+        let mut num_inputs = num_faces * num_values;
+        let mut hidden_layers = vec![];
+        for size in hidden_sizes {
+            hidden_layers.push(layer(num_inputs, size)?);
+            num_inputs = size;
+        }
+        let mut network = Self {
+            num_faces,
+            num_values,
+            num_turns,
+            hidden_layers,
+            value_head: layer(num_inputs, 1)?,
+            policy_head: layer(num_inputs, num_turns)?,
+        };
+        for param in network.params_mut() {
+            let mut bytes = [0; 4];
+            reader.read_exact(&mut bytes)?;
+            *param = f32::from_le_bytes(bytes);
+        }
+        Ok(network)
+    }
+}
+
+fn softmax(logits: &[f32]) -> Vec<f32> {
+    let max = logits.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = logits.iter().map(|&l| (l - max).exp()).collect();
+    let sum: f32 = exps.iter().sum();
+    exps.into_iter().map(|e| e / sum).collect()
+}
+
+/// The number of different values that the puzzle's faces can have
+fn num_values(puzzle: &TwistyPuzzle) -> usize {
+    puzzle.get_initial_state().into_iter().max().unwrap_or(0) + 1
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A state to train on, with the targets for the value and policy heads
+pub struct TrainingTarget<'a> {
+    pub state: &'a PuzzleState,
+    pub value: f32,
+    pub next_turn: usize,
+}
+
+/// Trains a network with the Adam optimizer
+pub struct Trainer {
+    pub network: Network,
+    pub learning_rate: f32,
+    gradients: Network,
+    first_moments: Network,
+    second_moments: Network,
+    num_steps: i32,
+}
+
+impl Trainer {
+    pub fn new(network: Network, learning_rate: f32) -> Self {
+        Self {
+            gradients: network.zeros_like(),
+            first_moments: network.zeros_like(),
+            second_moments: network.zeros_like(),
+            network,
+            learning_rate,
+            num_steps: 0,
+        }
+    }
+
+    /// Takes one optimizer step on the batch,
+    /// returning the mean squared value error and the mean policy cross-entropy
+    pub fn train_batch(&mut self, batch: &[TrainingTarget]) -> (f32, f32) {
+        for param in self.gradients.params_mut() {
+            *param = 0.0;
+        }
+        let mut value_loss = 0.0;
+        let mut policy_loss = 0.0;
+        for target in batch {
+            let (v, p) = self.backpropagate(target);
+            value_loss += v;
+            policy_loss += p;
+        }
+
+        const BETA1: f32 = 0.9;
+        const BETA2: f32 = 0.999;
+        self.num_steps += 1;
+        let scale = 1.0 / batch.len() as f32;
+        let step_size = self.learning_rate * (1.0 - BETA2.powi(self.num_steps)).sqrt()
+            / (1.0 - BETA1.powi(self.num_steps));
+        for (((param, gradient), m), v) in self
+            .network
+            .params_mut()
+            .zip(self.gradients.params_mut())
+            .zip(self.first_moments.params_mut())
+            .zip(self.second_moments.params_mut())
+        {
+            let gradient = *gradient * scale;
+            *m = BETA1 * *m + (1.0 - BETA1) * gradient;
+            *v = BETA2 * *v + (1.0 - BETA2) * gradient * gradient;
+            *param -= step_size * *m / (v.sqrt() + 1e-8);
+        }
+
+        (value_loss * scale, policy_loss * scale)
+    }
+
+    /// Adds the gradients of the loss for one state, returning its value and policy losses
+    fn backpropagate(&mut self, target: &TrainingTarget) -> (f32, f32) {
+        let network = &self.network;
+        let gradients = &mut self.gradients;
+        let active_inputs = network.encode(target.state);
+        let activations = network.hidden_activations(target.state);
+        let last = network.last_hidden(&activations, target.state);
+
+        let value = network.value_head.forward(&last)[0];
+        let value_error = value - target.value;
+        let policy = softmax(&network.policy_head.forward(&last));
+        let policy_loss = -policy[target.next_turn].max(1e-12).ln();
+
+        // Gradients of the loss with respect to each head's outputs
+        let value_delta = [value_error];
+        let mut policy_delta = policy;
+        policy_delta[target.next_turn] -= 1.0;
+
+        let mut delta = vec![0.0; last.len()];
+        for (head, head_gradients, head_delta) in [
+            (
+                &network.value_head,
+                &mut gradients.value_head,
+                &value_delta[..],
+            ),
+            (
+                &network.policy_head,
+                &mut gradients.policy_head,
+                &policy_delta[..],
+            ),
+        ] {
+            accumulate_layer_gradients(head_gradients, head_delta, &last);
+            linalg::mat_t_vec(
+                &head.weights,
+                head.num_outputs,
+                head.num_inputs,
+                head_delta,
+                &mut delta,
+            );
+        }
+
+        for i in (0..network.hidden_layers.len()).rev() {
+            let layer = &network.hidden_layers[i];
+            // ReLU only passes gradients through where it was active
+            for (d, &a) in delta.iter_mut().zip(&activations[i]) {
+                if a <= 0.0 {
+                    *d = 0.0;
+                }
+            }
+            let layer_gradients = &mut gradients.hidden_layers[i];
+            if i == 0 {
+                for (o, &d) in delta.iter().enumerate() {
+                    let row = &mut layer_gradients.weights[o * layer.num_inputs..];
+                    for &input in &active_inputs {
+                        row[input] += d;
+                    }
+                    layer_gradients.biases[o] += d;
+                }
+            } else {
+                accumulate_layer_gradients(layer_gradients, &delta, &activations[i - 1]);
+                let mut previous_delta = vec![0.0; layer.num_inputs];
+                linalg::mat_t_vec(
+                    &layer.weights,
+                    layer.num_outputs,
+                    layer.num_inputs,
+                    &delta,
+                    &mut previous_delta,
+                );
+                delta = previous_delta;
+            }
+        }
+
+        (value_error * value_error, policy_loss)
+    }
+}
+
+fn accumulate_layer_gradients(gradients: &mut Layer, delta: &[f32], inputs: &[f32]) {
+    linalg::outer_add(
+        &mut gradients.weights,
+        gradients.num_outputs,
+        gradients.num_inputs,
+        delta,
+        inputs,
+    );
+    for (bias, &d) in gradients.biases.iter_mut().zip(delta) {
+        *bias += d;
+    }
+}
+
+/// Computes value targets by one step of value iteration (as in DeepCubeA):
+/// each state is one turn more than its best neighbor according to `target_network`,
+/// and solved states are zero.
+/// The targets never exceed the scramble depth, since that many turns is known to solve it.
+pub fn value_targets(
+    target_network: &Network,
+    puzzle: &TwistyPuzzle,
+    data: &TrainingData,
+) -> Vec<f32> {
+    data.examples
+        .iter()
+        .map(|example| {
+            if puzzle.is_solved(&example.state) {
+                return 0.0;
+            }
+            let best_neighbor = (0..puzzle.turn_names.len())
+                .map(|turn_index| {
+                    let next_state =
+                        puzzle.get_derived_state_turn_index(&example.state, turn_index);
+                    if puzzle.is_solved(&next_state) {
+                        0.0
+                    } else {
+                        target_network.value(&next_state).max(0.0)
+                    }
+                })
+                .fold(f32::INFINITY, f32::min);
+            (1.0 + best_neighbor).min(example.depth as f32)
+        })
+        .collect()
+}
+
+/// Trains for one pass over the data in shuffled batches,
+/// using a frozen copy of the network to compute the value targets.
+/// Returns the mean value and policy losses,
+/// or `Error::InvalidBatchSize` if the batch size is 0.
+pub fn train_epoch<T: Rng>(
+    trainer: &mut Trainer,
+    puzzle: &TwistyPuzzle,
+    data: &TrainingData,
+    batch_size: usize,
+    rng: &mut T,
+) -> Result<(f32, f32)> {
+    if batch_size == 0 {
+        return Err(Error::InvalidBatchSize);
+    }
+    let targets = value_targets(&trainer.network.clone(), puzzle, data);
+    let mut order: Vec<usize> = (0..data.examples.len()).collect();
+    order.shuffle(rng);
+
+    let mut value_loss = 0.0;
+    let mut policy_loss = 0.0;
+    let mut num_batches = 0;
+    for batch_indices in order.chunks(batch_size) {
+        let batch: Vec<TrainingTarget> = batch_indices
+            .iter()
+            .map(|&i| TrainingTarget {
+                state: &data.examples[i].state,
+                value: targets[i],
+                next_turn: data.examples[i].next_turn,
+            })
+            .collect();
+        let (v, p) = trainer.train_batch(&batch);
+        value_loss += v;
+        policy_loss += p;
+        num_batches += 1;
+    }
+    Ok((
+        value_loss / num_batches.max(1) as f32,
+        policy_loss / num_batches.max(1) as f32,
+    ))
+}
+
+/// Dense matrix operations, using OpenBLAS when the `openblas` feature is enabled.
+/// Matrices are row-major with `rows * cols` elements.
+mod linalg {
+    #[cfg(feature = "openblas")]
+    mod cblas {
+        pub const ROW_MAJOR: i32 = 101;
+        pub const NO_TRANS: i32 = 111;
+        pub const TRANS: i32 = 112;
+
+        #[link(name = "openblas")]
+        extern "C" {
+            #[allow(clippy::too_many_arguments)]
+            pub fn cblas_sgemv(
+                layout: i32,
+                trans: i32,
+                m: i32,
+                n: i32,
+                alpha: f32,
+                a: *const f32,
+                lda: i32,
+                x: *const f32,
+                incx: i32,
+                beta: f32,
+                y: *mut f32,
+                incy: i32,
+            );
+            #[allow(clippy::too_many_arguments)]
+            pub fn cblas_sger(
+                layout: i32,
+                m: i32,
+                n: i32,
+                alpha: f32,
+                x: *const f32,
+                incx: i32,
+                y: *const f32,
+                incy: i32,
+                a: *mut f32,
+                lda: i32,
+            );
+        }
+    }
+
+    /// out += matrix * x
+    #[cfg(not(feature = "openblas"))]
+    pub fn mat_vec(matrix: &[f32], rows: usize, cols: usize, x: &[f32], out: &mut [f32]) {
+        for (o, row) in out.iter_mut().zip(matrix.chunks_exact(cols).take(rows)) {
+            *o += row.iter().zip(x).map(|(a, b)| a * b).sum::<f32>();
+        }
+    }
+
+    /// out += matrix^T * x
+    #[cfg(not(feature = "openblas"))]
+    pub fn mat_t_vec(matrix: &[f32], rows: usize, cols: usize, x: &[f32], out: &mut [f32]) {
+        for (row, &x) in matrix.chunks_exact(cols).take(rows).zip(x) {
+            for (o, a) in out.iter_mut().zip(row) {
+                *o += a * x;
+            }
+        }
+    }
+
+    /// matrix += x * y^T
+    #[cfg(not(feature = "openblas"))]
+    pub fn outer_add(matrix: &mut [f32], rows: usize, cols: usize, x: &[f32], y: &[f32]) {
+        for (row, &x) in matrix.chunks_exact_mut(cols).take(rows).zip(x) {
+            for (a, b) in row.iter_mut().zip(y) {
+                *a += x * b;
+            }
+        }
+    }
+
+    #[cfg(feature = "openblas")]
+    pub fn mat_vec(matrix: &[f32], rows: usize, cols: usize, x: &[f32], out: &mut [f32]) {
+        assert!(matrix.len() >= rows * cols && x.len() >= cols && out.len() >= rows);
+        // SAFETY: the lengths are checked above
+        unsafe {
+            cblas::cblas_sgemv(
+                cblas::ROW_MAJOR,
+                cblas::NO_TRANS,
+                rows as i32,
+                cols as i32,
+                1.0,
+                matrix.as_ptr(),
+                cols as i32,
+                x.as_ptr(),
+                1,
+                1.0,
+                out.as_mut_ptr(),
+                1,
+            )
+        }
+    }
+
+    #[cfg(feature = "openblas")]
+    pub fn mat_t_vec(matrix: &[f32], rows: usize, cols: usize, x: &[f32], out: &mut [f32]) {
+        assert!(matrix.len() >= rows * cols && x.len() >= rows && out.len() >= cols);
+        // SAFETY: the lengths are checked above
+        unsafe {
+            cblas::cblas_sgemv(
+                cblas::ROW_MAJOR,
+                cblas::TRANS,
+                rows as i32,
+                cols as i32,
+                1.0,
+                matrix.as_ptr(),
+                cols as i32,
+                x.as_ptr(),
+                1,
+                1.0,
+                out.as_mut_ptr(),
+                1,
+            )
+        }
+    }
+
+    #[cfg(feature = "openblas")]
+    pub fn outer_add(matrix: &mut [f32], rows: usize, cols: usize, x: &[f32], y: &[f32]) {
+        assert!(matrix.len() >= rows * cols && x.len() >= rows && y.len() >= cols);
+        // SAFETY: the lengths are checked above
+        unsafe {
+            cblas::cblas_sger(
+                cblas::ROW_MAJOR,
+                rows as i32,
+                cols as i32,
+                1.0,
+                x.as_ptr(),
+                1,
+                y.as_ptr(),
+                1,
+                matrix.as_mut_ptr(),
+                cols as i32,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::puzzles;
+
+    #[test]
+    fn test_network_training() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut data = TrainingData::new(&puzzle);
+        for _ in 0..50 {
+            data.add_random_walk(&puzzle, 3, &mut rng);
+        }
+
+        let network = Network::new(&puzzle, &[32], &mut rng);
+        assert!(network.matches(&puzzle));
+        assert!(!network.matches(&puzzles::rubiks_cube_3x3()));
+
+        let mut trainer = Trainer::new(network, 0.01);
+        let (first_value_loss, first_policy_loss) =
+            train_epoch(&mut trainer, &puzzle, &data, 16, &mut rng).unwrap();
+        let mut losses = (first_value_loss, first_policy_loss);
+        for _ in 0..30 {
+            losses = train_epoch(&mut trainer, &puzzle, &data, 16, &mut rng).unwrap();
+        }
+        assert!(losses.0 < first_value_loss);
+        assert!(losses.1 < first_policy_loss);
+
+        let (value, policy) = trainer.network.evaluate(&data.examples[0].state);
+        assert!((policy.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+        assert_eq!(value, trainer.network.value(&data.examples[0].state));
+
+        let mut bytes = vec![];
+        trainer.network.write(&mut bytes).unwrap();
+        assert_eq!(
+            Network::read(&mut bytes.as_slice(), &puzzle).unwrap(),
+            trainer.network
+        );
+        assert!(Network::read(&mut &bytes[..bytes.len() - 1], &puzzle).is_err());
+        assert!(Network::read(&mut bytes.as_slice(), &puzzles::rubiks_cube_3x3()).is_err());
+
+        // A hidden layer far too big to allocate, straight after the header
+        let mut huge = bytes[..18].to_vec();
+        huge.extend_from_slice(&1u32.to_le_bytes());
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            Network::read(&mut huge.as_slice(), &puzzle)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_train_epoch_zero_batch_size() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut data = TrainingData::new(&puzzle);
+        data.add_random_walk(&puzzle, 3, &mut rng);
+        let network = Network::new(&puzzle, &[8], &mut rng);
+        let mut trainer = Trainer::new(network.clone(), 0.01);
+        assert_eq!(
+            train_epoch(&mut trainer, &puzzle, &data, 0, &mut rng),
+            Err(Error::InvalidBatchSize)
+        );
+        assert_eq!(trainer.network, network);
+    }
+}
@@ -0,0 +1,127 @@
+use num::BigUint;
+
+use crate::bijection::Bijection;
+
+/// Counts the permutations that can be made by combining the generators,
+/// using the Schreier-Sims algorithm (Knuth's version) to build a table of coset representatives.
+/// The group order is the product of the number of representatives at each level.
+pub fn group_order(generators: &[Bijection], num_points: usize) -> BigUint {
+    let mut group = PermutationGroup::new(num_points);
+    for generator in generators {
+        group.add_generator(0, generator.0.clone());
+    }
+    group
+        .representatives
+        .iter()
+        .map(|level| BigUint::from(level.iter().filter(|rep| rep.is_some()).count()))
+        .product()
+}
+
+// Permutations map each point (index) to its image (value)
+type Permutation = Vec<usize>;
+
+struct PermutationGroup {
+    num_points: usize,
+    // representatives[k][j] fixes the points before k and maps k to j
+    representatives: Vec<Vec<Option<Permutation>>>,
+    // The generators added at each level, which all fix the points before that level
+    generators: Vec<Vec<Permutation>>,
+}
+
+impl PermutationGroup {
+    fn new(num_points: usize) -> Self {
+        let representatives = (0..num_points)
+            .map(|k| {
+                let mut level = vec![None; num_points];
+                level[k] = Some((0..num_points).collect());
+                level
+            })
+            .collect();
+        Self {
+            num_points,
+            representatives,
+            generators: vec![vec![]; num_points],
+        }
+    }
+
+    /// Checks whether the permutation (which fixes the points before `level`)
+    /// can be made from the representatives
+    fn contains(&self, level: usize, permutation: &Permutation) -> bool {
+        let mut permutation = permutation.clone();
+        for k in level..self.num_points {
+            match &self.representatives[k][permutation[k]] {
+                Some(representative) => {
+                    permutation = compose(&invert(representative), &permutation)
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn add_generator(&mut self, level: usize, generator: Permutation) {
+        if self.contains(level, &generator) {
+            return;
+        }
+        self.generators[level].push(generator.clone());
+        let representatives: Vec<Permutation> = self.representatives[level]
+            .iter()
+            .flatten()
+            .cloned()
+            .collect();
+        for representative in representatives {
+            self.add_representative(level, compose(&generator, &representative));
+        }
+    }
+
+    fn add_representative(&mut self, level: usize, permutation: Permutation) {
+        let image = permutation[level];
+        match &self.representatives[level][image] {
+            Some(representative) => {
+                // The permutation is in the same coset as the representative,
+                // so the difference between them fixes this level's point too
+                let fixing = compose(&invert(representative), &permutation);
+                self.add_generator(level + 1, fixing);
+            }
+            None => {
+                self.representatives[level][image] = Some(permutation.clone());
+                let generators = self.generators[level].clone();
+                for generator in generators {
+                    self.add_representative(level, compose(&generator, &permutation));
+                }
+            }
+        }
+    }
+}
+
+/// Applies `second` and then `first`
+fn compose(first: &Permutation, second: &Permutation) -> Permutation {
+    second.iter().map(|&point| first[point]).collect()
+}
+
+fn invert(permutation: &Permutation) -> Permutation {
+    let mut inverted = vec![0; permutation.len()];
+    for (point, &image) in permutation.iter().enumerate() {
+        inverted[image] = point;
+    }
+    inverted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_order() {
+        let swap = [Bijection(vec![1, 0, 2, 3])];
+        let rotate = [Bijection(vec![1, 2, 3, 0])];
+        assert_eq!(group_order(&[], 4), BigUint::from(1u32));
+        assert_eq!(group_order(&swap, 4), BigUint::from(2u32));
+        assert_eq!(group_order(&rotate, 4), BigUint::from(4u32));
+        // A transposition and a full cycle generate every permutation
+        assert_eq!(
+            group_order(&[swap, rotate].concat(), 4),
+            BigUint::from(24u32)
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Finding what is under the cursor, so that dragging across a sticker turns the puzzle.
+//! Points in the image are relative to its center, like the ones `Camera::see_point` gives.
+
+use crate::render::Camera;
+use crate::twisty_puzzle::{PieceFace, TwistyPuzzle};
+use crate::vector3d::Vector3D;
+
+// How far along a turn's direction to move a point to see which way it goes in the image
+const DIRECTION_STEP: f64 = 1e-3;
+
+/// The index of the sticker nearest the camera at a point in the image,
+/// and the point on the sticker that is there.
+/// Stickers that face away from the camera are ignored, the same as when drawing.
+pub fn pick_face(
+    camera: &Camera,
+    faces: &[PieceFace],
+    image_point: (f64, f64),
+) -> Option<(usize, Vector3D)> {
+    let ray = camera.ray_through(image_point);
+    faces
+        .iter()
+        .enumerate()
+        .filter_map(|(face_index, piece_face)| {
+            let plane = piece_face.face.plane();
+            if ray.direction.dot(&piece_face.face.normal()) >= 0.0 {
+                return None;
+            }
+            let point = plane.intersection(&ray);
+            let distance = (point - &ray.point).dot(&ray.direction);
+            if distance <= 0.0 || !piece_face.face.contains_point(&point) {
+                return None;
+            }
+            Some((face_index, point, distance))
+        })
+        .min_by(|(_, _, a), (_, _, b)| a.partial_cmp(b).unwrap())
+        .map(|(face_index, point, _)| (face_index, point))
+}
+
+/// The turn made by dragging from `start` to `end`:
+/// of the turns that move the sticker under `start`,
+/// the one that starts moving that point on the sticker most nearly the way the cursor went.
+/// Works the same for any puzzle, whether its turns are around faces, vertices or edges.
+/// Returns None if there is no sticker under `start`, or no turn moves it that way.
+pub fn pick_turn(
+    camera: &Camera,
+    puzzle: &TwistyPuzzle,
+    start: (f64, f64),
+    end: (f64, f64),
+) -> Option<usize> {
+    // Every state has the stickers in the same places, so the solved one will do
+    let (face_index, point) = pick_face(camera, &puzzle.faces, start)?;
+    let drag = (end.0 - start.0, end.1 - start.1);
+    let drag_length = drag.0.hypot(drag.1);
+    if drag_length == 0.0 {
+        return None;
+    }
+    let seen_point = camera.see_point(point)?;
+
+    (0..puzzle.turn_names.len())
+        .filter(|&turn_index| puzzle.faces[face_index].is_turned_by(turn_index))
+        .filter_map(|turn_index| {
+            let direction = puzzle.get_turn_direction(turn_index, &point);
+            let moved = camera.see_point(&point + &(DIRECTION_STEP * direction))?;
+            let movement = (moved.0 - seen_point.0, moved.1 - seen_point.1);
+            let movement_length = movement.0.hypot(movement.1);
+            if movement_length == 0.0 {
+                return None;
+            }
+            // The cosine of the angle between the drag and the way the point moves
+            let alignment =
+                (movement.0 * drag.0 + movement.1 * drag.1) / (movement_length * drag_length);
+            Some((turn_index, alignment))
+        })
+        .filter(|&(_, alignment)| alignment > 0.0)
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(turn_index, _)| turn_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles;
+
+    #[test]
+    fn test_pick_face() {
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let camera = Camera::default();
+        let faces = puzzle.faces(&puzzle.get_initial_state());
+        let mut num_visible = 0;
+        for (face_index, piece_face) in faces.iter().enumerate() {
+            if camera.see_face(&piece_face.face, "", false).is_none() {
+                continue;
+            }
+            num_visible += 1;
+            let center = Vector3D::from_average(&piece_face.face.vertices);
+            let (picked_index, picked_point) =
+                pick_face(&camera, &faces, camera.see_point(center).unwrap()).unwrap();
+            assert_eq!(picked_index, face_index);
+            assert!(picked_point.approx_equals(&center));
+        }
+        assert_eq!(num_visible, 9 * 3);
+
+        assert!(pick_face(&camera, &faces, (1000.0, 1000.0)).is_none());
+    }
+
+    #[test]
+    fn test_pick_turn() {
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let camera = Camera::default();
+        let center =
+            |face_index: usize| Vector3D::from_average(&puzzle.faces[face_index].face.vertices);
+        let turns_of = |face_index: usize| -> Vec<usize> {
+            (0..puzzle.turn_names.len())
+                .filter(|&turn_index| puzzle.faces[face_index].is_turned_by(turn_index))
+                .collect()
+        };
+        let top_stickers: Vec<_> = (0..puzzle.faces.len())
+            .filter(|&face_index| (center(face_index).z - 0.5).abs() < 1e-6)
+            .collect();
+        let top_center = *top_stickers
+            .iter()
+            .find(|&&face_index| center(face_index).approx_equals(&Vector3D::new(0.0, 0.0, 0.5)))
+            .unwrap();
+        let top_turns = turns_of(top_center);
+        // The edge sticker on top that is nearest the camera,
+        // which turns with the top and with the side below it
+        let edge = *top_stickers
+            .iter()
+            .filter(|&&face_index| turns_of(face_index).len() == 4)
+            .min_by(|&&a, &&b| {
+                let distance = |face_index| (center(face_index) - &camera.point).magnitude();
+                distance(a).partial_cmp(&distance(b)).unwrap()
+            })
+            .unwrap();
+        let start = camera.see_point(center(edge)).unwrap();
+        let drag =
+            |(x, y): (f64, f64)| pick_turn(&camera, &puzzle, start, (start.0 + x, start.1 + y));
+
+        // Dragging down the image pulls the sticker over the edge and down the side
+        let down_turn = drag((0.0, 30.0)).unwrap();
+        assert!(!top_turns.contains(&down_turn));
+        assert_eq!(
+            drag((0.0, -30.0)),
+            Some(puzzle.inverted_turn_index(down_turn))
+        );
+        // Dragging across turns the top
+        let right_turn = drag((30.0, 0.0)).unwrap();
+        assert!(top_turns.contains(&right_turn));
+        assert_eq!(
+            drag((-30.0, 0.0)),
+            Some(puzzle.inverted_turn_index(right_turn))
+        );
+
+        assert_eq!(drag((0.0, 0.0)), None);
+        // The center of a face doesn't move when the face turns
+        let top_center_point = camera.see_point(center(top_center)).unwrap();
+        assert_eq!(
+            pick_turn(
+                &camera,
+                &puzzle,
+                top_center_point,
+                (top_center_point.0 + 30.0, top_center_point.1)
+            ),
+            None
+        );
+        assert_eq!(
+            pick_turn(&camera, &puzzle, (1000.0, 1000.0), (1000.0, 900.0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::bijection::Bijection;
+
+/// Precomputed number of turns needed to move each single piece
+/// from any position and orientation back to its solved position,
+/// ignoring all of the other pieces.
+pub(crate) struct PieceDistanceTable {
+    // For each piece slot, every (piece, orientation) that can occupy it
+    slots: Vec<Vec<SlotCandidate>>,
+}
+
+struct SlotCandidate {
+    // The face positions of the piece's faces, in the same order as the piece's home faces
+    positions: Vec<usize>,
+    home_faces: Vec<usize>,
+    distance: usize,
+}
+
+impl PieceDistanceTable {
+    pub fn new(pieces: &[Vec<usize>], face_maps: &[&Bijection]) -> Self {
+        // The face maps pull from old positions, so inverting them gives
+        // the new position of the face which was at each old position
+        let forward_maps: Vec<Bijection> = face_maps.iter().map(|m| m.invert()).collect();
+
+        let slot_of_face: HashMap<usize, usize> = pieces
+            .iter()
+            .enumerate()
+            .flat_map(|(slot_index, piece)| piece.iter().map(move |&f| (f, slot_index)))
+            .collect();
+
+        let mut slots: Vec<Vec<SlotCandidate>> = pieces.iter().map(|_| vec![]).collect();
+
+        for home_faces in pieces {
+            // Breadth-first search over the positions this piece can reach
+            let mut distances: HashMap<Vec<usize>, usize> = HashMap::new();
+            let mut queue: VecDeque<Vec<usize>> = VecDeque::new();
+            distances.insert(home_faces.clone(), 0);
+            queue.push_back(home_faces.clone());
+            while let Some(positions) = queue.pop_front() {
+                let distance = distances[&positions];
+                for forward_map in &forward_maps {
+                    let new_positions: Vec<usize> =
+                        positions.iter().map(|&p| forward_map.0[p]).collect();
+                    if !distances.contains_key(&new_positions) {
+                        distances.insert(new_positions.clone(), distance + 1);
+                        queue.push_back(new_positions);
+                    }
+                }
+            }
+
+            for (positions, distance) in distances {
+                slots[slot_of_face[&positions[0]]].push(SlotCandidate {
+                    positions,
+                    home_faces: home_faces.clone(),
+                    distance,
+                });
+            }
+        }
+
+        for candidates in &mut slots {
+            // Check the closest candidates first
+            candidates.sort_by(|a, b| {
+                (a.distance, &a.home_faces, &a.positions).cmp(&(
+                    b.distance,
+                    &b.home_faces,
+                    &b.positions,
+                ))
+            });
+        }
+
+        Self { slots }
+    }
+
+    /// The number of turns needed to solve the piece in each slot on its own.
+    /// When several pieces look the same (e.g. 4x4 centers), the closest one is used.
+    pub fn piece_distances<'a>(
+        &'a self,
+        puzzle_state: &'a [usize],
+        initial_state: &'a [usize],
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.slots.iter().map(move |candidates| {
+            candidates
+                .iter()
+                .find(|candidate| {
+                    candidate.positions.iter().zip(&candidate.home_faces).all(
+                        |(&position, &home_face)| {
+                            puzzle_state[position] == initial_state[home_face]
+                        },
+                    )
+                })
+                .map(|candidate| candidate.distance)
+                // Only possible if the state can't be reached by turning
+                .unwrap_or(0)
+        })
+    }
+}
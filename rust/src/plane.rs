@@ -15,6 +15,10 @@ impl Plane {
         let prod3 = prod1 / prod2;
         &ray.point - &(&ray.direction * prod3)
     }
+    /// How far the point is from the plane, positive on the side that the normal points to
+    pub fn distance_to(&self, point: &Vector3D) -> f64 {
+        (point - self.point).dot(&self.normal.to_unit_vector())
+    }
     pub fn offset(&self, offset: f64) -> Plane {
         let offset_vector = offset * self.normal.to_unit_vector();
         Plane {
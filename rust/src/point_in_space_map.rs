@@ -1,141 +1,87 @@
+use crate::error::{Error, Result};
 use crate::vector3d::Vector3D;
-use num::traits::Float;
 use std::collections::HashMap;
 
-const THRESHOLD: f64 = 1e-10;
-const HALF_THRESHOLD: f64 = THRESHOLD / 2.0;
+// Points closer together than this are treated as the same point
+const TOLERANCE: f64 = 1e-10;
 
-#[derive(PartialEq, Eq, Hash, Debug)]
-struct SplitUpFloat(u64, i16, i8);
-impl SplitUpFloat {
-    pub fn new(f: f64) -> Self {
-        let (mantissa, exponent, sign) = Float::integer_decode(f);
-        Self(mantissa, exponent, sign)
-    }
-}
-
-fn round(f: f64) -> f64 {
-    let rounded = (f / THRESHOLD).round() * THRESHOLD;
-    if rounded == -0.0 {
-        0.0
-    } else {
-        rounded
-    }
-}
-
-#[derive(PartialEq, Eq, Hash, Debug)]
-struct VectorKey(SplitUpFloat, SplitUpFloat, SplitUpFloat);
-impl VectorKey {
-    pub fn new(v: &Vector3D) -> Self {
-        Self(
-            SplitUpFloat::new(round(v.x)),
-            SplitUpFloat::new(round(v.y)),
-            SplitUpFloat::new(round(v.z)),
-        )
-    }
-
-    /// If the point is near the edge of rounding buckets, it will return the adjacent buckets too
-    /// This prevents errors where an insert and retrieval might barely round in different directions
-    pub fn new_and_nearby(v: &Vector3D) -> Vec<Self> {
-        let rounded_x = round(v.x);
-        let rounded_y = round(v.y);
-        let rounded_z = round(v.z);
-        let mut output = vec![(rounded_x, rounded_y, rounded_z)];
-
-        if rounded_x - v.x >= HALF_THRESHOLD {
-            // rounded up by a lot; add the rounded down version
-            let add_to_output: Vec<_> = output
-                .iter()
-                .map(|(x, y, z)| (x - THRESHOLD, *y, *z))
-                .collect();
-            output.extend_from_slice(&add_to_output);
-        } else if v.x - rounded_x >= HALF_THRESHOLD {
-            // rounded down by a lot; add the rounded up version
-            let add_to_output: Vec<_> = output
-                .iter()
-                .map(|(x, y, z)| (x + THRESHOLD, *y, *z))
-                .collect();
-            output.extend_from_slice(&add_to_output);
-        }
-
-        if rounded_y - v.y >= HALF_THRESHOLD {
-            // rounded up by a lot; add the rounded down version
-            let add_to_output: Vec<_> = output
-                .iter()
-                .map(|(x, y, z)| (*x, y - THRESHOLD, *z))
-                .collect();
-            output.extend_from_slice(&add_to_output);
-        } else if v.y - rounded_y >= HALF_THRESHOLD {
-            // rounded down by a lot; add the rounded up version
-            let add_to_output: Vec<_> = output
-                .iter()
-                .map(|(x, y, z)| (*x, y + THRESHOLD, *z))
-                .collect();
-            output.extend_from_slice(&add_to_output);
-        }
-
-        if rounded_z - v.z >= HALF_THRESHOLD {
-            // rounded up by a lot; add the rounded down version
-            let add_to_output: Vec<_> = output
-                .iter()
-                .map(|(x, y, z)| (*x, *y, z - THRESHOLD))
-                .collect();
-            output.extend_from_slice(&add_to_output);
-        } else if v.z - rounded_z >= HALF_THRESHOLD {
-            // rounded down by a lot; add the rounded up version
-            let add_to_output: Vec<_> = output
-                .iter()
-                .map(|(x, y, z)| (*x, *y, z + THRESHOLD))
-                .collect();
-            output.extend_from_slice(&add_to_output);
-        }
+type CellKey = (i64, i64, i64);
 
-        output
-            .into_iter()
-            .map(|(x, y, z)| {
-                Self(
-                    SplitUpFloat::new(x),
-                    SplitUpFloat::new(y),
-                    SplitUpFloat::new(z),
-                )
-            })
-            .collect()
-    }
+fn cell_key(v: &Vector3D) -> CellKey {
+    let cell = |f: f64| (f / TOLERANCE).floor() as i64;
+    (cell(v.x), cell(v.y), cell(v.z))
 }
 
 /// HashMap that is keyed by Vector3d's, and which deals with floating-point errors
+/// by treating points within `TOLERANCE` of each other as the same point.
+/// The points are kept in a grid of cells as wide as the tolerance,
+/// so anything close enough to a point is in the point's cell or one of the cells around it.
 pub struct PointInSpaceMap<V> {
-    hash_map: HashMap<VectorKey, Vec<(Vector3D, V)>>,
+    cells: HashMap<CellKey, Vec<(Vector3D, V)>>,
 }
 
-impl<V: Clone> PointInSpaceMap<V> {
+impl<V> PointInSpaceMap<V> {
     pub fn new() -> Self {
         Self {
-            hash_map: HashMap::new(),
+            cells: HashMap::new(),
         }
     }
-    pub fn insert(&mut self, key: Vector3D, value: V) {
-        let key_buckets = VectorKey::new_and_nearby(&key);
-        for key_bucket in key_buckets {
-            let entry = self.hash_map.entry(key_bucket).or_insert_with(Vec::new);
-            let existing_entry = entry
-                .iter_mut()
-                .find(|(entry_key, _)| entry_key.approx_equals(&key));
-            match existing_entry {
-                Some((_existing_key, existing_value)) => {
-                    *existing_value = value.clone();
+
+    /// The cell and the index in that cell of every point that is close enough to the key
+    fn find_nearby(&self, key: &Vector3D) -> Vec<(CellKey, usize)> {
+        let (x, y, z) = cell_key(key);
+        let mut nearby = vec![];
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let cell = (x + dx, y + dy, z + dz);
+                    if let Some(entries) = self.cells.get(&cell) {
+                        for (i, (point, _)) in entries.iter().enumerate() {
+                            if (point - key).magnitude() < TOLERANCE {
+                                nearby.push((cell, i));
+                            }
+                        }
+                    }
                 }
-                None => entry.push((key, value.clone())),
             }
         }
+        nearby
+    }
+
+    fn ambiguous(key: &Vector3D, num_nearby: usize) -> Error {
+        Error::AmbiguousMapping(format!(
+            "({}, {}, {}) is close to {} different points",
+            key.x, key.y, key.z, num_nearby
+        ))
+    }
+
+    /// Inserts the value, replacing (and returning) the value of a point that is close enough
+    /// to count as the same point
+    pub fn insert(&mut self, key: Vector3D, value: V) -> Result<Option<V>> {
+        match self.find_nearby(&key)[..] {
+            [] => {
+                self.cells
+                    .entry(cell_key(&key))
+                    .or_default()
+                    .push((key, value));
+                Ok(None)
+            }
+            [(cell, i)] => {
+                let entries = self.cells.get_mut(&cell).unwrap();
+                Ok(Some(std::mem::replace(&mut entries[i].1, value)))
+            }
+            ref nearby => Err(Self::ambiguous(&key, nearby.len())),
+        }
     }
-    pub fn get(&self, key: &Vector3D) -> Option<&V> {
-        let key_bucket = VectorKey::new(key);
-        let entry = self.hash_map.get(&key_bucket)?;
-        let (_existing_key, existing_value) = entry
-            .iter()
-            .find(|(entry_key, _)| entry_key.approx_equals(key))?;
-        Some(existing_value)
+
+    /// Finds the value of the point that is close enough to count as the same point as the key,
+    /// or fails if there is more than one
+    pub fn get(&self, key: &Vector3D) -> Result<Option<&V>> {
+        match self.find_nearby(key)[..] {
+            [] => Ok(None),
+            [(cell, i)] => Ok(Some(&self.cells[&cell][i].1)),
+            ref nearby => Err(Self::ambiguous(key, nearby.len())),
+        }
     }
 }
 
@@ -144,56 +90,51 @@ mod tests {
     use super::*;
 
     #[test]
-    #[allow(clippy::assertions_on_constants)]
     fn test() {
         let mut f = PointInSpaceMap::new();
-        assert!(f64::EPSILON < THRESHOLD);
-        f.insert(Vector3D::new(0.0, 0.0, 0.0), 1);
-        assert_eq!(f.get(&Vector3D::new(0.0, 0.0, 0.0)), Some(&1));
+        assert_eq!(f.insert(Vector3D::new(0.0, 0.0, 0.0), 1), Ok(None));
+        assert_eq!(f.get(&Vector3D::new(0.0, 0.0, 0.0)), Ok(Some(&1)));
 
-        // This is right on the edge of the threshold (inside)
+        // Inside the tolerance, in either direction
         assert_eq!(
-            f.get(&Vector3D::new(0.0 + THRESHOLD / 2.0 - 1e-20, 0.0, 0.0)),
-            Some(&1)
+            f.get(&Vector3D::new(TOLERANCE * 0.9, 0.0, 0.0)),
+            Ok(Some(&1))
         );
-
-        // This is right on the other edge of the threshold (inside)
         assert_eq!(
-            f.get(&Vector3D::new(0.0 - THRESHOLD / 2.0 + 1e-20, 0.0, 0.0)),
-            Some(&1)
+            f.get(&Vector3D::new(0.0, -TOLERANCE * 0.9, 0.0)),
+            Ok(Some(&1))
+        );
+        // Each coordinate is inside the tolerance, but the distance isn't
+        assert_eq!(
+            f.get(&Vector3D::new(TOLERANCE * 0.9, TOLERANCE * 0.9, 0.0)),
+            Ok(None)
         );
-
-        // This is right on the edge of the threshold (outside)
-        assert_eq!(f.get(&Vector3D::new(0.0 + THRESHOLD / 2.0, 0.0, 0.0)), None);
-
-        // This is right on the other edge of the threshold (outside)
-        assert_eq!(f.get(&Vector3D::new(0.0 - THRESHOLD / 2.0, 0.0, 0.0)), None);
 
         // Fully outside
-        assert_eq!(f.get(&Vector3D::new(0.01, 0.0, 0.0)), None);
+        assert_eq!(f.get(&Vector3D::new(0.01, 0.0, 0.0)), Ok(None));
 
-        f.insert(Vector3D::new(0.0, 1.0 + THRESHOLD / 2.0, 0.0), 27);
-        assert_eq!(
-            f.get(&Vector3D::new(0.0, 1.0 + THRESHOLD / 2.0, 0.0)),
-            Some(&27)
-        );
-        // These numbers are on the edge of being rounded either up or down
-        // Both should work because we check adjacent buckets
+        // Points on either side of a cell boundary are still the same point
+        let boundary = 1.0 + TOLERANCE * 3.0;
+        f.insert(Vector3D::new(0.0, boundary + f64::EPSILON, 0.0), 27)
+            .unwrap();
         assert_eq!(
-            f.get(&Vector3D::new(
-                0.0,
-                1.0 + THRESHOLD / 2.0 + f64::EPSILON,
-                0.0
-            )),
-            Some(&27)
+            f.get(&Vector3D::new(0.0, boundary - f64::EPSILON, 0.0)),
+            Ok(Some(&27))
         );
+
+        // Inserting close to an existing point replaces it
         assert_eq!(
-            f.get(&Vector3D::new(
-                0.0,
-                1.0 + THRESHOLD / 2.0 - f64::EPSILON,
-                0.0
-            )),
-            Some(&27)
+            f.insert(Vector3D::new(0.0, 0.0, TOLERANCE / 2.0), 2),
+            Ok(Some(1))
         );
+        assert_eq!(f.get(&Vector3D::new(0.0, 0.0, 0.0)), Ok(Some(&2)));
+
+        // Halfway between two points that are only just far enough apart to be different
+        f.insert(Vector3D::new(TOLERANCE * 1.5, 0.0, 0.0), 3)
+            .unwrap();
+        assert!(matches!(
+            f.get(&Vector3D::new(TOLERANCE * 0.75, 0.0, 0.0)),
+            Err(Error::AmbiguousMapping(_))
+        ));
     }
 }
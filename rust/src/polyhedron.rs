@@ -1,3 +1,4 @@
+use crate::error::{Error, Result};
 use crate::plane::Plane;
 use crate::rotation3d::Rotation3D;
 use crate::vector3d::Vector3D;
@@ -5,6 +6,7 @@ use std::collections::VecDeque;
 use std::f64::consts::{PI, TAU};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Face {
     pub vertices: Vec<Vector3D>,
 }
@@ -17,6 +19,19 @@ impl Face {
             .map(move |(i, vertex_a)| Edge(*vertex_a, self.vertices[(i + 1) % self.vertices.len()]))
     }
 
+    /// Rotates the face, keeping its vertices going the same way around when seen from outside
+    pub fn rotate(&self, rotation: &Rotation3D, rotation_axis_position: &Vector3D) -> Self {
+        Self {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|vertex| {
+                    rotation.rotate_point_about_positioned_axis(vertex, rotation_axis_position)
+                })
+                .collect(),
+        }
+    }
+
     pub fn rotate_about_axis(
         &self,
         rotation: &Rotation3D,
@@ -45,20 +60,66 @@ impl Face {
             normal: edge_1_vector.cross(&edge_2_vector),
         }
     }
+
+    /// Points out of the side that the vertices go counter-clockwise around,
+    /// with a length of twice the area.
+    /// Found by splitting the (flat, convex) polygon into triangles that fan out from the first
+    /// vertex, so unlike `plane`, it still works when the first vertices are in a line.
+    pub fn normal(&self) -> Vector3D {
+        let first = &self.vertices[0];
+        self.vertices[1..]
+            .windows(2)
+            .map(|pair| (pair[0] - first).cross(&(pair[1] - first)))
+            .fold(Vector3D::zero(), |sum, cross| &sum + &cross)
+    }
+
+    /// The area of the (flat, convex) polygon
+    pub fn area(&self) -> f64 {
+        self.normal().magnitude() / 2.0
+    }
+
+    /// Whether a point in the face's plane is inside the (convex) face, or on its edge
+    pub fn contains_point(&self, point: &Vector3D) -> bool {
+        let normal = self.normal();
+        self.edges_iter()
+            .all(|Edge(a, b)| (b - &a).cross(&(point - a)).dot(&normal) >= 0.0)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Polyhedron {
     pub faces: Vec<Face>,
     pub vertices: Vec<Vector3D>,
     /// Distance from the origin to the center of a face
     pub inradius: f64,
+    /// The angle between the planes of adjacent faces, measured inside the polyhedron
+    pub dihedral_angle: f64,
+    /// For each face, the face that it was folded up from and the edge between them,
+    /// which together make a tree that unfolds the polyhedron into a net.
+    /// None for the first face, which everything else is folded up from.
+    pub unfolding: Vec<Option<Hinge>>,
+}
+
+/// Where one face of a polyhedron is attached to another in its net
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hinge {
+    pub parent_face_index: usize,
+    pub edge: Edge,
 }
 
 impl Polyhedron {
     // p means the faces are p-sided polygons
     // q means there are q faces on each vertex
-    pub fn generate(p: usize, q: usize) -> Polyhedron {
+    pub fn generate(p: usize, q: usize) -> Result<Polyhedron> {
+        // Only the five platonic solids close up; other values tile a plane or a hyperbolic plane
+        if p < 3 || q < 3 || (p - 2) * (q - 2) >= 4 {
+            return Err(Error::InvalidGeometry(format!(
+                "there is no regular polyhedron with {}-sided faces and {} faces at each vertex",
+                p, q
+            )));
+        }
         // Dihedral angle is the angle between adjacent faces
         let dihedral_angle = 2.0 * ((PI / q as f64).cos() / (PI / p as f64).sin()).asin();
         let edge_length = 1.0;
@@ -93,6 +154,7 @@ impl Polyhedron {
         // Edges which have one associated face but not yet two
         let mut incomplete_edges: VecDeque<QueuedEdge> = VecDeque::new();
         let mut faces = vec![bottom_face];
+        let mut unfolding = vec![None];
         for edge in faces[0].edges_iter() {
             vertices.push(edge.0);
             incomplete_edges.push_back(QueuedEdge {
@@ -136,13 +198,37 @@ impl Polyhedron {
                 }
             }
             faces.push(new_face);
+            unfolding.push(Some(Hinge {
+                parent_face_index: queued_edge.face_index,
+                edge: queued_edge.edge,
+            }));
         }
 
-        Polyhedron {
+        Ok(Polyhedron {
             faces,
             vertices,
             inradius,
+            dihedral_angle,
+            unfolding,
+        })
+    }
+
+    /// Where a point on the face ends up when the polyhedron is unfolded flat
+    /// into the plane of the first face.
+    /// Each face was folded up from its parent by rotating it about their shared edge,
+    /// so the rest of the way around (to a half turn) lays it flat next to its parent,
+    /// which is then laid flat next to its own parent, and so on.
+    pub fn unfold_point(&self, face_index: usize, point: &Vector3D) -> Vector3D {
+        let mut point = *point;
+        let mut face_index = face_index;
+        while let Some(hinge) = &self.unfolding[face_index] {
+            let Edge(vertex_a, vertex_b) = &hinge.edge;
+            let rotation_axis = (vertex_a - vertex_b).to_unit_vector();
+            let rotation = Rotation3D::new(&rotation_axis, PI - self.dihedral_angle);
+            point = rotation.rotate_point_about_positioned_axis(&point, vertex_a);
+            face_index = hinge.parent_face_index;
         }
+        point
     }
     pub fn opposite_face_pairs(&self) -> Vec<(&Face, &Face)> {
         let mut face_pairs = vec![None; self.faces.len()];
@@ -196,7 +282,8 @@ struct QueuedEdge {
     face_index: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Edge(pub Vector3D, pub Vector3D);
 impl Edge {
     pub fn approx_equals(&self, other: &Edge) -> bool {
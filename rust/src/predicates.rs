@@ -0,0 +1,79 @@
+use num::{BigRational, Zero};
+use std::cmp::Ordering;
+
+use crate::plane::Plane;
+use crate::vector3d::Vector3D;
+
+// The most that rounding can change the floating-point dot product of differences,
+// relative to the sum of the magnitudes of its terms (as in Shewchuk's adaptive predicates)
+const DOT_PRODUCT_ERROR_BOUND: f64 = (3.0 + 16.0 * f64::EPSILON) * f64::EPSILON;
+
+/// Which side of the plane the point is on:
+/// `Greater` on the side that the normal points to, `Less` on the other side,
+/// and `Equal` if the point is exactly on the plane.
+/// Floating point is used when the answer is clear,
+/// and exact rational arithmetic when it is too close for rounding errors to be ruled out.
+pub fn side_of_plane(point: &Vector3D, plane: &Plane) -> Ordering {
+    let terms = [
+        (point.x - plane.point.x, plane.normal.x),
+        (point.y - plane.point.y, plane.normal.y),
+        (point.z - plane.point.z, plane.normal.z),
+    ];
+    let dot: f64 = terms
+        .iter()
+        .map(|(difference, normal)| difference * normal)
+        .sum();
+    let magnitude: f64 = terms
+        .iter()
+        .map(|(difference, normal)| (difference * normal).abs())
+        .sum();
+    if dot.abs() > DOT_PRODUCT_ERROR_BOUND * magnitude {
+        return dot.partial_cmp(&0.0).unwrap();
+    }
+    exact_side_of_plane(point, plane)
+}
+
+fn exact_side_of_plane(point: &Vector3D, plane: &Plane) -> Ordering {
+    // Every finite float is a rational number, so this doesn't lose anything
+    let exact = |f: f64| BigRational::from_float(f).expect("coordinates are finite");
+    let dot = [
+        (point.x, plane.point.x, plane.normal.x),
+        (point.y, plane.point.y, plane.normal.y),
+        (point.z, plane.point.z, plane.normal.z),
+    ]
+    .into_iter()
+    .map(|(point, plane_point, normal)| (exact(point) - exact(plane_point)) * exact(normal))
+    .fold(BigRational::zero(), |sum, term| sum + term);
+    dot.cmp(&BigRational::zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_side_of_plane() {
+        let plane = Plane {
+            point: Vector3D::new(0.0, 0.0, 0.0),
+            normal: Vector3D::new(1.0, 1.0, 1.0),
+        };
+        assert_eq!(
+            side_of_plane(&Vector3D::new(1.0, 0.0, 0.0), &plane),
+            Ordering::Greater
+        );
+        assert_eq!(
+            side_of_plane(&Vector3D::new(0.5, -0.25, -0.25), &plane),
+            Ordering::Equal
+        );
+        // 1e16 + 1 rounds to 1e16, so floating point would put this on the plane
+        assert_eq!(
+            side_of_plane(&Vector3D::new(1e16, 1.0, -1e16), &plane),
+            Ordering::Greater
+        );
+        // 0.1 + 0.2 - 0.3 isn't quite zero, and the rounding error is about as big as the result
+        assert_eq!(
+            side_of_plane(&Vector3D::new(-0.1, -0.2, 0.3), &plane),
+            Ordering::Less
+        );
+    }
+}
@@ -2,27 +2,178 @@ use std::f64::consts::TAU;
 
 use crate::plane::Plane;
 use crate::polyhedron::Polyhedron;
-use crate::twisty_puzzle::{CutDefinition, TwistyPuzzle};
+use crate::twisty_puzzle::{CutDefinition, StickerMode, TwistyPuzzle};
 use crate::vector3d::Vector3D;
 
 fn tetrahedron() -> Polyhedron {
-    Polyhedron::generate(3, 3)
+    Polyhedron::generate(3, 3).expect("platonic solid")
 }
 
 fn cube() -> Polyhedron {
-    Polyhedron::generate(4, 3)
+    Polyhedron::generate(4, 3).expect("platonic solid")
 }
 
 fn octahedron() -> Polyhedron {
-    Polyhedron::generate(3, 4)
+    Polyhedron::generate(3, 4).expect("platonic solid")
 }
 
 fn dodecahedron() -> Polyhedron {
-    Polyhedron::generate(5, 3)
+    Polyhedron::generate(5, 3).expect("platonic solid")
 }
 
 fn icosahedron() -> Polyhedron {
-    Polyhedron::generate(3, 5)
+    Polyhedron::generate(3, 5).expect("platonic solid")
+}
+
+pub type PuzzleConstructor = fn() -> TwistyPuzzle;
+
+/// What the turns of a puzzle go around
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnAxis {
+    Faces,
+    Vertices,
+}
+
+/// A built-in puzzle, and what is known about it without building it
+#[derive(Debug, Clone, Copy)]
+pub struct PuzzleInfo {
+    /// Used on the command line and in the catalog
+    pub name: &'static str,
+    /// Shown in the browser UI
+    pub display_name: &'static str,
+    /// The polyhedron that the puzzle is cut from
+    pub shape: &'static str,
+    pub turn_axis: TurnAxis,
+    pub constructor: PuzzleConstructor,
+}
+
+const fn info(
+    name: &'static str,
+    display_name: &'static str,
+    shape: &'static str,
+    turn_axis: TurnAxis,
+    constructor: PuzzleConstructor,
+) -> PuzzleInfo {
+    PuzzleInfo {
+        name,
+        display_name,
+        shape,
+        turn_axis,
+        constructor,
+    }
+}
+
+/// Every built-in puzzle
+pub const ALL_PUZZLES: &[PuzzleInfo] = &[
+    info("2x2", "2x2 Cube", "cube", TurnAxis::Faces, rubiks_cube_2x2),
+    info("3x3", "3x3 Cube", "cube", TurnAxis::Faces, rubiks_cube_3x3),
+    info("4x4", "4x4 Cube", "cube", TurnAxis::Faces, rubiks_cube_4x4),
+    info(
+        "supercube-3x3",
+        "3x3 Supercube",
+        "cube",
+        TurnAxis::Faces,
+        rubiks_supercube_3x3,
+    ),
+    info(
+        "supercube-4x4",
+        "4x4 Supercube",
+        "cube",
+        TurnAxis::Faces,
+        rubiks_supercube_4x4,
+    ),
+    info(
+        "megaminx",
+        "Megaminx",
+        "dodecahedron",
+        TurnAxis::Faces,
+        megaminx,
+    ),
+    info(
+        "starminx",
+        "Starminx",
+        "dodecahedron",
+        TurnAxis::Faces,
+        starminx,
+    ),
+    info(
+        "compy-cube",
+        "Compy Cube",
+        "cube",
+        TurnAxis::Vertices,
+        compy_cube,
+    ),
+    info("skewb", "Skewb", "cube", TurnAxis::Vertices, skewb),
+    info(
+        "pentultimate",
+        "Pentultimate",
+        "dodecahedron",
+        TurnAxis::Faces,
+        pentultimate,
+    ),
+    info(
+        "master-pentultimate",
+        "Master Pentultimate",
+        "dodecahedron",
+        TurnAxis::Faces,
+        master_pentultimate,
+    ),
+    info(
+        "dino-starminx",
+        "Dino Starminx",
+        "dodecahedron",
+        TurnAxis::Vertices,
+        dino_starminx,
+    ),
+    info(
+        "pyraminx",
+        "Pyraminx",
+        "tetrahedron",
+        TurnAxis::Vertices,
+        pyraminx,
+    ),
+    info(
+        "skewb-diamond",
+        "Skewb Diamond",
+        "octahedron",
+        TurnAxis::Faces,
+        skewb_diamond,
+    ),
+    info(
+        "fto",
+        "Face-Turning Octahedron",
+        "octahedron",
+        TurnAxis::Faces,
+        fto,
+    ),
+    info(
+        "eitans-star",
+        "Eitan's Star",
+        "icosahedron",
+        TurnAxis::Faces,
+        eitans_star,
+    ),
+];
+
+/// The puzzles that the site has branch deploys for,
+/// which the built-in metamove catalog needs to cover
+pub const DEPLOYED_PUZZLES: [&str; 6] = [
+    "2x2",
+    "3x3",
+    "megaminx",
+    "dino-starminx",
+    "compy-cube",
+    "starminx",
+];
+
+/// Looks up a built-in puzzle's information by its name in `ALL_PUZZLES`
+pub fn info_by_name(name: &str) -> Option<&'static PuzzleInfo> {
+    ALL_PUZZLES.iter().find(|info| info.name == name)
+}
+
+/// Builds a built-in puzzle from its name in `ALL_PUZZLES`
+pub fn by_name(name: &str) -> Option<TwistyPuzzle> {
+    info_by_name(name).map(|info| (info.constructor)())
 }
 
 const RUBIKS_CUBE_CUT_NAMES: [&str; 6] = ["U", "F", "R", "B", "L", "D"];
@@ -38,6 +189,7 @@ pub fn megaminx() -> TwistyPuzzle {
             .map(|face| CutDefinition::new_infer_name(face.plane().offset(-0.33), TAU / 5.0))
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
@@ -51,27 +203,41 @@ pub fn starminx() -> TwistyPuzzle {
             .map(|face| CutDefinition::new_infer_name(face.plane().offset(-0.75), TAU / 5.0))
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
 pub fn eitans_star() -> TwistyPuzzle {
     let icosahedron = icosahedron();
+    let golden_ratio = (1.0 + 5f64.sqrt()) / 2.0;
+    // The real puzzle is cut 1/φ² of the way to the center,
+    // where the small pieces along each edge shrink to nothing
+    let cut_depth = -icosahedron.inradius / (golden_ratio * golden_ratio);
     TwistyPuzzle::new(
         &icosahedron,
         &icosahedron
             .faces
             .iter()
-            // This cut is positioned to create an extra edge piece that doesn't exist on the real thing
-            // To prevent zero-size pieces from being created
-            .map(|face| CutDefinition::new_infer_name(face.plane().offset(-0.27), TAU / 3.0))
+            .map(|face| CutDefinition::new_infer_name(face.plane().offset(cut_depth), TAU / 3.0))
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
 pub fn rubiks_cube_3x3() -> TwistyPuzzle {
+    rubiks_cube_3x3_with_sticker_mode(StickerMode::Color)
+}
+
+/// A 3x3 where every sticker is distinct, including the orientation of the centers
+#[allow(dead_code)]
+pub fn rubiks_supercube_3x3() -> TwistyPuzzle {
+    rubiks_cube_3x3_with_sticker_mode(StickerMode::Identity)
+}
+
+fn rubiks_cube_3x3_with_sticker_mode(sticker_mode: StickerMode) -> TwistyPuzzle {
     let cube = cube();
-    TwistyPuzzle::new(
+    TwistyPuzzle::new_with_sticker_mode(
         &cube,
         &cube
             .faces
@@ -85,13 +251,25 @@ pub fn rubiks_cube_3x3() -> TwistyPuzzle {
                 )
             })
             .collect::<Vec<_>>(),
+        sticker_mode,
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
 pub fn rubiks_cube_4x4() -> TwistyPuzzle {
+    rubiks_cube_4x4_with_sticker_mode(StickerMode::Color)
+}
+
+/// A 4x4 where every sticker is distinct, so the centers can't be swapped with each other
+#[allow(dead_code)]
+pub fn rubiks_supercube_4x4() -> TwistyPuzzle {
+    rubiks_cube_4x4_with_sticker_mode(StickerMode::Identity)
+}
+
+fn rubiks_cube_4x4_with_sticker_mode(sticker_mode: StickerMode) -> TwistyPuzzle {
     let cube = cube();
-    TwistyPuzzle::new(
+    TwistyPuzzle::new_with_sticker_mode(
         &cube,
         &cube
             .faces
@@ -110,7 +288,9 @@ pub fn rubiks_cube_4x4() -> TwistyPuzzle {
                 }),
             )
             .collect::<Vec<_>>(),
+        sticker_mode,
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
@@ -130,6 +310,7 @@ pub fn rubiks_cube_2x2() -> TwistyPuzzle {
             })
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
@@ -149,6 +330,7 @@ pub fn compy_cube() -> TwistyPuzzle {
             })
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
@@ -169,6 +351,7 @@ pub fn skewb() -> TwistyPuzzle {
             })
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
@@ -187,6 +370,7 @@ pub fn pentultimate() -> TwistyPuzzle {
             })
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
@@ -200,6 +384,7 @@ pub fn master_pentultimate() -> TwistyPuzzle {
             .map(|face| CutDefinition::new_infer_name(face.plane().offset(-1.03), TAU / 5.0))
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
@@ -219,6 +404,7 @@ pub fn dino_starminx() -> TwistyPuzzle {
             })
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
@@ -238,6 +424,7 @@ pub fn pyraminx() -> TwistyPuzzle {
             })
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
@@ -245,19 +432,24 @@ pub fn skewb_diamond() -> TwistyPuzzle {
     let octahedron = octahedron();
     TwistyPuzzle::new(
         &octahedron,
-        &octahedron.faces[0..=3]
+        // Each cut goes through the center, so it is the cut for the opposite face too.
+        // (Cutting at a fixed 0.41, just past the center, left thin slivers by the center
+        // that turns couldn't map onto any face, so the puzzle could never be built.)
+        &octahedron
+            .opposite_face_pairs()
             .iter()
-            .map(|face| CutDefinition::new_infer_name(face.plane().offset(-0.41), TAU / 3.0))
+            .map(|(face, _opposite_face)| {
+                CutDefinition::new_infer_name(face.plane().offset(-octahedron.inradius), TAU / 3.0)
+            })
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[allow(dead_code)]
 pub fn fto() -> TwistyPuzzle {
     let octahedron = octahedron();
-    // There is a small extra piece in the center because of the + at the end there:
-    // This is needed, otherwise there is a zero-size piece that messes things up
-    let cut_depth = -2.0 / 3.0 * octahedron.inradius + 0.02;
+    let cut_depth = -2.0 / 3.0 * octahedron.inradius;
     TwistyPuzzle::new(
         &octahedron,
         &octahedron
@@ -266,6 +458,7 @@ pub fn fto() -> TwistyPuzzle {
             .map(|face| CutDefinition::new_infer_name(face.plane().offset(cut_depth), TAU / 3.0))
             .collect::<Vec<_>>(),
     )
+    .expect("built-in puzzle geometry is valid")
 }
 
 #[cfg(test)]
@@ -283,6 +476,36 @@ mod tests {
         assert_eq!(initial_state, turned_again_state);
     }
 
+    #[test]
+    fn test_by_name() {
+        let names: std::collections::HashSet<&str> =
+            ALL_PUZZLES.iter().map(|info| info.name).collect();
+        assert_eq!(names.len(), ALL_PUZZLES.len());
+
+        assert_eq!(by_name("3x3").unwrap().get_num_faces(), 9 * 6);
+        assert!(by_name("3x3x3").is_none());
+        assert_eq!(info_by_name("fto").unwrap().shape, "octahedron");
+    }
+
+    #[test]
+    fn test_puzzle_info() {
+        for info in ALL_PUZZLES {
+            // Each face of the polyhedron has its own color
+            let num_shape_faces = match info.shape {
+                "tetrahedron" => 4,
+                "cube" => 6,
+                "octahedron" => 8,
+                "dodecahedron" => 12,
+                "icosahedron" => 20,
+                shape => panic!("{} has an unknown shape {}", info.name, shape),
+            };
+            let puzzle = (info.constructor)();
+            let colors: std::collections::HashSet<_> =
+                puzzle.faces.iter().map(|face| face.color_index).collect();
+            assert_eq!(colors.len(), num_shape_faces, "{}", info.name);
+        }
+    }
+
     #[test]
     fn test_rubiks_cube_3x3() {
         let puzzle = rubiks_cube_3x3();
@@ -294,6 +517,49 @@ mod tests {
         assert_eq!(initial_state, turned_again_state);
     }
 
+    #[test]
+    fn test_rubiks_supercube_3x3() {
+        let puzzle = rubiks_supercube_3x3();
+        // Each center is split into four so that its orientation is visible
+        assert_eq!(puzzle.get_num_faces(), 8 * 6 + 4 * 6);
+        assert_eq!(puzzle.get_num_pieces(), rubiks_cube_3x3().get_num_pieces());
+
+        let initial_state = puzzle.get_initial_state();
+        let turned_state = puzzle.get_derived_state_turn_index(&initial_state, 0);
+        let turned_again_state = puzzle.get_derived_state_turn_index(&turned_state, 1);
+        assert_eq!(initial_state, turned_again_state);
+
+        // The center of the turned face is no longer solved, unlike on a regular 3x3
+        assert_eq!(puzzle.get_num_solved_pieces(&turned_state), 26 - 9);
+        let regular_puzzle = rubiks_cube_3x3();
+        let regular_turned_state =
+            regular_puzzle.get_derived_state_turn_index(&regular_puzzle.get_initial_state(), 0);
+        assert_eq!(
+            regular_puzzle.get_num_solved_pieces(&regular_turned_state),
+            26 - 8
+        );
+
+        // A full rotation of the face brings the center back to its original orientation
+        let half_turned_state =
+            puzzle.get_derived_state_from_turn_sequence(&initial_state, [0, 0].into_iter());
+        assert_ne!(half_turned_state, initial_state);
+        let fully_turned_state =
+            puzzle.get_derived_state_from_turn_sequence(&half_turned_state, [0, 0].into_iter());
+        assert_eq!(fully_turned_state, initial_state);
+    }
+
+    #[test]
+    fn test_rubiks_supercube_4x4() {
+        let puzzle = rubiks_supercube_4x4();
+        assert_eq!(puzzle.get_num_faces(), 16 * 6);
+
+        // Every sticker can be told apart, including the centers
+        let mut initial_state = puzzle.get_initial_state();
+        initial_state.sort_unstable();
+        initial_state.dedup();
+        assert_eq!(initial_state.len(), puzzle.get_num_faces());
+    }
+
     #[test]
     fn test_rubiks_cube_2x2() {
         let puzzle = rubiks_cube_2x2();
@@ -338,11 +604,10 @@ mod tests {
         assert_eq!(initial_state, turned_again_state);
     }
 
-    // #[test]
-    #[allow(dead_code)]
+    #[test]
     fn test_eitans_star() {
         let puzzle = eitans_star();
-        assert_eq!(puzzle.get_num_faces(), 16 * 20);
+        assert_eq!(puzzle.get_num_faces(), 13 * 20);
 
         let initial_state = puzzle.get_initial_state();
         let turned_state = puzzle.get_derived_state_turn_index(&initial_state, 0);
@@ -361,10 +626,29 @@ mod tests {
         assert_eq!(initial_state, turned_again_state);
     }
 
+    #[test]
+    fn test_skewb_diamond() {
+        let puzzle = skewb_diamond();
+        // Each triangle is cut into three corners and a center
+        assert_eq!(puzzle.get_num_faces(), 4 * 8);
+        assert_eq!(puzzle.get_num_pieces(), 6 + 8);
+        // Every cut goes through the center, halfway between a pair of opposite faces
+        let cut_planes = puzzle.cut_planes();
+        assert_eq!(cut_planes.len(), 4);
+        for (_, cut_plane) in cut_planes {
+            assert!(cut_plane.distance_to(&Vector3D::zero()).abs() < 1e-9);
+        }
+
+        let initial_state = puzzle.get_initial_state();
+        let turned_state = puzzle.get_derived_state_turn_index(&initial_state, 0);
+        let turned_again_state = puzzle.get_derived_state_turn_index(&turned_state, 1);
+        assert_eq!(initial_state, turned_again_state);
+    }
+
     #[test]
     fn test_fto() {
         let puzzle = fto();
-        assert_eq!(puzzle.get_num_faces(), 10 * 8);
+        assert_eq!(puzzle.get_num_faces(), 9 * 8);
 
         let initial_state = puzzle.get_initial_state();
         let turned_state = puzzle.get_derived_state_turn_index(&initial_state, 0);
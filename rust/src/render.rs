@@ -0,0 +1,488 @@
+//! Drawing the puzzle: projecting its faces through a camera and filling them in back-to-front,
+//! with backends for the browser canvas (in the `web` module) and for SVG files
+//!
+//! The puzzles are convex, so leaving out the faces that point away from the camera
+//! is enough to get the right faces in front.
+//! Partway through a turn, the puzzle is two convex parts on either side of the cut,
+//! so the part on the far side of the cut from the camera is drawn first.
+
+use std::fmt::Write;
+
+use crate::error::{Error, Result};
+use crate::plane::Plane;
+use crate::polyhedron::Face;
+use crate::ray::Ray;
+use crate::rotation3d::Rotation3D;
+use crate::twisty_puzzle::{PieceFace, PuzzleState, TwistyPuzzle};
+use crate::vector3d::Vector3D;
+
+const WHITE: &str = "#ffffff";
+const BLUE: &str = "#1976d2";
+const ORANGE: &str = "#ff5722";
+const GREEN: &str = "#7cb342";
+const RED: &str = "#d32f2f";
+const YELLOW: &str = "#ffeb3b";
+const PURPLE: &str = "#5e35b1";
+const DARK_RED: &str = "#6a1111";
+const DARK_BLUE: &str = "#0a3576";
+const DARK_GREEN: &str = "#174f1b";
+const PINK: &str = "#e758b4";
+const GREY: &str = "#a0a0a0";
+
+/// The color of each color index, repeating for puzzles with more colors than this
+pub const COLORS: [&str; 12] = [
+    WHITE, BLUE, ORANGE, GREEN, RED, YELLOW, // 1-6
+    PURPLE, DARK_RED, DARK_BLUE, DARK_GREEN, PINK, GREY, // 7-12
+];
+
+/// The colors that the color indices are drawn in, like the stickers that a puzzle came with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorScheme {
+    colors: Vec<String>,
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        Self {
+            colors: COLORS.iter().map(|color| color.to_string()).collect(),
+        }
+    }
+}
+
+impl ColorScheme {
+    /// Reads colors separated by commas or spaces, each either a hex color like `#ff5722`
+    /// or a name like `white`, in the order of the color indices
+    pub fn parse(text: &str) -> Result<Self> {
+        let colors: Vec<String> = text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|color| !color.is_empty())
+            .map(|color| color.to_string())
+            .collect();
+        if colors.is_empty() {
+            return Err(Error::InvalidColorScheme("there are no colors".to_string()));
+        }
+        // Only these are allowed, since colors are written straight into SVG attributes
+        let is_valid = |color: &str| match color.strip_prefix('#') {
+            Some(hex) => {
+                [3, 4, 6, 8].contains(&hex.len()) && hex.chars().all(|c| c.is_ascii_hexdigit())
+            }
+            None => color.chars().all(|c| c.is_ascii_alphabetic()),
+        };
+        if let Some(color) = colors.iter().find(|color| !is_valid(color)) {
+            return Err(Error::InvalidColorScheme(format!(
+                "invalid color {}",
+                color
+            )));
+        }
+        Ok(Self { colors })
+    }
+
+    /// The color of the color index, repeating for puzzles with more colors than the scheme
+    pub fn color(&self, color_index: usize) -> &str {
+        &self.colors[color_index % self.colors.len()]
+    }
+}
+
+// The named colors that 3D model files can use, since they only take numbers.
+// Other names are left to SVG viewers, which know all of them.
+const COLOR_NAMES: [(&str, &str); 20] = [
+    ("white", "#ffffff"),
+    ("black", "#000000"),
+    ("red", "#ff0000"),
+    ("green", "#008000"),
+    ("lime", "#00ff00"),
+    ("blue", "#0000ff"),
+    ("yellow", "#ffff00"),
+    ("orange", "#ffa500"),
+    ("purple", "#800080"),
+    ("pink", "#ffc0cb"),
+    ("brown", "#a52a2a"),
+    ("grey", "#808080"),
+    ("gray", "#808080"),
+    ("silver", "#c0c0c0"),
+    ("maroon", "#800000"),
+    ("navy", "#000080"),
+    ("teal", "#008080"),
+    ("olive", "#808000"),
+    ("aqua", "#00ffff"),
+    ("fuchsia", "#ff00ff"),
+];
+
+/// The red, green and blue of a color from a [`ColorScheme`] (or one of the colors here),
+/// from 0 to 1. Names that aren't known are grey.
+pub(crate) fn rgb(color: &str) -> [f64; 3] {
+    let color = COLOR_NAMES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(color))
+        .map_or(color, |(_, hex)| hex);
+    let Some(hex) = color.strip_prefix('#') else {
+        return [0.5; 3];
+    };
+    let digits: Vec<u32> = hex.chars().filter_map(|c| c.to_digit(16)).collect();
+    let channel = |i: usize| match digits.len() {
+        // #rgb and #rgba have one digit per channel, which is repeated
+        3 | 4 => (digits[i] * 17) as f64 / 255.0,
+        6 | 8 => (digits[2 * i] * 16 + digits[2 * i + 1]) as f64 / 255.0,
+        _ => 0.5,
+    };
+    [channel(0), channel(1), channel(2)]
+}
+
+pub const BACKGROUND_COLOR: &str = "black";
+// The inside of the puzzle, which shows through the gap partway through a turn
+pub const INTERIOR_COLOR: &str = "#202020";
+// Drawn over a face to shrink it towards its center, so the marker shows which way it faces
+const ORIENTATION_MARKER_COLOR: &str = "rgba(0, 0, 0, 0.4)";
+// How many pixels of the image one unit of the camera's plane covers
+const PIXELS_PER_UNIT: f64 = 1200.0;
+
+/// Something that can fill in shapes, in image coordinates (pixels from the top left)
+pub trait Renderer {
+    /// Fills the whole image, before anything else is drawn
+    fn fill_background(&mut self, color: &str);
+    fn fill_polygon(&mut self, points: &[(f64, f64)], color: &str);
+    fn fill_text(&mut self, text: &str, x: f64, y: f64, font_size: f64, color: &str);
+}
+
+/// A face as the camera sees it, relative to the center of the image
+pub struct SeenFace<'a> {
+    pub points: Vec<(f64, f64)>,
+    pub color: &'a str,
+    pub orientation_marker: bool,
+    pub distance_from_camera: f64,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Camera {
+    pub plane: Plane,
+    pub u_right: Vector3D,
+    pub u_up: Vector3D,
+    pub point: Vector3D,
+}
+
+impl Default for Camera {
+    /// Looking at the corner between the top and two of the sides
+    fn default() -> Self {
+        Camera::new_towards(Vector3D::new(4.0, 2.0, 2.0), Vector3D::zero())
+    }
+}
+
+impl Camera {
+    pub fn new_towards(camera_point: Vector3D, target: Vector3D) -> Camera {
+        let camera_to_target = target - &camera_point;
+        let u_camera_to_target = camera_to_target.to_unit_vector();
+        let u_z = Vector3D::new(0.0, 0.0, 1.0);
+        let u_right = u_camera_to_target.cross(&u_z);
+        let u_up = u_right.cross(&u_camera_to_target);
+        Camera {
+            plane: Plane {
+                normal: u_camera_to_target,
+                point: &camera_point + &u_camera_to_target,
+            },
+            u_right,
+            u_up,
+            point: camera_point,
+        }
+    }
+    /// Moves the camera around the origin, so the puzzle looks rotated the opposite way
+    pub fn rotate(&self, rotation: &Rotation3D) -> Camera {
+        Camera {
+            u_up: rotation.rotate_point_about_origin(&self.u_up),
+            u_right: rotation.rotate_point_about_origin(&self.u_right),
+            plane: Plane {
+                point: rotation.rotate_point_about_origin(&self.plane.point),
+                normal: rotation.rotate_point_about_origin(&self.plane.normal),
+            },
+            point: rotation.rotate_point_about_origin(&self.point),
+        }
+    }
+    // This returns an option in case the point is behind the camera
+    pub fn see_point(&self, point: Vector3D) -> Option<(f64, f64)> {
+        let ray_to_camera = point.ray_to(&self.point);
+        // Point is behind camera; ignore
+        if ray_to_camera.direction.dot(&self.plane.normal) >= 0.0 {
+            return None;
+        }
+        let camera_plane_intersection = self.plane.intersection(&ray_to_camera);
+        let point_in_camera_plane = &camera_plane_intersection - &self.point;
+        let point_x_in_camera = PIXELS_PER_UNIT * point_in_camera_plane.dot(&self.u_right);
+        let point_y_in_camera = PIXELS_PER_UNIT * point_in_camera_plane.dot(&-&self.u_up);
+
+        Some((point_x_in_camera, point_y_in_camera))
+    }
+    /// The ray from the camera through a point in the image (relative to the center),
+    /// which goes through everything that `see_point` puts at that point
+    pub fn ray_through(&self, (x, y): (f64, f64)) -> Ray {
+        // The inverse of see_point; u_right and u_up are perpendicular, but not always unit length
+        let right = x / PIXELS_PER_UNIT / self.u_right.dot(&self.u_right);
+        let up = -y / PIXELS_PER_UNIT / self.u_up.dot(&self.u_up);
+        Ray {
+            point: self.point,
+            direction: &(&self.plane.normal + &(right * self.u_right)) + &(up * self.u_up),
+        }
+    }
+    /// Projects the face, or returns None if the face points away from the camera
+    /// (so it's on the back of the puzzle) or is behind the camera
+    pub fn see_face<'a>(
+        &self,
+        face: &Face,
+        color: &'a str,
+        orientation_marker: bool,
+    ) -> Option<SeenFace<'a>> {
+        let center = Vector3D::from_average(&face.vertices);
+        if (self.point - &center).dot(&face.normal()) <= 0.0 {
+            return None;
+        }
+        let mut points = Vec::<(f64, f64)>::new();
+        let mut sum_dist = 0.0;
+        for &vertex in &face.vertices {
+            sum_dist += (vertex - &self.point).magnitude();
+            match self.see_point(vertex) {
+                Some(point) => points.push(point),
+                None => return None,
+            }
+        }
+        Some(SeenFace {
+            color,
+            orientation_marker,
+            points,
+            distance_from_camera: sum_dist / face.vertices.len() as f64,
+        })
+    }
+
+    /// The stickers that the camera can see, in the scheme's colors, sorted from the farthest
+    /// to the nearest so that drawing them in order paints the near ones over the far ones
+    pub fn see_faces<'a>(
+        &self,
+        faces: &[PieceFace],
+        color_scheme: &'a ColorScheme,
+    ) -> Vec<SeenFace<'a>> {
+        let mut seen_faces = faces
+            .iter()
+            .filter_map(|face| {
+                self.see_face(
+                    &face.face,
+                    color_scheme.color(face.color_index),
+                    face.orientation_marker,
+                )
+            })
+            .collect::<Vec<_>>();
+        seen_faces.sort_by(|a, b| {
+            b.distance_from_camera
+                .partial_cmp(&a.distance_from_camera)
+                .unwrap()
+        });
+        seen_faces
+    }
+}
+
+/// Draws the puzzle as the camera sees it, centered in an image of the given size.
+/// `turn` is the index of the turn that is in progress, and how far through it is (0 to 1).
+#[allow(clippy::too_many_arguments)]
+pub fn draw_puzzle(
+    renderer: &mut impl Renderer,
+    puzzle: &TwistyPuzzle,
+    puzzle_state: &PuzzleState,
+    color_scheme: &ColorScheme,
+    turn: Option<(usize, f64)>,
+    camera: &Camera,
+    width: f64,
+    height: f64,
+) {
+    renderer.fill_background(BACKGROUND_COLOR);
+    let center = (width / 2.0, height / 2.0);
+    let (turn_index, turn_progress) = match turn {
+        Some(turn) => turn,
+        None => {
+            let faces = puzzle.faces(puzzle_state);
+            draw_seen_faces(renderer, camera.see_faces(&faces, color_scheme), center);
+            return;
+        }
+    };
+
+    let (turning_faces, still_faces): (Vec<_>, Vec<_>) = puzzle
+        .get_physically_turned_faces(turn_index, puzzle_state, turn_progress)
+        .into_iter()
+        .partition(|face| face.is_turned_by(turn_index));
+    let mut turning_part = camera.see_faces(&turning_faces, color_scheme);
+    let mut still_part = camera.see_faces(&still_faces, color_scheme);
+
+    let mut camera_is_on_turning_side = false;
+    if let Some((cross_section, turning_cross_section)) =
+        puzzle.get_turn_cross_sections(turn_index, turn_progress)
+    {
+        let center = Vector3D::from_average(&cross_section.vertices);
+        camera_is_on_turning_side = (camera.point - &center).dot(&cross_section.normal()) > 0.0;
+
+        still_part.extend(camera.see_face(&cross_section, INTERIOR_COLOR, false));
+        turning_part.extend(camera.see_face(&turning_cross_section, INTERIOR_COLOR, false));
+    }
+
+    if camera_is_on_turning_side {
+        draw_seen_faces(renderer, still_part, center);
+        draw_seen_faces(renderer, turning_part, center);
+    } else {
+        draw_seen_faces(renderer, turning_part, center);
+        draw_seen_faces(renderer, still_part, center);
+    }
+}
+
+/// Fills in the faces in order, with their points relative to `center` in the image
+pub(crate) fn draw_seen_faces(
+    renderer: &mut impl Renderer,
+    seen_faces: Vec<SeenFace>,
+    (center_x, center_y): (f64, f64),
+) {
+    let to_image = |(x, y): (f64, f64)| (x + center_x, y + center_y);
+
+    for polygon in seen_faces {
+        let points: Vec<_> = polygon.points.iter().copied().map(to_image).collect();
+        renderer.fill_polygon(&points, polygon.color);
+
+        if polygon.orientation_marker {
+            let num_points = polygon.points.len() as f64;
+            let center_x = polygon.points.iter().map(|point| point.0).sum::<f64>() / num_points;
+            let center_y = polygon.points.iter().map(|point| point.1).sum::<f64>() / num_points;
+            let marker_points: Vec<_> = polygon
+                .points
+                .iter()
+                .map(|point| to_image(((point.0 + center_x) / 2.0, (point.1 + center_y) / 2.0)))
+                .collect();
+            renderer.fill_polygon(&marker_points, ORIENTATION_MARKER_COLOR);
+        }
+    }
+}
+
+/// Builds up an SVG document, which can be saved or compared without a browser
+pub struct SvgRenderer {
+    width: f64,
+    height: f64,
+    elements: String,
+}
+
+impl SvgRenderer {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            elements: String::new(),
+        }
+    }
+
+    pub fn to_svg(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n{}</svg>\n",
+            self.width, self.height, self.width, self.height, self.elements
+        )
+    }
+}
+
+impl Renderer for SvgRenderer {
+    fn fill_background(&mut self, color: &str) {
+        writeln!(
+            self.elements,
+            "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            self.width, self.height, color
+        )
+        .unwrap();
+    }
+
+    fn fill_polygon(&mut self, points: &[(f64, f64)], color: &str) {
+        let points: Vec<String> = points
+            .iter()
+            .map(|(x, y)| format!("{:.2},{:.2}", x, y))
+            .collect();
+        writeln!(
+            self.elements,
+            "<polygon points=\"{}\" fill=\"{}\"/>",
+            points.join(" "),
+            color
+        )
+        .unwrap();
+    }
+
+    fn fill_text(&mut self, text: &str, x: f64, y: f64, font_size: f64, color: &str) {
+        let text = text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        writeln!(
+            self.elements,
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"Arial\" font-size=\"{}\" fill=\"{}\">{}</text>",
+            x, y, font_size, color, text
+        )
+        .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use super::*;
+    use crate::puzzles;
+
+    #[test]
+    fn test_svg_2x2() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let state = puzzle.get_derived_state_turn_index(&puzzle.get_initial_state(), 0);
+        let mut renderer = SvgRenderer::new(400.0, 400.0);
+        draw_puzzle(
+            &mut renderer,
+            &puzzle,
+            &state,
+            &ColorScheme::default(),
+            None,
+            &Camera::default(),
+            400.0,
+            400.0,
+        );
+        renderer.fill_text("R & U", 10.0, 30.0, 20.0, WHITE);
+        assert_snapshot!(renderer.to_svg());
+    }
+
+    #[test]
+    fn test_svg_mid_turn() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let mut renderer = SvgRenderer::new(400.0, 400.0);
+        draw_puzzle(
+            &mut renderer,
+            &puzzle,
+            &puzzle.get_initial_state(),
+            &ColorScheme::default(),
+            Some((0, 0.3)),
+            &Camera::default(),
+            400.0,
+            400.0,
+        );
+        let svg = renderer.to_svg();
+        // Only the three faces of the cube that point towards the camera are drawn
+        // (four stickers each), along with the side of the cut that shows through the gap
+        assert_eq!(svg.matches("<polygon").count(), 4 * 3 + 1);
+        assert_eq!(svg.matches(INTERIOR_COLOR).count(), 1);
+        assert_snapshot!(svg);
+    }
+
+    #[test]
+    fn test_color_scheme() {
+        let color_scheme = ColorScheme::parse("white, #00f\n#ff5722ff").unwrap();
+        assert_eq!(color_scheme.color(1), "#00f");
+        assert_eq!(color_scheme.color(5), "#ff5722ff");
+        assert_eq!(ColorScheme::default().color(13), BLUE);
+        assert_eq!(
+            ColorScheme::parse("white,#12"),
+            Err(Error::InvalidColorScheme("invalid color #12".to_string()))
+        );
+        assert!(ColorScheme::parse("red\"/><script").is_err());
+        assert!(ColorScheme::parse(" ,").is_err());
+
+        assert_eq!(rgb(color_scheme.color(0)), [1.0, 1.0, 1.0]);
+        assert_eq!(rgb(color_scheme.color(1)), [0.0, 0.0, 1.0]);
+        assert_eq!(
+            rgb(color_scheme.color(2)),
+            [1.0, 87.0 / 255.0, 34.0 / 255.0]
+        );
+        assert_eq!(rgb("Green"), [0.0, 128.0 / 255.0, 0.0]);
+        assert_eq!(rgb("chartreuse"), [0.5; 3]);
+    }
+}
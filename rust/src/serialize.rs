@@ -0,0 +1,44 @@
+//! The file format for precomputed data, such as puzzles and metamoves.
+//! Files start with magic bytes and a format version,
+//! followed by the data encoded with bincode.
+//! Files with a different version are rejected rather than misread,
+//! so the version must be bumped whenever a serialized type changes.
+
+use std::io::{self, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const MAGIC: &[u8; 4] = b"TWPZ";
+pub const FORMAT_VERSION: u16 = 2;
+
+pub fn write<T: Serialize, W: Write>(writer: &mut W, value: &T) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    bincode::serialize_into(writer, value).map_err(|error| to_io_error(*error))
+}
+
+pub fn read<T: DeserializeOwned, R: Read>(reader: &mut R) -> io::Result<T> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a twisty puzzles data file"));
+    }
+    let mut version = [0; 2];
+    reader.read_exact(&mut version)?;
+    if u16::from_le_bytes(version) != FORMAT_VERSION {
+        return Err(invalid_data("unsupported data file version"));
+    }
+    bincode::deserialize_from(reader).map_err(|error| to_io_error(*error))
+}
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn to_io_error(error: bincode::ErrorKind) -> io::Error {
+    match error {
+        bincode::ErrorKind::Io(error) => error,
+        error => invalid_data(&error.to_string()),
+    }
+}
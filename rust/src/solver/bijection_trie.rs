@@ -11,7 +11,6 @@ struct TrieNode<T> {
 #[derive(Debug)]
 pub struct BijectionTrie<T> {
     root: TrieNode<T>,
-    len: usize,
 }
 
 impl<T: std::fmt::Debug + std::cmp::Ord> BijectionTrie<T> {
@@ -22,15 +21,9 @@ impl<T: std::fmt::Debug + std::cmp::Ord> BijectionTrie<T> {
                 children: vec![],
                 data: None,
             },
-            len: 0,
         }
     }
-    #[inline]
-    pub fn len(&self) -> usize {
-        self.len
-    }
     pub fn insert(&mut self, bijection: &Bijection, data: T) {
-        self.len += 1;
         let mut node = &mut self.root;
         for mapping in &bijection.0 {
             let found_child_index = node
@@ -50,6 +43,7 @@ impl<T: std::fmt::Debug + std::cmp::Ord> BijectionTrie<T> {
         }
         node.data = Some(data);
     }
+    #[cfg(test)]
     pub fn find_exact_bijection(&self, bijection: &Bijection) -> Option<&T> {
         let mut node = &self.root;
         for mapping in &bijection.0 {
@@ -201,15 +195,16 @@ mod tests {
         );
 
         // 5's do not appear
+        // (ties are returned in descending order of their data)
         assert_eq!(
             trie.find_most_similar(&Bijection(vec![5, 5, 5]))
                 .collect::<Vec<_>>(),
             vec![
-                (3, &"123"),
                 (3, &"291"),
                 (3, &"224"),
+                (3, &"223"),
                 (3, &"124"),
-                (3, &"223")
+                (3, &"123")
             ],
         );
     }
@@ -0,0 +1,269 @@
+//! A compact catalog of the metamoves that the phased solver needs for each puzzle,
+//! so that they don't need to be discovered at start-up.
+//! The catalog for the built-in puzzles is generated by the `build-catalog` binary
+//! and embedded into the crate.
+
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+
+use super::metamoves::MetaMove;
+use crate::bijection::Bijection;
+use crate::twisty_puzzle::TwistyPuzzle;
+
+const MAGIC: &[u8; 4] = b"TWMC";
+const VERSION: u16 = 1;
+
+static BUILTIN_CATALOG: &[u8] = include_bytes!("../../catalog/metamoves.bin");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    pub puzzles: Vec<PuzzleCatalog>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PuzzleCatalog {
+    pub name: String,
+    pub phases: Vec<CatalogPhase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogPhase {
+    // Indices into the puzzle's piece types
+    pub target_piece_type: usize,
+    pub preserve_piece_types: Vec<usize>,
+    pub three_cycle: CatalogMetaMove,
+    pub parity_flipper: Option<CatalogMetaMove>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogMetaMove {
+    pub turns: Vec<usize>,
+    pub num_affected_pieces: usize,
+    // Used to check that the turns still have the same effect on the puzzle
+    pub face_map_hash: u64,
+}
+
+impl CatalogMetaMove {
+    pub fn new(metamove: &MetaMove) -> Self {
+        Self {
+            turns: metamove.turns.clone(),
+            num_affected_pieces: metamove.num_affected_pieces,
+            face_map_hash: hash_face_map(&metamove.face_map),
+        }
+    }
+
+    /// Rebuilds the metamove for the puzzle,
+    /// or returns None if it no longer matches the puzzle's face maps
+    pub fn to_metamove(&self, puzzle: &Rc<TwistyPuzzle>) -> Option<MetaMove> {
+        if self.turns.iter().any(|&t| t >= puzzle.turn_names.len()) {
+            return None;
+        }
+        let metamove = MetaMove::new_infer_face_map(Rc::clone(puzzle), self.turns.clone());
+        if metamove.num_affected_pieces != self.num_affected_pieces
+            || hash_face_map(&metamove.face_map) != self.face_map_hash
+        {
+            return None;
+        }
+        Some(metamove)
+    }
+}
+
+/// FNV-1a, which (unlike the std hasher) is the same on every platform and version
+fn hash_face_map(face_map: &Bijection) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &face_index in &face_map.0 {
+        for byte in (face_index as u32).to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+impl Catalog {
+    /// The catalog of the built-in puzzles, generated by `build-catalog`
+    pub fn builtin() -> Self {
+        Self::read(&mut &BUILTIN_CATALOG[..]).expect("built-in metamove catalog is invalid")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PuzzleCatalog> {
+        self.puzzles.iter().find(|puzzle| puzzle.name == name)
+    }
+
+    /// Writes the catalog with one byte for each turn index and piece type index,
+    /// since no puzzle has more than 255 of either
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        write_u8(writer, self.puzzles.len())?;
+        for puzzle in &self.puzzles {
+            write_u8(writer, puzzle.name.len())?;
+            writer.write_all(puzzle.name.as_bytes())?;
+            write_u8(writer, puzzle.phases.len())?;
+            for phase in &puzzle.phases {
+                write_u8(writer, phase.target_piece_type)?;
+                write_u8(writer, phase.preserve_piece_types.len())?;
+                for &piece_type in &phase.preserve_piece_types {
+                    write_u8(writer, piece_type)?;
+                }
+                write_metamove(writer, &phase.three_cycle)?;
+                match &phase.parity_flipper {
+                    Some(parity_flipper) => {
+                        write_u8(writer, 1)?;
+                        write_metamove(writer, parity_flipper)?;
+                    }
+                    None => write_u8(writer, 0)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a metamove catalog"));
+        }
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        if u16::from_le_bytes(version) != VERSION {
+            return Err(invalid_data("unsupported metamove catalog version"));
+        }
+        let num_puzzles = read_u8(reader)?;
+        let mut puzzles = Vec::with_capacity(num_puzzles);
+        for _ in 0..num_puzzles {
+            let mut name = vec![0; read_u8(reader)?];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|_| invalid_data("invalid puzzle name"))?;
+            let num_phases = read_u8(reader)?;
+            let mut phases = Vec::with_capacity(num_phases);
+            for _ in 0..num_phases {
+                let target_piece_type = read_u8(reader)?;
+                let num_preserve_piece_types = read_u8(reader)?;
+                let preserve_piece_types = (0..num_preserve_piece_types)
+                    .map(|_| read_u8(reader))
+                    .collect::<io::Result<_>>()?;
+                let three_cycle = read_metamove(reader)?;
+                let parity_flipper = match read_u8(reader)? {
+                    0 => None,
+                    _ => Some(read_metamove(reader)?),
+                };
+                phases.push(CatalogPhase {
+                    target_piece_type,
+                    preserve_piece_types,
+                    three_cycle,
+                    parity_flipper,
+                });
+            }
+            puzzles.push(PuzzleCatalog { name, phases });
+        }
+        Ok(Self { puzzles })
+    }
+}
+
+fn write_metamove<W: Write>(writer: &mut W, metamove: &CatalogMetaMove) -> io::Result<()> {
+    let num_turns =
+        u16::try_from(metamove.turns.len()).map_err(|_| invalid_data("metamove is too long"))?;
+    writer.write_all(&num_turns.to_le_bytes())?;
+    for &turn_index in &metamove.turns {
+        write_u8(writer, turn_index)?;
+    }
+    write_u8(writer, metamove.num_affected_pieces)?;
+    writer.write_all(&metamove.face_map_hash.to_le_bytes())
+}
+
+fn read_metamove<R: Read>(reader: &mut R) -> io::Result<CatalogMetaMove> {
+    let mut num_turns = [0; 2];
+    reader.read_exact(&mut num_turns)?;
+    let turns = (0..u16::from_le_bytes(num_turns))
+        .map(|_| read_u8(reader))
+        .collect::<io::Result<_>>()?;
+    let num_affected_pieces = read_u8(reader)?;
+    let mut face_map_hash = [0; 8];
+    reader.read_exact(&mut face_map_hash)?;
+    Ok(CatalogMetaMove {
+        turns,
+        num_affected_pieces,
+        face_map_hash: u64::from_le_bytes(face_map_hash),
+    })
+}
+
+fn write_u8<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    let value = u8::try_from(value).map_err(|_| invalid_data("value does not fit in a u8"))?;
+    writer.write_all(&[value])
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0] as usize)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles;
+
+    #[test]
+    fn test_catalog() {
+        let puzzle = Rc::new(puzzles::rubiks_cube_3x3());
+        let three_cycle = MetaMove::new_infer_face_map(Rc::clone(&puzzle), vec![0, 2, 1, 3]);
+        let catalog = Catalog {
+            puzzles: vec![PuzzleCatalog {
+                name: "3x3".to_string(),
+                phases: vec![CatalogPhase {
+                    target_piece_type: 1,
+                    preserve_piece_types: vec![0],
+                    three_cycle: CatalogMetaMove::new(&three_cycle),
+                    parity_flipper: None,
+                }],
+            }],
+        };
+        let mut bytes = vec![];
+        catalog.write(&mut bytes).unwrap();
+        let loaded = Catalog::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(loaded, catalog);
+
+        let phase = &loaded.get("3x3").unwrap().phases[0];
+        assert_eq!(phase.three_cycle.to_metamove(&puzzle), Some(three_cycle));
+        // The same turns do something else on a different puzzle
+        assert_eq!(
+            phase
+                .three_cycle
+                .to_metamove(&Rc::new(puzzles::rubiks_supercube_3x3())),
+            None
+        );
+        assert!(loaded.get("megaminx").is_none());
+    }
+
+    #[test]
+    fn test_builtin_catalog() {
+        let catalog = Catalog::builtin();
+        for puzzle_catalog in &catalog.puzzles {
+            let puzzle = Rc::new(puzzles::by_name(&puzzle_catalog.name).unwrap());
+            for phase in &puzzle_catalog.phases {
+                assert!(phase.three_cycle.to_metamove(&puzzle).is_some());
+                if let Some(parity_flipper) = &phase.parity_flipper {
+                    assert!(parity_flipper.to_metamove(&puzzle).is_some());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_builtin_catalog_has_deployed_puzzles() {
+        let catalog = Catalog::builtin();
+        for name in puzzles::DEPLOYED_PUZZLES {
+            assert!(
+                catalog.get(name).is_some(),
+                "{} is not in the catalog",
+                name
+            );
+        }
+    }
+}
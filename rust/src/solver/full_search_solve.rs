@@ -1,8 +1,9 @@
 use std::{collections::VecDeque, rc::Rc};
 
+use crate::error::Result;
 use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
 
-use super::ScrambleSolver;
+use super::{ScrambleSolver, StateScorer};
 
 pub struct FullSearchSolver {
     puzzle: Rc<TwistyPuzzle>,
@@ -13,31 +14,33 @@ pub struct FullSearchSolver {
 #[derive(Clone)]
 pub struct FullSearchSolverOpts {
     pub depth: usize,
+    pub scorer: Rc<dyn StateScorer>,
 }
 
 impl ScrambleSolver for FullSearchSolver {
     type Opts = FullSearchSolverOpts;
 
-    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Self {
+    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Result<Self> {
+        puzzle.validate_state(&initial_state)?;
+        opts.scorer.check_puzzle(&puzzle)?;
         let num_turns = puzzle.turn_names_iter().count();
         let mut fringe_stack_max_size = opts.depth + 1;
         let mut fringe_stack: Vec<StateToExpand> = vec![StateToExpand {
             puzzle_state: initial_state.clone(),
             turn_index: 0,
         }];
-        let solved_score = puzzle.get_num_pieces();
 
         let mut best = BestSolution {
             num_moves: 0,
-            score: puzzle.get_num_solved_pieces(&initial_state),
+            score: opts.scorer.score(&puzzle, &initial_state),
             turns: vec![],
         };
-        if best.score == solved_score {
-            return Self {
+        if puzzle.is_solved(&initial_state) {
+            return Ok(Self {
                 solution: VecDeque::new(),
                 state: initial_state,
                 puzzle,
-            };
+            });
         }
 
         while let Some(state_to_expand) = fringe_stack.last() {
@@ -46,7 +49,7 @@ impl ScrambleSolver for FullSearchSolver {
                     &state_to_expand.puzzle_state,
                     state_to_expand.turn_index,
                 );
-                let score = puzzle.get_num_solved_pieces(&derived_state);
+                let score = opts.scorer.score(&puzzle, &derived_state);
                 let num_moves = fringe_stack.len();
                 if score > best.score || (score == best.score && num_moves < best.num_moves) {
                     best = BestSolution {
@@ -55,7 +58,7 @@ impl ScrambleSolver for FullSearchSolver {
                         turns: fringe_stack.iter().map(|t| t.turn_index).collect(),
                     }
                 }
-                if score == solved_score {
+                if puzzle.is_solved(&derived_state) {
                     fringe_stack_max_size = fringe_stack.len();
                 }
                 fringe_stack.push(StateToExpand {
@@ -76,11 +79,11 @@ impl ScrambleSolver for FullSearchSolver {
 
         let solution: VecDeque<_> = best.turns.into();
 
-        Self {
+        Ok(Self {
             solution,
             state: initial_state,
             puzzle,
-        }
+        })
     }
 
     fn get_state(&self) -> &PuzzleState {
@@ -107,6 +110,6 @@ struct StateToExpand {
 #[derive(Debug, Clone)]
 struct BestSolution {
     num_moves: usize,
-    score: usize,
+    score: f64,
     turns: Vec<usize>,
 }
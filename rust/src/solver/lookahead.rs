@@ -1,8 +1,9 @@
 use std::rc::Rc;
 
+use crate::error::Result;
 use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
 
-use super::ScrambleSolver;
+use super::{ScrambleSolver, StateScorer};
 
 pub struct LookaheadSolver {
     state: PuzzleState,
@@ -14,18 +15,21 @@ pub struct LookaheadSolver {
 #[derive(Clone)]
 pub struct LookaheadSolverOpts {
     pub depth: usize,
+    pub scorer: Rc<dyn StateScorer>,
 }
 
 impl ScrambleSolver for LookaheadSolver {
     type Opts = LookaheadSolverOpts;
 
-    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Self {
-        Self {
+    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Result<Self> {
+        puzzle.validate_state(&initial_state)?;
+        opts.scorer.check_puzzle(&puzzle)?;
+        Ok(Self {
             state: initial_state,
             turns: puzzle.turn_names_iter().cloned().collect(),
             puzzle,
             opts,
-        }
+        })
     }
 
     fn get_state(&self) -> &PuzzleState {
@@ -39,14 +43,13 @@ impl Iterator for LookaheadSolver {
     fn next(&mut self) -> Option<Self::Item> {
         let initial_state = StateWithScore {
             puzzle_state: self.state.clone(),
-            score: self.puzzle.get_num_solved_pieces(&self.state),
+            score: self.opts.scorer.score(&self.puzzle, &self.state),
             initial_turn: None,
             most_recent_turn: None,
         };
         let mut fringe: Vec<StateWithScore> = vec![initial_state.clone()];
-        let solved_score = self.puzzle.get_num_pieces();
 
-        if initial_state.score == solved_score {
+        if self.puzzle.is_solved(&self.state) {
             return None;
         }
 
@@ -73,7 +76,8 @@ impl Iterator for LookaheadSolver {
                     let new_state = self
                         .puzzle
                         .get_derived_state_turn_index(&state.puzzle_state, turn_index);
-                    let new_score = self.puzzle.get_num_solved_pieces(&new_state);
+                    let new_score = self.opts.scorer.score(&self.puzzle, &new_state);
+                    let is_solved = self.puzzle.is_solved(&new_state);
                     let new_state_with_score = StateWithScore {
                         initial_turn: match state.initial_turn {
                             None => Some(turn_index),
@@ -83,7 +87,7 @@ impl Iterator for LookaheadSolver {
                         puzzle_state: new_state,
                         score: new_score,
                     };
-                    if new_score == solved_score {
+                    if is_solved {
                         return new_state_with_score.initial_turn;
                     }
                     if new_score > best.score {
@@ -104,7 +108,7 @@ impl Iterator for LookaheadSolver {
 #[derive(Debug, Clone)]
 struct StateWithScore {
     puzzle_state: PuzzleState,
-    score: usize,
+    score: f64,
     initial_turn: Option<usize>,
     most_recent_turn: Option<usize>,
 }
@@ -1,35 +1,27 @@
 use super::{
+    catalog::{CatalogMetaMove, CatalogPhase, PuzzleCatalog},
     metamoves::{discover_metamoves, MetaMove},
-    ScrambleSolver,
+    ScrambleSolver, StateScorer,
 };
 use crate::{
+    error::{Error, Result},
     solver::bijection_trie::BijectionTrie,
     traverse_combinations::{traverse_combinations, TraverseResult},
     twisty_puzzle::{PieceType, PuzzleState, TwistyPuzzle},
 };
 use std::{
-    collections::{hash_map::Entry, HashMap, VecDeque},
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
     rc::Rc,
 };
 
 pub struct MetaMovePhasedSolver {
     puzzle: Rc<TwistyPuzzle>,
     state: PuzzleState,
-    depth: usize,
     solve_phases: Vec<SolvePhase>,
     current_phase: usize,
     queued_turns: VecDeque<usize>,
-}
-
-macro_rules! console_log {
-    ($($t:tt)*) => {
-        #[cfg(target_arch = "wasm32")] {
-            web_sys::console::log_1(&format!($($t)*).into());
-        }
-        #[cfg(not(target_arch = "wasm32"))] {
-            println!($($t)*);
-        }
-    };
+    scorer: Option<Rc<dyn StateScorer>>,
 }
 
 /// Checks that the metamove preserves the required "preserve piece types"
@@ -50,21 +42,32 @@ fn metamove_preserves(metamove: &MetaMove, preserve_piece_types: &[&PieceType])
     true
 }
 
+/// Counts the solved pieces of a single type,
+/// which is how phases score their metamoves unless given another scorer
+struct SolvedPiecesOfTypeScorer<'a>(&'a PieceType);
+
+impl StateScorer for SolvedPiecesOfTypeScorer<'_> {
+    fn score(&self, puzzle: &TwistyPuzzle, puzzle_state: &PuzzleState) -> f64 {
+        puzzle.get_num_solved_pieces_of_type(puzzle_state, self.0) as f64
+    }
+}
+
 struct SolvePhase {
     puzzle: Rc<TwistyPuzzle>,
     three_cycle: MetaMove,
     parity_flipper: Option<MetaMove>,
     target_piece_type: PieceType,
-    preserve_piece_types: Vec<PieceType>,
 }
 impl SolvePhase {
+    /// Finds the metamoves for solving the target piece type,
+    /// or None if the metamoves that preserve the solved piece types never move it
     #[inline]
     pub fn new(
         base_metamoves: &[MetaMove],
         target_piece_type: &PieceType,
         preserve_piece_types: &[&PieceType],
         solve_parity: bool,
-    ) -> Option<Self> {
+    ) -> Result<Option<Self>> {
         let puzzle = Rc::clone(&base_metamoves[0].puzzle);
         console_log!(
             "puzzle face types {:#?}",
@@ -88,6 +91,28 @@ impl SolvePhase {
                 solve_parity,
             );
         }
+        if !preserve_piece_types.is_empty() {
+            let three_cycle = match find_preserving_three_cycle(
+                base_metamoves,
+                target_piece_type,
+                preserve_piece_types,
+            ) {
+                PreservingThreeCycle::Found(three_cycle) => three_cycle,
+                PreservingThreeCycle::TargetNeverMoved => return Ok(None),
+                PreservingThreeCycle::NotFound => {
+                    return Err(Error::UnsolvablePhase(
+                        "no three-cycle found that preserves the solved piece types".to_string(),
+                    ))
+                }
+            };
+            console_log!("three cycle: {:#?}", three_cycle);
+            return Ok(Some(SolvePhase {
+                puzzle,
+                three_cycle,
+                parity_flipper: None,
+                target_piece_type: target_piece_type.clone(),
+            }));
+        }
         console_log!("Build trie");
         let mut trie = BijectionTrie::new();
 
@@ -104,7 +129,6 @@ impl SolvePhase {
         let target_piece_types = [target_piece_type];
         for initial in base_metamoves {
             console_log!("initial {initial:#?}");
-            // console_log!("trie size: {}", trie.len());
             // console_log!(
             //     "matches: {:#?}",
             //     trie.find_most_similar(&initial.face_map)
@@ -162,21 +186,26 @@ impl SolvePhase {
             }
         }
 
-        Some(SolvePhase {
+        let three_cycle = three_cycle.ok_or_else(|| {
+            Error::UnsolvablePhase("no three-cycle found for the target piece type".to_string())
+        })?;
+
+        Ok(Some(SolvePhase {
             puzzle,
-            three_cycle: three_cycle?,
+            three_cycle,
             parity_flipper,
             target_piece_type: target_piece_type.clone(),
-            preserve_piece_types: preserve_piece_types.iter().cloned().cloned().collect(),
-        })
+        }))
     }
 
-    fn next(&self, state: &PuzzleState) -> MetaMove {
+    /// Finds a conjugate of the three-cycle that the scorer rates higher than the state,
+    /// or an empty metamove if there is none
+    fn next(&self, state: &PuzzleState, scorer: &dyn StateScorer) -> MetaMove {
         let puzzle = &self.puzzle;
         let mut best_metamove = MetaMove::empty(Rc::clone(puzzle));
-        let solved_of_type = puzzle.get_num_solved_pieces_of_type(state, &self.target_piece_type);
-        let unsolved_of_type = puzzle.get_num_pieces_of_type(&self.target_piece_type)
-            - puzzle.get_num_solved_pieces_of_type(state, &self.target_piece_type);
+        let num_of_type = puzzle.get_num_pieces_of_type(&self.target_piece_type);
+        let unsolved_of_type =
+            num_of_type - puzzle.get_num_solved_pieces_of_type(state, &self.target_piece_type);
 
         console_log!("unsolved_of_type: {}", unsolved_of_type);
         // Even parity; apply parity fix
@@ -198,7 +227,7 @@ impl SolvePhase {
             })
             .collect();
 
-        let mut best_score = solved_of_type;
+        let mut best_score = scorer.score(puzzle, state);
 
         traverse_combinations(
             &individual_turns_metamoves,
@@ -213,8 +242,7 @@ impl SolvePhase {
                 let new_mm = mm.apply(&self.three_cycle).apply(&mm.invert());
                 // Try both possibilities, A B A' or B A B'
                 let next_state = puzzle.get_derived_state(state, &new_mm.face_map);
-                let next_state_score =
-                    puzzle.get_num_solved_pieces_of_type(&next_state, &self.target_piece_type);
+                let next_state_score = scorer.score(puzzle, &next_state);
                 if next_state_score > best_score {
                     best_metamove = new_mm;
                     best_score = next_state_score;
@@ -222,7 +250,9 @@ impl SolvePhase {
                     // Stop once we find _anything_ better, not the best one
                     // return TraverseResult::Break;
                 }
-                if next_state_score == puzzle.get_num_pieces_of_type(&self.target_piece_type) {
+                if puzzle.get_num_solved_pieces_of_type(&next_state, &self.target_piece_type)
+                    == num_of_type
+                {
                     return TraverseResult::Break;
                 }
                 TraverseResult::Continue
@@ -233,62 +263,237 @@ impl SolvePhase {
     }
 }
 
+#[derive(Clone, Default)]
+pub struct MetaMovePhasedSolverOpts {
+    // Precomputed metamoves for the puzzle, to skip discovering them at start-up
+    pub catalog: Option<Rc<PuzzleCatalog>>,
+    // Scores the metamoves each phase picks from,
+    // or None to count the solved pieces of the phase's target type
+    pub scorer: Option<Rc<dyn StateScorer>>,
+}
+
+/// Discovers the metamoves for each phase of the solve.
+/// Each phase solves one type of piece, without disturbing the types solved in earlier phases.
+/// Piece types with only one piece (like 3x3 centers) can't move, so they don't need a phase,
+/// and neither do pieces that only ever move along with the types solved before them
+/// (like starminx centers, which are back in place once everything around them is).
+pub fn discover_phase_catalog(puzzle: &Rc<TwistyPuzzle>) -> Result<Vec<CatalogPhase>> {
+    let is_moved_by_turns = |piece_type: &PieceType| {
+        puzzle.turns.iter().any(|turn| {
+            (0..puzzle.get_num_faces()).any(|face_index| {
+                piece_type.face_mask()[face_index] && turn.face_map.0[face_index] != face_index
+            })
+        })
+    };
+    let movable_piece_types: Vec<usize> = (0..puzzle.piece_types.len())
+        .rev()
+        .filter(|&i| {
+            let piece_type = &puzzle.piece_types[i];
+            puzzle.get_num_pieces_of_type(piece_type) > 1 && is_moved_by_turns(piece_type)
+        })
+        .collect();
+
+    console_log!("Phases for piece types {:?}", movable_piece_types);
+    console_log!("Initial traverse");
+    let metamoves = discover_metamoves(Rc::clone(puzzle), |_mm| true, 4);
+
+    let phases: Vec<Option<CatalogPhase>> = movable_piece_types
+        .iter()
+        .enumerate()
+        .map(|(phase_index, &target_piece_type)| {
+            let preserve_piece_types = &movable_piece_types[..phase_index];
+            let phase = SolvePhase::new(
+                &metamoves,
+                &puzzle.piece_types[target_piece_type],
+                &preserve_piece_types
+                    .iter()
+                    .map(|&i| &puzzle.piece_types[i])
+                    .collect::<Vec<_>>(),
+                // Only the first phase has parity to fix,
+                // since later phases can't use the pieces of earlier phases to fix it
+                phase_index == 0 && can_have_parity(puzzle, &puzzle.piece_types[target_piece_type]),
+            )?;
+            Ok(phase.map(|phase| CatalogPhase {
+                target_piece_type,
+                preserve_piece_types: preserve_piece_types.to_vec(),
+                three_cycle: CatalogMetaMove::new(&phase.three_cycle),
+                parity_flipper: phase.parity_flipper.as_ref().map(CatalogMetaMove::new),
+            }))
+        })
+        .collect::<Result<_>>()?;
+    Ok(phases.into_iter().flatten().collect())
+}
+
+enum PreservingThreeCycle {
+    Found(MetaMove),
+    /// None of the metamoves that preserve the solved piece types move the target pieces,
+    /// so they are solved along with them
+    TargetNeverMoved,
+    NotFound,
+}
+
+/// Pairs up metamoves that do the same thing to the preserved piece types, since doing one and
+/// then undoing the other puts those pieces back where they were. This finds commutators like
+/// the ones that cycle megaminx corners without moving its edges, which matching metamoves by
+/// what they do to the target piece type almost never finds.
+fn find_preserving_three_cycle(
+    base_metamoves: &[MetaMove],
+    target_piece_type: &PieceType,
+    preserve_piece_types: &[&PieceType],
+) -> PreservingThreeCycle {
+    let mut preserve_mask = vec![false; target_piece_type.face_mask().len()];
+    for piece_type in preserve_piece_types {
+        for (is_preserved, &is_of_type) in preserve_mask.iter_mut().zip(piece_type.face_mask()) {
+            *is_preserved |= is_of_type;
+        }
+    }
+    let target_piece_types = [target_piece_type];
+    let puzzle = &base_metamoves[0].puzzle;
+    let single_turns: Vec<MetaMove> = (0..puzzle.turns.len())
+        .map(|turn_index| MetaMove::new_infer_face_map(Rc::clone(puzzle), vec![turn_index]))
+        .collect();
+    // Keyed by a hash of what the metamoves do to the preserved pieces,
+    // since keeping a copy of each masked face map would take too much memory
+    let mut groups: HashMap<u64, Vec<&MetaMove>> = HashMap::new();
+    let mut target_moved = false;
+    for metamove in base_metamoves {
+        let mut hasher = DefaultHasher::new();
+        metamove.face_map.mask(&preserve_mask).hash(&mut hasher);
+        let group = groups.entry(hasher.finish()).or_default();
+        for other in group.iter() {
+            let combined = other.apply(&metamove.invert());
+            // Hashes can collide, so the preserved pieces are checked again
+            let num_affected = combined.get_num_affected_pieces_of_types(&target_piece_types);
+            if num_affected == 0 || !metamove_preserves(&combined, preserve_piece_types) {
+                continue;
+            }
+            target_moved = true;
+            if num_affected == 3 {
+                return PreservingThreeCycle::Found(combined);
+            }
+            // A commutator with a turn preserves them too (the turn is undone while the combined
+            // metamove leaves them alone), and only moves the target pieces that both move
+            let commutator = single_turns.iter().chain(base_metamoves).find_map(|turn| {
+                let commutator = combined
+                    .apply(turn)
+                    .apply(&combined.invert())
+                    .apply(&turn.invert());
+                (commutator.get_num_affected_pieces_of_types(&target_piece_types) == 3)
+                    .then_some(commutator)
+            });
+            if let Some(commutator) = commutator {
+                return PreservingThreeCycle::Found(commutator);
+            }
+        }
+        group.push(metamove);
+    }
+    if target_moved {
+        PreservingThreeCycle::NotFound
+    } else {
+        PreservingThreeCycle::TargetNeverMoved
+    }
+}
+
+/// Whether any turn moves the pieces of the type in an odd permutation, which is the only way
+/// to be left with two of them swapped. Megaminx turns are all 5-cycles, for example, so there is
+/// no parity flipper to find (and looking for one would try every metamove).
+fn can_have_parity(puzzle: &TwistyPuzzle, piece_type: &PieceType) -> bool {
+    let mut face_pieces = vec![0; puzzle.get_num_faces()];
+    for (piece_index, piece) in puzzle.pieces().iter().enumerate() {
+        for &face_index in piece {
+            face_pieces[face_index] = piece_index;
+        }
+    }
+    let pieces_of_type: Vec<usize> = (0..puzzle.get_num_pieces())
+        .filter(|&piece_index| piece_type.face_mask()[puzzle.pieces()[piece_index][0]])
+        .collect();
+    puzzle.turns.iter().any(|turn| {
+        // Where each piece of the type goes, going by its first face
+        let piece_map: HashMap<usize, usize> = pieces_of_type
+            .iter()
+            .map(|&piece_index| {
+                let face_index = puzzle.pieces()[piece_index][0];
+                (piece_index, face_pieces[turn.face_map.0[face_index]])
+            })
+            .collect();
+        // A permutation is odd when it has an odd number of cycles of even length
+        let mut visited = vec![false; puzzle.get_num_pieces()];
+        let mut num_even_cycles = 0;
+        for &start in &pieces_of_type {
+            let mut cycle_length = 0;
+            let mut piece_index = start;
+            while !visited[piece_index] {
+                visited[piece_index] = true;
+                piece_index = piece_map[&piece_index];
+                cycle_length += 1;
+            }
+            if cycle_length > 0 && cycle_length % 2 == 0 {
+                num_even_cycles += 1;
+            }
+        }
+        num_even_cycles % 2 == 1
+    })
+}
+
+/// Rebuilds the phases from their catalog entries,
+/// or returns None if any of them doesn't match the puzzle
+fn phases_from_catalog(
+    puzzle: &Rc<TwistyPuzzle>,
+    catalog_phases: &[CatalogPhase],
+) -> Option<Vec<SolvePhase>> {
+    catalog_phases
+        .iter()
+        .map(|phase| {
+            let piece_type = |i: usize| puzzle.piece_types.get(i).cloned();
+            Some(SolvePhase {
+                puzzle: Rc::clone(puzzle),
+                three_cycle: phase.three_cycle.to_metamove(puzzle)?,
+                parity_flipper: match &phase.parity_flipper {
+                    Some(parity_flipper) => Some(parity_flipper.to_metamove(puzzle)?),
+                    None => None,
+                },
+                target_piece_type: piece_type(phase.target_piece_type)?,
+            })
+        })
+        .collect()
+}
+
 impl ScrambleSolver for MetaMovePhasedSolver {
-    type Opts = ();
-
-    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, _opts: Self::Opts) -> Self {
-        let edges = &puzzle.piece_types[0];
-        let corners = &puzzle.piece_types[1];
-
-        // let turn_num_affected_pieces =
-        //     MetaMove::new_infer_face_map(Rc::clone(&puzzle), vec![0]).num_affected_pieces;
-        console_log!("Initial traverse");
-        let metamoves = discover_metamoves(Rc::clone(&puzzle), |_mm| true, 4);
-        // TODO: delete
-        // SolvePhase::new(&metamoves, corners, &[edges], false);
-        let solve_phases = vec![
-            SolvePhase::new(&metamoves, corners, &[], true).unwrap(),
-            SolvePhase::new(&metamoves, edges, &[corners], false).unwrap(),
-        ];
-        // console_log!("Discovering metamoves affecting edges but not corners");
-        // discover_three_cycle(&metamoves, edges, &[corners]);
-        // console_log!("Discovering metamoves affecting corners (ignoring edges)");
-        // discover_three_cycle(&metamoves, corners, &[]);
-        // console_log!("Discovering metamoves affecting corners but not edges");
-        // discover_three_cycle(&metamoves, corners, &[edges]);
-        console_log!("Done");
-
-        // console_log!("num metamoves: {}", metamoves.len());
-        // let best = metamoves.iter().min().unwrap();
-        // console_log!(
-        //     "best metamove: {} turns affecting {} pieces",
-        //     best.turns.len(),
-        //     best.num_affected_pieces
-        // );
-
-        // let metamoves: Vec<_> = combine_metamoves(
-        //     Rc::clone(&puzzle),
-        //     |mm| {
-        //         let derived_state =
-        //             puzzle.get_derived_state(&puzzle.get_initial_state(), &mm.face_map);
-        //         let pieces_outside_type =
-        //             puzzle.get_num_pieces() - puzzle.get_num_pieces_of_type(piece_type);
-        //         let solved_pieces_outside_type = puzzle.get_num_solved_pieces(&derived_state)
-        //             - puzzle.get_num_solved_pieces_of_type(&derived_state, piece_type);
-        //         pieces_outside_type == solved_pieces_outside_type
-        //     },
-        //     &metamoves,
-        //     3,
-        // );
-
-        Self {
-            depth: 2,
+    type Opts = MetaMovePhasedSolverOpts;
+
+    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Result<Self> {
+        puzzle.validate_state(&initial_state)?;
+        if let Some(scorer) = &opts.scorer {
+            scorer.check_puzzle(&puzzle)?;
+        }
+        let cached_phases = opts.catalog.and_then(|catalog| {
+            let phases = phases_from_catalog(&puzzle, &catalog.phases);
+            if phases.is_none() {
+                console_log!("Catalog does not match the puzzle, discovering metamoves instead");
+            }
+            phases
+        });
+        let solve_phases = match cached_phases {
+            Some(solve_phases) => solve_phases,
+            None => {
+                let catalog_phases = discover_phase_catalog(&puzzle)?;
+                phases_from_catalog(&puzzle, &catalog_phases).ok_or(Error::CatalogMismatch)?
+            }
+        };
+        if solve_phases.is_empty() {
+            return Err(Error::UnsolvablePhase(
+                "there are no piece types to solve".to_string(),
+            ));
+        }
+
+        Ok(Self {
             puzzle,
             solve_phases,
             current_phase: 0,
             state: initial_state,
             queued_turns: VecDeque::new(),
-        }
+            scorer: opts.scorer,
+        })
     }
 
     fn get_state(&self) -> &PuzzleState {
@@ -309,7 +514,13 @@ impl Iterator for MetaMovePhasedSolver {
         }
 
         let current_phase = &self.solve_phases[self.current_phase];
-        let best_metamove = current_phase.next(&self.state);
+        let best_metamove = match &self.scorer {
+            Some(scorer) => current_phase.next(&self.state, scorer.as_ref()),
+            None => current_phase.next(
+                &self.state,
+                &SolvedPiecesOfTypeScorer(&current_phase.target_piece_type),
+            ),
+        };
         console_log!("adding metamove with {} turns", best_metamove.turns.len());
         if best_metamove.turns.is_empty() && self.current_phase < self.solve_phases.len() - 1 {
             let num_solved = self
@@ -337,74 +548,67 @@ impl Iterator for MetaMovePhasedSolver {
     }
 }
 
-fn find_best_metamove(
-    puzzle: Rc<TwistyPuzzle>,
-    state: &PuzzleState,
-    metamoves: &[MetaMove],
-    piece_type: &PieceType,
-    depth: usize,
-) -> MetaMove {
-    let mut best_metamove = MetaMove::empty(Rc::clone(&puzzle));
-    let mut best_score = puzzle.get_num_solved_pieces_of_type(state, piece_type);
-
-    traverse_combinations(
-        metamoves,
-        depth,
-        MetaMove::empty(Rc::clone(&puzzle)),
-        |previous_metamove: &MetaMove, new_metamove: &MetaMove| {
-            previous_metamove.apply(new_metamove)
-        },
-        &mut |mm| {
-            let next_state = puzzle.get_derived_state(state, &mm.face_map);
-            let next_state_score = puzzle.get_num_solved_pieces_of_type(&next_state, piece_type);
-            if next_state_score > best_score {
-                best_metamove = mm.clone();
-                best_score = next_state_score;
-                // Stop once we find _anything_ better, not the best one
-                return TraverseResult::Break;
-            }
-            if next_state_score == puzzle.get_num_pieces_of_type(piece_type) {
-                return TraverseResult::Break;
-            }
-            TraverseResult::Continue
-        },
-    );
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
 
-    best_metamove
-}
+    use super::*;
+    use crate::{puzzles, solver::catalog::Catalog};
 
-fn filter_duplicates(metamoves: Vec<MetaMove>) -> Vec<MetaMove> {
-    let mut metamoves_reduced = HashMap::new();
-    for mm in metamoves {
-        let entry = metamoves_reduced.entry(mm.face_map.clone());
+    /// Rates states higher the fewer pieces are solved
+    struct UnsolvedPiecesScorer;
 
-        match entry {
-            Entry::Vacant(entry) => {
-                entry.insert(mm);
-            }
-            Entry::Occupied(mut entry) => {
-                if entry.get().turns.len() > mm.turns.len() {
-                    entry.insert(mm);
-                }
-            }
+    impl StateScorer for UnsolvedPiecesScorer {
+        fn score(&self, puzzle: &TwistyPuzzle, puzzle_state: &PuzzleState) -> f64 {
+            -(puzzle.get_num_solved_pieces(puzzle_state) as f64)
         }
     }
 
-    metamoves_reduced.into_values().collect()
-}
-
-#[cfg(test)]
-mod tests {
-    use rand::SeedableRng;
-    use rand_chacha::ChaCha8Rng;
+    #[test]
+    fn test_no_phases() {
+        let puzzle = Rc::new(puzzles::rubiks_cube_3x3());
+        let solver = MetaMovePhasedSolver::new(
+            Rc::clone(&puzzle),
+            puzzle.get_initial_state(),
+            MetaMovePhasedSolverOpts {
+                catalog: Some(Rc::new(PuzzleCatalog {
+                    name: "3x3".to_string(),
+                    phases: vec![],
+                })),
+                ..MetaMovePhasedSolverOpts::default()
+            },
+        );
+        assert!(matches!(solver, Err(Error::UnsolvablePhase(_))));
+    }
 
-    use super::*;
-    use crate::puzzles;
+    #[test]
+    fn test_scorer_chooses_metamove() {
+        let puzzle = Rc::new(puzzles::rubiks_cube_3x3());
+        let catalog = Catalog::builtin();
+        let phases = phases_from_catalog(&puzzle, &catalog.get("3x3").unwrap().phases).unwrap();
+        let phase = &phases[0];
+        let state =
+            puzzle.get_derived_state(&puzzle.get_initial_state(), &phase.three_cycle.face_map);
+        let num_solved = |metamove: &MetaMove| {
+            let next_state = puzzle.get_derived_state(&state, &metamove.face_map);
+            puzzle.get_num_solved_pieces_of_type(&next_state, &phase.target_piece_type)
+        };
+        let num_solved_before = num_solved(&MetaMove::empty(Rc::clone(&puzzle)));
+
+        let default_metamove =
+            phase.next(&state, &SolvedPiecesOfTypeScorer(&phase.target_piece_type));
+        assert!(num_solved(&default_metamove) > num_solved_before);
+
+        let unsolving_metamove = phase.next(&state, &UnsolvedPiecesScorer);
+        assert!(num_solved(&unsolving_metamove) < num_solved_before);
+    }
 
     #[test]
     fn solve_rubiks_3x3() {
         let puzzle = Rc::new(puzzles::rubiks_cube_3x3());
 
+        #[allow(unused_variables, unused_mut)]
         let mut rng = ChaCha8Rng::seed_from_u64(1);
 
         let mut sum = 0;
@@ -417,9 +621,13 @@ mod tests {
         for _ in 0..num_scrambles {
             // let scrambled_state = puzzle.scramble(&puzzle.get_initial_state(), 20, &mut rng);
             let scrambled_state = puzzle.get_initial_state();
-            let solution: Vec<_> =
-                MetaMovePhasedSolver::new(Rc::clone(&puzzle), scrambled_state.clone(), ())
-                    .collect();
+            let solution: Vec<_> = MetaMovePhasedSolver::new(
+                Rc::clone(&puzzle),
+                scrambled_state.clone(),
+                MetaMovePhasedSolverOpts::default(),
+            )
+            .unwrap()
+            .collect();
 
             let out = puzzle
                 .get_derived_state_from_turn_sequence(&scrambled_state, solution.iter().cloned());
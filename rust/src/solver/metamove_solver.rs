@@ -1,8 +1,9 @@
 use super::{
     metamoves::{combine_metamoves, discover_metamoves, MetaMove},
-    ScrambleSolver,
+    ScrambleSolver, SolvedPiecesScorer, StateScorer,
 };
 use crate::{
+    error::{Error, Result},
     traverse_combinations::{traverse_combinations, TraverseResult},
     twisty_puzzle::{PuzzleState, TwistyPuzzle},
 };
@@ -10,7 +11,6 @@ use std::{
     collections::{hash_map::Entry, HashMap, VecDeque},
     rc::Rc,
 };
-use wasm_bindgen::throw_str;
 
 pub struct MetaMoveSolver {
     puzzle: Rc<TwistyPuzzle>,
@@ -19,6 +19,23 @@ pub struct MetaMoveSolver {
     depth: usize,
     metamoves: Vec<MetaMove>,
     buffered_turns: VecDeque<usize>,
+    opts: MetaMoveSolverOpts,
+}
+
+#[derive(Clone)]
+pub struct MetaMoveSolverOpts {
+    pub scorer: Rc<dyn StateScorer>,
+    // Previously discovered metamoves, to skip discovering them again
+    pub metamoves: Option<Rc<Vec<MetaMove>>>,
+}
+
+impl Default for MetaMoveSolverOpts {
+    fn default() -> Self {
+        Self {
+            scorer: Rc::new(SolvedPiecesScorer),
+            metamoves: None,
+        }
+    }
 }
 
 #[derive(PartialEq, Eq)]
@@ -27,92 +44,18 @@ enum SolvePhase {
     Metamoves,
 }
 
-macro_rules! console_log {
-    ($($t:tt)*) => {
-        #[cfg(target_arch = "wasm32")] {
-            web_sys::console::log_1(&format!($($t)*).into());
-        }
-        #[cfg(not(target_arch = "wasm32"))] {
-            println!($($t)*);
-        }
-    };
-}
-
 impl ScrambleSolver for MetaMoveSolver {
-    type Opts = ();
-
-    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, _opts: Self::Opts) -> Self {
-        // let max_discover_metamoves_depth =
-        //     (2_000_000f64.ln() / (puzzle.turns.len() as f64).ln()) as usize;
-        // For now using a hardcoded tree depth,
-        // but in the future might switch to dynamic depth based on puzzle complexity
-        let max_discover_metamoves_depth = 5;
-        // Count the number of pieces affected by an individual turn
-        let turn_num_affected_pieces =
-            MetaMove::new_infer_face_map(Rc::clone(&puzzle), vec![0]).num_affected_pieces;
-        // Discover sets of moves that affect fewer pieces than an individual turn
-        let metamoves = discover_metamoves(
-            Rc::clone(&puzzle),
-            |mm| mm.num_affected_pieces < turn_num_affected_pieces,
-            max_discover_metamoves_depth,
-        );
-
-        console_log!("num metamoves: {}", metamoves.len());
-        let best = metamoves.iter().min().unwrap();
-        console_log!(
-            "best metamove: {} turns affecting {} pieces",
-            best.turns.len(),
-            best.num_affected_pieces
-        );
+    type Opts = MetaMoveSolverOpts;
 
-        // Smoosh together pairs of sets of moves
-        let metamoves: Vec<_> = combine_metamoves(Rc::clone(&puzzle), |_mm| true, &metamoves, 2);
-        console_log!("num metamoves: {}", metamoves.len());
-        let best = metamoves.iter().min().unwrap();
-        console_log!(
-            "best metamove: {} turns affecting {} pieces",
-            best.turns.len(),
-            best.num_affected_pieces
-        );
-
-        // Take out metamoves that have the same effect as others (keep ones with fewest # moves)
-        let metamoves = filter_duplicates(metamoves);
-
-        let metamoves: Vec<_> = metamoves
-            .into_iter()
-            .flat_map(|mm| {
-                // Repeat each metamove multiple times so that some of the face-cycles within the
-                // metamove cancel out
-                mm.discover_repeat_metamoves()
-                    .into_iter()
-                    .chain(std::iter::once(mm))
-            })
-            // Filter out metamoves based on how many pieces they affect
-            .filter(|mm| mm.num_affected_pieces <= 3)
-            .collect();
-
-        console_log!("all mm {}", metamoves.len());
-
-        let mut metamoves = filter_duplicates(metamoves);
-
-        console_log!("reduced mm {}", metamoves.len());
-
-        console_log!("num metamoves: {}", metamoves.len());
-        let best = metamoves.iter().min().unwrap();
-        console_log!(
-            "best metamove: {} turns affecting {} pieces",
-            best.turns.len(),
-            best.num_affected_pieces
-        );
+    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Result<Self> {
+        puzzle.validate_state(&initial_state)?;
+        opts.scorer.check_puzzle(&puzzle)?;
+        let metamoves = match &opts.metamoves {
+            Some(metamoves) => metamoves.as_ref().clone(),
+            None => discover_solver_metamoves(&puzzle)?,
+        };
 
-        metamoves.sort();
-
-        if metamoves.is_empty() {
-            throw_str("no metamoves");
-        }
-        console_log!("done scanning");
-
-        Self {
+        Ok(Self {
             // depth: (500_000f64.ln() / (metamoves.len() as f64).ln()) as usize,
             // For now, using a static depth, but in the future, consider doing a dynamic depth
             // based on the number of metamoves available at this point
@@ -122,7 +65,8 @@ impl ScrambleSolver for MetaMoveSolver {
             puzzle,
             state: initial_state,
             buffered_turns: VecDeque::new(),
-        }
+            opts,
+        })
     }
 
     fn get_state(&self) -> &PuzzleState {
@@ -130,6 +74,13 @@ impl ScrambleSolver for MetaMoveSolver {
     }
 }
 
+impl MetaMoveSolver {
+    #[inline]
+    pub fn metamoves(&self) -> &[MetaMove] {
+        &self.metamoves
+    }
+}
+
 impl Iterator for MetaMoveSolver {
     type Item = usize;
 
@@ -145,7 +96,7 @@ impl Iterator for MetaMoveSolver {
         // First phase: do a shallow search to make it more solved
         if self.phase == SolvePhase::Search {
             let mut best_metamove = MetaMove::empty(Rc::clone(&self.puzzle));
-            let mut best_score = self.puzzle.get_num_solved_pieces(&self.state);
+            let mut best_score = self.opts.scorer.score(&self.puzzle, &self.state);
             let individual_turns_metamoves: Vec<MetaMove> = self
                 .puzzle
                 .turns
@@ -170,7 +121,7 @@ impl Iterator for MetaMoveSolver {
                     },
                     &mut |mm| {
                         let next_state = self.puzzle.get_derived_state(&self.state, &mm.face_map);
-                        let next_state_score = self.puzzle.get_num_solved_pieces(&next_state);
+                        let next_state_score = self.opts.scorer.score(&self.puzzle, &next_state);
                         if next_state_score > best_score
                             || (next_state_score == best_score
                                 && mm.turns.len() < best_metamove.turns.len())
@@ -203,8 +154,13 @@ impl Iterator for MetaMoveSolver {
 
         let options = self.metamoves.clone();
 
-        let best_metamove =
-            find_best_metamove(Rc::clone(&self.puzzle), &self.state, &options, self.depth);
+        let best_metamove = find_best_metamove(
+            Rc::clone(&self.puzzle),
+            &self.state,
+            &options,
+            self.depth,
+            self.opts.scorer.as_ref(),
+        );
         let &first_turn = best_metamove.turns.first()?;
         self.state = self
             .puzzle
@@ -219,14 +175,90 @@ impl Iterator for MetaMoveSolver {
     }
 }
 
+/// Finds the metamoves that the solver uses to finish solving,
+/// which can be saved and passed back in through `MetaMoveSolverOpts::metamoves`
+pub fn discover_solver_metamoves(puzzle: &Rc<TwistyPuzzle>) -> Result<Vec<MetaMove>> {
+    let no_metamoves = || {
+        Error::UnsolvablePhase("no metamoves affect fewer pieces than a single turn".to_string())
+    };
+    // let max_discover_metamoves_depth =
+    //     (2_000_000f64.ln() / (puzzle.turns.len() as f64).ln()) as usize;
+    // For now using a hardcoded tree depth,
+    // but in the future might switch to dynamic depth based on puzzle complexity
+    let max_discover_metamoves_depth = 5;
+    // Count the number of pieces affected by an individual turn
+    let turn_num_affected_pieces =
+        MetaMove::new_infer_face_map(Rc::clone(puzzle), vec![0]).num_affected_pieces;
+    // Discover sets of moves that affect fewer pieces than an individual turn
+    let metamoves = discover_metamoves(
+        Rc::clone(puzzle),
+        |mm| mm.num_affected_pieces < turn_num_affected_pieces,
+        max_discover_metamoves_depth,
+    );
+
+    console_log!("num metamoves: {}", metamoves.len());
+    let best = metamoves.iter().min().ok_or_else(no_metamoves)?;
+    console_log!(
+        "best metamove: {} turns affecting {} pieces",
+        best.turns.len(),
+        best.num_affected_pieces
+    );
+
+    // Smoosh together pairs of sets of moves
+    let metamoves: Vec<_> = combine_metamoves(Rc::clone(puzzle), |_mm| true, &metamoves, 2);
+    console_log!("num metamoves: {}", metamoves.len());
+    let best = metamoves.iter().min().ok_or_else(no_metamoves)?;
+    console_log!(
+        "best metamove: {} turns affecting {} pieces",
+        best.turns.len(),
+        best.num_affected_pieces
+    );
+
+    // Take out metamoves that have the same effect as others (keep ones with fewest # moves)
+    let metamoves = filter_duplicates(metamoves);
+
+    let metamoves: Vec<_> = metamoves
+        .into_iter()
+        .flat_map(|mm| {
+            // Repeat each metamove multiple times so that some of the face-cycles within the
+            // metamove cancel out
+            mm.discover_repeat_metamoves()
+                .into_iter()
+                .chain(std::iter::once(mm))
+        })
+        // Filter out metamoves based on how many pieces they affect
+        .filter(|mm| mm.num_affected_pieces <= 3)
+        .collect();
+
+    console_log!("all mm {}", metamoves.len());
+
+    let mut metamoves = filter_duplicates(metamoves);
+
+    console_log!("reduced mm {}", metamoves.len());
+
+    console_log!("num metamoves: {}", metamoves.len());
+    let best = metamoves.iter().min().ok_or_else(no_metamoves)?;
+    console_log!(
+        "best metamove: {} turns affecting {} pieces",
+        best.turns.len(),
+        best.num_affected_pieces
+    );
+
+    metamoves.sort();
+    console_log!("done scanning");
+
+    Ok(metamoves)
+}
+
 fn find_best_metamove(
     puzzle: Rc<TwistyPuzzle>,
     state: &PuzzleState,
     metamoves: &[MetaMove],
     depth: usize,
+    scorer: &dyn StateScorer,
 ) -> MetaMove {
     let mut best_metamove = MetaMove::empty(Rc::clone(&puzzle));
-    let mut best_score = puzzle.get_num_solved_pieces(state);
+    let mut best_score = scorer.score(&puzzle, state);
 
     traverse_combinations(
         metamoves,
@@ -237,7 +269,7 @@ fn find_best_metamove(
         },
         &mut |mm| {
             let next_state = puzzle.get_derived_state(state, &mm.face_map);
-            let next_state_score = puzzle.get_num_solved_pieces(&next_state);
+            let next_state_score = scorer.score(&puzzle, &next_state);
             if next_state_score > best_score {
                 best_metamove = mm.clone();
                 best_score = next_state_score;
@@ -245,7 +277,7 @@ fn find_best_metamove(
                 // not necessarily the best one
                 // return TraverseResult::Break;
             }
-            if next_state_score == puzzle.get_num_pieces() {
+            if puzzle.is_solved(&next_state) {
                 return TraverseResult::Break;
             }
             TraverseResult::Continue
@@ -298,8 +330,13 @@ mod tests {
         // avg 3x3 solution length: 384.6 turns, (30 / 50)
         for _ in 0..num_scrambles {
             let scrambled_state = puzzle.scramble(&puzzle.get_initial_state(), 20, &mut rng);
-            let solution: Vec<_> =
-                MetaMoveSolver::new(Rc::clone(&puzzle), scrambled_state.clone(), ()).collect();
+            let solution: Vec<_> = MetaMoveSolver::new(
+                Rc::clone(&puzzle),
+                scrambled_state.clone(),
+                MetaMoveSolverOpts::default(),
+            )
+            .unwrap()
+            .collect();
 
             let out = puzzle
                 .get_derived_state_from_turn_sequence(&scrambled_state, solution.iter().cloned());
@@ -8,17 +8,6 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::rc::Rc;
 
-macro_rules! console_log {
-    ($($t:tt)*) => {
-        #[cfg(target_arch = "wasm32")] {
-            web_sys::console::log_1(&format!($($t)*).into());
-        }
-        #[cfg(not(target_arch = "wasm32"))] {
-            println!($($t)*);
-        }
-    };
-}
-
 /// A metamove is a set of moves that combines to one large "move"
 /// that ends up (hopefully) moving only a small number of pieces.
 #[derive(Clone)]
@@ -149,10 +138,10 @@ impl MetaMove {
             repeat_to_cancel_cycles(&cycles.iter().map(|c| c.len()).collect::<Vec<usize>>())
                 .into_iter()
                 .map(|times_to_repeat| {
-                    let new_turns: Vec<usize> = std::iter::repeat(self.turns.iter().cloned())
-                        .take(times_to_repeat)
-                        .flatten()
-                        .collect();
+                    let new_turns: Vec<usize> =
+                        std::iter::repeat_n(self.turns.iter().cloned(), times_to_repeat)
+                            .flatten()
+                            .collect();
                     let mut face_map_vec: Vec<usize> = (0..self.face_map.0.len()).collect();
                     for cycle in &cycles {
                         // If cycle cancels out; ignore it
@@ -432,6 +421,61 @@ where
     combined_metamoves
 }
 
+/// Metamoves are saved as their turns, along with the puzzle's turn names
+/// so that they aren't loaded for a different puzzle
+#[cfg(feature = "serialize")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct MetaMoveList {
+    turn_names: Vec<String>,
+    metamoves: Vec<Vec<usize>>,
+}
+
+#[cfg(feature = "serialize")]
+pub fn write_metamoves<W: std::io::Write>(
+    writer: &mut W,
+    metamoves: &[MetaMove],
+) -> std::io::Result<()> {
+    let turn_names = match metamoves.first() {
+        Some(metamove) => metamove.puzzle.turn_names.clone(),
+        None => vec![],
+    };
+    let list = MetaMoveList {
+        turn_names,
+        metamoves: metamoves.iter().map(|mm| mm.turns.clone()).collect(),
+    };
+    crate::serialize::write(writer, &list)
+}
+
+/// Loads metamoves saved by `write_metamoves`, recomputing their face maps
+#[cfg(feature = "serialize")]
+pub fn read_metamoves<R: std::io::Read>(
+    reader: &mut R,
+    puzzle: Rc<TwistyPuzzle>,
+) -> std::io::Result<Vec<MetaMove>> {
+    let list: MetaMoveList = crate::serialize::read(reader)?;
+    if !list.metamoves.is_empty() && list.turn_names != puzzle.turn_names {
+        return Err(crate::serialize::invalid_data(
+            "metamoves are for a different puzzle",
+        ));
+    }
+    let num_turns = puzzle.turn_names.len();
+    if list
+        .metamoves
+        .iter()
+        .flatten()
+        .any(|&turn_index| turn_index >= num_turns)
+    {
+        return Err(crate::serialize::invalid_data(
+            "metamoves have turns that the puzzle doesn't have",
+        ));
+    }
+    Ok(list
+        .metamoves
+        .into_iter()
+        .map(|turns| MetaMove::new_infer_face_map(Rc::clone(&puzzle), turns))
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::puzzles;
@@ -454,6 +498,37 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_write_read_metamoves() {
+        let puzzle = Rc::new(puzzles::rubiks_cube_3x3());
+        let metamoves = vec![
+            MetaMove::new_infer_face_map(Rc::clone(&puzzle), vec![0, 2, 1, 3]),
+            MetaMove::new_infer_face_map(Rc::clone(&puzzle), vec![4]),
+        ];
+        let mut bytes = vec![];
+        write_metamoves(&mut bytes, &metamoves).unwrap();
+        assert_eq!(
+            read_metamoves(&mut bytes.as_slice(), Rc::clone(&puzzle)).unwrap(),
+            metamoves
+        );
+        assert!(read_metamoves(&mut bytes.as_slice(), Rc::new(puzzles::skewb())).is_err());
+
+        // A turn past the end of the puzzle's turns
+        let list = MetaMoveList {
+            turn_names: puzzle.turn_names.clone(),
+            metamoves: vec![vec![0, puzzle.turn_names.len()]],
+        };
+        let mut bytes = vec![];
+        crate::serialize::write(&mut bytes, &list).unwrap();
+        assert_eq!(
+            read_metamoves(&mut bytes.as_slice(), Rc::clone(&puzzle))
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidData
+        );
+    }
+
     #[test]
     fn test_apply_symmetry() {
         let puzzle = Rc::new(puzzles::rubiks_cube_3x3());
@@ -1,18 +1,33 @@
 use std::rc::Rc;
 
+use crate::error::Result;
 use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
 mod bijection_trie;
+mod catalog;
 mod full_search_solve;
 mod lookahead;
 mod metamove_phased_solver;
 mod metamove_solver;
 mod metamoves;
+mod network_solver;
+mod scorer;
 mod simple_one_move;
+pub use catalog::{Catalog, CatalogMetaMove, CatalogPhase, PuzzleCatalog};
 pub use full_search_solve::{FullSearchSolver, FullSearchSolverOpts};
 pub use lookahead::{LookaheadSolver, LookaheadSolverOpts};
-pub use metamove_phased_solver::MetaMovePhasedSolver;
-pub use metamove_solver::MetaMoveSolver;
-pub use simple_one_move::OneMoveSolver;
+pub use metamove_phased_solver::{
+    discover_phase_catalog, MetaMovePhasedSolver, MetaMovePhasedSolverOpts,
+};
+pub use metamove_solver::{discover_solver_metamoves, MetaMoveSolver, MetaMoveSolverOpts};
+pub use metamoves::MetaMove;
+#[cfg(feature = "serialize")]
+pub use metamoves::{read_metamoves, write_metamoves};
+pub use network_solver::{NetworkSolver, NetworkSolverOpts};
+pub use scorer::{
+    PieceDistanceScorer, SolvedFacesScorer, SolvedPiecesScorer, StateScorer,
+    WeightedPieceTypesScorer,
+};
+pub use simple_one_move::{OneMoveSolver, OneMoveSolverOpts};
 
 pub struct Solver<T: ScrambleSolver> {
     opts: T::Opts,
@@ -23,13 +38,134 @@ impl<T: ScrambleSolver> Solver<T> {
     pub fn new(puzzle: Rc<TwistyPuzzle>, opts: T::Opts) -> Self {
         Self { opts, puzzle }
     }
-    pub fn solve(&self, initial_state: PuzzleState) -> T {
+    pub fn solve(&self, initial_state: PuzzleState) -> Result<T> {
         T::new(self.puzzle.clone(), initial_state, self.opts.clone())
     }
 }
 
-pub trait ScrambleSolver: Iterator<Item = usize> {
+pub trait ScrambleSolver: Iterator<Item = usize> + Sized {
     type Opts: Clone;
-    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Self;
+    /// Fails if the state isn't valid for the puzzle,
+    /// or if the solver can't find what it needs to solve the puzzle
+    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Result<Self>;
     fn get_state(&self) -> &PuzzleState;
 }
+
+/// A solve in progress from any kind of solver, so that the kind can be chosen at runtime
+pub trait AnyScrambleSolver: Iterator<Item = usize> {
+    fn get_state(&self) -> &PuzzleState;
+}
+
+impl<T: ScrambleSolver> AnyScrambleSolver for T {
+    fn get_state(&self) -> &PuzzleState {
+        ScrambleSolver::get_state(self)
+    }
+}
+
+/// A [`Solver`] of any kind, so that the kind can be chosen at runtime
+pub trait AnySolver {
+    fn solve(&self, initial_state: PuzzleState) -> Result<Box<dyn AnyScrambleSolver>>;
+}
+
+impl<T: ScrambleSolver + 'static> AnySolver for Solver<T> {
+    fn solve(&self, initial_state: PuzzleState) -> Result<Box<dyn AnyScrambleSolver>> {
+        Ok(Box::new(Solver::<T>::solve(self, initial_state)?))
+    }
+}
+
+/// The solvers that can be chosen by name, from the simplest to the most thorough
+pub const ALL_SOLVERS: &[&str] = &["one-move", "lookahead", "full-search", "metamove", "phased"];
+
+/// Sets up the solver with this name from `ALL_SOLVERS` for a puzzle,
+/// doing the work that is shared between solves, like discovering metamoves.
+/// The phased solver uses the built-in catalog for the puzzle's name if there is one.
+/// Returns None if there is no solver with the name.
+pub fn solver_by_name(
+    solver_name: &str,
+    puzzle_name: &str,
+    puzzle: &Rc<TwistyPuzzle>,
+) -> Option<Result<Box<dyn AnySolver>>> {
+    fn boxed<T: ScrambleSolver + 'static>(
+        puzzle: &Rc<TwistyPuzzle>,
+        opts: T::Opts,
+    ) -> Box<dyn AnySolver> {
+        Box::new(Solver::<T>::new(puzzle.clone(), opts))
+    }
+
+    let solver = match solver_name {
+        "one-move" => boxed::<OneMoveSolver>(puzzle, OneMoveSolverOpts::default()),
+        "lookahead" => boxed::<LookaheadSolver>(
+            puzzle,
+            LookaheadSolverOpts {
+                depth: 2,
+                scorer: Rc::new(SolvedPiecesScorer),
+            },
+        ),
+        "full-search" => boxed::<FullSearchSolver>(
+            puzzle,
+            FullSearchSolverOpts {
+                depth: 4,
+                scorer: Rc::new(SolvedPiecesScorer),
+            },
+        ),
+        "metamove" => {
+            let metamoves = match discover_solver_metamoves(puzzle) {
+                Ok(metamoves) => metamoves,
+                Err(error) => return Some(Err(error)),
+            };
+            boxed::<MetaMoveSolver>(
+                puzzle,
+                MetaMoveSolverOpts {
+                    metamoves: Some(Rc::new(metamoves)),
+                    ..MetaMoveSolverOpts::default()
+                },
+            )
+        }
+        "phased" => {
+            let catalog = match Catalog::builtin().get(puzzle_name) {
+                Some(catalog) => catalog.clone(),
+                None => match discover_phase_catalog(puzzle) {
+                    Ok(phases) => PuzzleCatalog {
+                        name: puzzle_name.to_string(),
+                        phases,
+                    },
+                    Err(error) => return Some(Err(error)),
+                },
+            };
+            boxed::<MetaMovePhasedSolver>(
+                puzzle,
+                MetaMovePhasedSolverOpts {
+                    catalog: Some(Rc::new(catalog)),
+                    ..MetaMovePhasedSolverOpts::default()
+                },
+            )
+        }
+        _ => return None,
+    };
+    Some(Ok(solver))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles;
+
+    #[test]
+    fn test_solver_by_name() {
+        let puzzle = Rc::new(puzzles::rubiks_cube_2x2());
+        assert!(solver_by_name("two-move", "2x2", &puzzle).is_none());
+
+        let scrambled = puzzle.get_derived_state_turn_index(&puzzle.get_initial_state(), 0);
+        // Discovering the metamove solver's metamoves takes too long for a test
+        for &solver_name in ALL_SOLVERS.iter().filter(|&&name| name != "metamove") {
+            let solver = solver_by_name(solver_name, "2x2", &puzzle)
+                .unwrap()
+                .unwrap();
+            let mut scramble_solver = solver.solve(scrambled.clone()).unwrap();
+            scramble_solver.by_ref().take(100).for_each(drop);
+            if solver_name == "phased" {
+                assert!(puzzle.is_solved(scramble_solver.get_state()));
+            }
+        }
+    }
+}
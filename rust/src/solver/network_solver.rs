@@ -0,0 +1,240 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::rc::Rc;
+
+use crate::error::{Error, Result};
+use crate::network::Network;
+use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+
+use super::ScrambleSolver;
+
+/// Searches for a solution with weighted A*,
+/// using a trained network to estimate the number of turns left from each state
+pub struct NetworkSolver {
+    puzzle: Rc<TwistyPuzzle>,
+    state: PuzzleState,
+    solution: VecDeque<usize>,
+}
+
+#[derive(Clone)]
+pub struct NetworkSolverOpts {
+    pub network: Rc<Network>,
+    // How much the number of turns so far counts compared to the estimated number left.
+    // Lower weights find solutions faster, but they are longer.
+    pub weight: f32,
+    // The most states to keep in the open set, dropping the least promising ones
+    pub beam_width: usize,
+    // The most states to expand before giving up
+    pub max_expansions: usize,
+    // Only try the turns that the policy rates the highest from each state
+    pub max_turns_per_state: Option<usize>,
+}
+
+struct Node {
+    parent: Option<usize>,
+    turn_index: usize,
+    num_turns: usize,
+    estimate: f32,
+}
+
+struct OpenEntry {
+    priority: f32,
+    node_index: usize,
+}
+
+impl PartialEq for OpenEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for OpenEntry {}
+impl PartialOrd for OpenEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for OpenEntry {
+    // Reversed, so that the heap pops the lowest priority first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then(other.node_index.cmp(&self.node_index))
+    }
+}
+
+impl ScrambleSolver for NetworkSolver {
+    type Opts = NetworkSolverOpts;
+
+    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Result<Self> {
+        puzzle.validate_state(&initial_state)?;
+        if !opts.network.matches(&puzzle) {
+            return Err(Error::NetworkMismatch);
+        }
+        let num_turns = puzzle.turn_names.len();
+
+        let mut nodes = vec![Node {
+            parent: None,
+            turn_index: 0,
+            num_turns: 0,
+            estimate: opts.network.value(&initial_state),
+        }];
+        let mut states = vec![initial_state.clone()];
+        let mut best_num_turns: HashMap<PuzzleState, usize> = HashMap::new();
+        best_num_turns.insert(initial_state.clone(), 0);
+        let mut open = BinaryHeap::new();
+        open.push(OpenEntry {
+            priority: nodes[0].estimate,
+            node_index: 0,
+        });
+
+        // If no solution is found, go to the state that seems closest to solved
+        let mut best_node = 0;
+        let mut solved_node = None;
+        let mut num_expansions = 0;
+
+        while let Some(OpenEntry { node_index, .. }) = open.pop() {
+            let state = states[node_index].clone();
+            if puzzle.is_solved(&state) {
+                solved_node = Some(node_index);
+                break;
+            }
+            if num_expansions >= opts.max_expansions {
+                break;
+            }
+            num_expansions += 1;
+
+            let turn_indices: Vec<usize> = match opts.max_turns_per_state {
+                Some(max_turns) => {
+                    let (_, policy) = opts.network.evaluate(&state);
+                    let mut turn_indices: Vec<usize> = (0..num_turns).collect();
+                    turn_indices.sort_by(|&a, &b| policy[b].total_cmp(&policy[a]));
+                    turn_indices.truncate(max_turns);
+                    turn_indices
+                }
+                None => (0..num_turns).collect(),
+            };
+
+            let num_turns_so_far = nodes[node_index].num_turns + 1;
+            for turn_index in turn_indices {
+                let next_state = puzzle.get_derived_state_turn_index(&state, turn_index);
+                if let Some(&previous) = best_num_turns.get(&next_state) {
+                    if previous <= num_turns_so_far {
+                        continue;
+                    }
+                }
+                best_num_turns.insert(next_state.clone(), num_turns_so_far);
+                let estimate = if puzzle.is_solved(&next_state) {
+                    0.0
+                } else {
+                    opts.network.value(&next_state).max(0.0)
+                };
+                let next_node_index = nodes.len();
+                nodes.push(Node {
+                    parent: Some(node_index),
+                    turn_index,
+                    num_turns: num_turns_so_far,
+                    estimate,
+                });
+                states.push(next_state);
+                if estimate < nodes[best_node].estimate {
+                    best_node = next_node_index;
+                }
+                open.push(OpenEntry {
+                    priority: opts.weight * num_turns_so_far as f32 + estimate,
+                    node_index: next_node_index,
+                });
+            }
+
+            if open.len() > opts.beam_width * 2 {
+                let mut entries = open.into_sorted_vec();
+                // Sorted in reverse, so the most promising entries are at the end
+                entries.drain(..entries.len() - opts.beam_width);
+                open = entries.into();
+            }
+        }
+
+        let mut solution = VecDeque::new();
+        let mut node_index = solved_node.unwrap_or(best_node);
+        while let Some(parent) = nodes[node_index].parent {
+            solution.push_front(nodes[node_index].turn_index);
+            node_index = parent;
+        }
+
+        Ok(Self {
+            puzzle,
+            state: initial_state,
+            solution,
+        })
+    }
+
+    fn get_state(&self) -> &PuzzleState {
+        &self.state
+    }
+}
+
+impl Iterator for NetworkSolver {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let turn = self.solution.pop_front()?;
+        self.state = self.puzzle.get_derived_state_turn_index(&self.state, turn);
+        Some(turn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::network::{train_epoch, Trainer};
+    use crate::puzzles;
+    use crate::training_data::TrainingData;
+
+    #[test]
+    fn solve_rubiks_2x2_short_scrambles() {
+        let puzzle = Rc::new(puzzles::rubiks_cube_2x2());
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut data = TrainingData::new(&puzzle);
+        for _ in 0..100 {
+            data.add_random_walk(&puzzle, 4, &mut rng);
+        }
+        let mut trainer = Trainer::new(Network::new(&puzzle, &[64], &mut rng), 0.01);
+        for _ in 0..20 {
+            train_epoch(&mut trainer, &puzzle, &data, 32, &mut rng).unwrap();
+        }
+
+        let opts = NetworkSolverOpts {
+            network: Rc::new(trainer.network),
+            weight: 0.6,
+            beam_width: 1000,
+            max_expansions: 2000,
+            max_turns_per_state: None,
+        };
+        for _ in 0..5 {
+            let scrambled_state = puzzle.scramble(&puzzle.get_initial_state(), 4, &mut rng);
+            let mut solver =
+                NetworkSolver::new(Rc::clone(&puzzle), scrambled_state, opts.clone()).unwrap();
+            solver.by_ref().for_each(drop);
+            assert!(puzzle.is_solved(solver.get_state()));
+        }
+    }
+
+    #[test]
+    fn network_for_another_puzzle() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let network = Network::new(&puzzles::rubiks_cube_2x2(), &[8], &mut rng);
+        let puzzle = Rc::new(puzzles::rubiks_cube_3x3());
+        let opts = NetworkSolverOpts {
+            network: Rc::new(network),
+            weight: 0.6,
+            beam_width: 1000,
+            max_expansions: 2000,
+            max_turns_per_state: None,
+        };
+        let result = NetworkSolver::new(Rc::clone(&puzzle), puzzle.get_initial_state(), opts);
+        assert_eq!(result.err(), Some(Error::NetworkMismatch));
+    }
+}
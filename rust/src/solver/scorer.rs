@@ -0,0 +1,141 @@
+use crate::error::{Error, Result};
+use crate::network::Network;
+use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+
+/// The objective that a heuristic solver tries to maximize.
+/// Higher scores are closer to solved.
+pub trait StateScorer {
+    fn score(&self, puzzle: &TwistyPuzzle, puzzle_state: &PuzzleState) -> f64;
+
+    /// Checks that the scorer can score states of the puzzle,
+    /// which solvers do before they start
+    fn check_puzzle(&self, _puzzle: &TwistyPuzzle) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Counts the pieces that are in the correct position and oriented correctly
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SolvedPiecesScorer;
+
+impl StateScorer for SolvedPiecesScorer {
+    fn score(&self, puzzle: &TwistyPuzzle, puzzle_state: &PuzzleState) -> f64 {
+        puzzle.get_num_solved_pieces(puzzle_state) as f64
+    }
+}
+
+/// Counts the faces that have the correct color,
+/// even if the rest of their piece is wrong
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SolvedFacesScorer;
+
+impl StateScorer for SolvedFacesScorer {
+    fn score(&self, puzzle: &TwistyPuzzle, puzzle_state: &PuzzleState) -> f64 {
+        puzzle
+            .get_initial_state()
+            .iter()
+            .zip(puzzle_state)
+            .filter(|(solved_color, color)| solved_color == color)
+            .count() as f64
+    }
+}
+
+/// Uses the number of turns each piece is from being solved,
+/// negated so that fewer turns is a higher score
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PieceDistanceScorer;
+
+impl StateScorer for PieceDistanceScorer {
+    fn score(&self, puzzle: &TwistyPuzzle, puzzle_state: &PuzzleState) -> f64 {
+        -(puzzle.heuristic_distance(puzzle_state) as f64)
+    }
+}
+
+/// Counts the solved pieces of each piece type, multiplied by a weight for that type.
+/// The weights are in the same order as `TwistyPuzzle::piece_types`,
+/// and missing weights count as 1.
+#[derive(Debug, Clone, Default)]
+pub struct WeightedPieceTypesScorer {
+    pub weights: Vec<f64>,
+}
+
+impl StateScorer for WeightedPieceTypesScorer {
+    fn score(&self, puzzle: &TwistyPuzzle, puzzle_state: &PuzzleState) -> f64 {
+        puzzle
+            .piece_types
+            .iter()
+            .enumerate()
+            .map(|(i, piece_type)| {
+                let weight = self.weights.get(i).copied().unwrap_or(1.0);
+                weight * puzzle.get_num_solved_pieces_of_type(puzzle_state, piece_type) as f64
+            })
+            .sum()
+    }
+}
+
+/// Uses the number of turns left estimated by a trained network,
+/// negated so that fewer turns is a higher score.
+/// Solvers using it fail with `Error::NetworkMismatch` if it was built for another puzzle.
+impl StateScorer for Network {
+    fn score(&self, _puzzle: &TwistyPuzzle, puzzle_state: &PuzzleState) -> f64 {
+        -(self.value(puzzle_state) as f64)
+    }
+
+    fn check_puzzle(&self, puzzle: &TwistyPuzzle) -> Result<()> {
+        if self.matches(puzzle) {
+            Ok(())
+        } else {
+            Err(Error::NetworkMismatch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::puzzles;
+    use crate::solver::{OneMoveSolver, OneMoveSolverOpts, ScrambleSolver};
+
+    #[test]
+    fn test_scorers_prefer_solved() {
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let solved_state = puzzle.get_initial_state();
+        let turned_state = puzzle.get_derived_state_turn_index(&solved_state, 0);
+        let scorers: [(&dyn StateScorer, f64, f64); 4] = [
+            (&SolvedPiecesScorer, 26.0, 18.0),
+            (&SolvedFacesScorer, 54.0, 42.0),
+            (&PieceDistanceScorer, 0.0, -8.0),
+            (
+                &WeightedPieceTypesScorer {
+                    weights: vec![2.0, 0.0],
+                },
+                // Corners count double, edges are ignored, and centers count once
+                2.0 * 8.0 + 6.0,
+                2.0 * 4.0 + 6.0,
+            ),
+        ];
+        for (scorer, solved_score, turned_score) in scorers {
+            assert_eq!(scorer.score(&puzzle, &solved_state), solved_score);
+            assert_eq!(scorer.score(&puzzle, &turned_state), turned_score);
+        }
+    }
+
+    #[test]
+    fn test_network_scorer_for_another_puzzle() {
+        let puzzle = Rc::new(puzzles::rubiks_cube_3x3());
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let network = Network::new(&puzzles::rubiks_cube_2x2(), &[8], &mut rng);
+        assert_eq!(network.check_puzzle(&puzzle), Err(Error::NetworkMismatch));
+
+        let opts = OneMoveSolverOpts {
+            scorer: Rc::new(network),
+        };
+        let result = OneMoveSolver::new(Rc::clone(&puzzle), puzzle.get_initial_state(), opts);
+        assert_eq!(result.err(), Some(Error::NetworkMismatch));
+    }
+}
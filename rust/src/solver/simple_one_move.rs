@@ -1,22 +1,40 @@
 use std::rc::Rc;
 
+use crate::error::Result;
 use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
 
-use super::ScrambleSolver;
+use super::{ScrambleSolver, SolvedPiecesScorer, StateScorer};
 
 pub struct OneMoveSolver {
     puzzle: Rc<TwistyPuzzle>,
     state: PuzzleState,
+    opts: OneMoveSolverOpts,
 }
 
-impl ScrambleSolver for OneMoveSolver {
-    type Opts = ();
+#[derive(Clone)]
+pub struct OneMoveSolverOpts {
+    pub scorer: Rc<dyn StateScorer>,
+}
 
-    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, _opts: Self::Opts) -> Self {
+impl Default for OneMoveSolverOpts {
+    fn default() -> Self {
         Self {
+            scorer: Rc::new(SolvedPiecesScorer),
+        }
+    }
+}
+
+impl ScrambleSolver for OneMoveSolver {
+    type Opts = OneMoveSolverOpts;
+
+    fn new(puzzle: Rc<TwistyPuzzle>, initial_state: PuzzleState, opts: Self::Opts) -> Result<Self> {
+        puzzle.validate_state(&initial_state)?;
+        opts.scorer.check_puzzle(&puzzle)?;
+        Ok(Self {
             puzzle,
             state: initial_state,
-        }
+            opts,
+        })
     }
 
     fn get_state(&self) -> &PuzzleState {
@@ -28,7 +46,7 @@ impl Iterator for OneMoveSolver {
     type Item = usize;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let current_score = self.puzzle.get_num_solved_pieces(&self.state);
+        let current_score = self.opts.scorer.score(&self.puzzle, &self.state);
         let (next_turn_index, _, next_state) = self
             .puzzle
             .turns
@@ -36,14 +54,14 @@ impl Iterator for OneMoveSolver {
             .enumerate()
             .filter_map(|(turn_index, turn)| {
                 let next_state = self.puzzle.get_derived_state(&self.state, &turn.face_map);
-                let next_state_score = self.puzzle.get_num_solved_pieces(&next_state);
+                let next_state_score = self.opts.scorer.score(&self.puzzle, &next_state);
                 if next_state_score > current_score {
                     Some((turn_index, next_state_score, next_state))
                 } else {
                     None
                 }
             })
-            .max_by_key(|(_, score, _)| *score)?;
+            .max_by(|(_, a, _), (_, b, _)| a.total_cmp(b))?;
 
         self.state = next_state;
 
@@ -0,0 +1,524 @@
+//! Timing solves like a speedsolving timer: inspection, then a timer that starts on the first turn
+//! and stops as soon as the puzzle is solved. Solves are kept in sessions, which are saved to a
+//! `Storage` so that they last between visits.
+//!
+//! Sessions are saved as text, one solve per line, as the time in seconds (without any penalty),
+//! the penalty (`ok`, `+2` or `dnf`), the number of turns, and the scramble:
+//!
+//! ```text
+//! 12.345 ok 48 R U R' U'
+//! 20.100 +2 63 F D L
+//! ```
+
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+
+/// How long there is to look at the puzzle before the solve has to start without a penalty
+pub const INSPECTION_SECONDS: f64 = 15.0;
+// Starting up to this late gets two seconds added, and any later doesn't count
+const LATE_INSPECTION_SECONDS: f64 = 17.0;
+
+// The keys that sessions are saved under start with this, followed by the session's name
+const SESSION_KEY_PREFIX: &str = "twisty-puzzles.session.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Penalty {
+    None,
+    /// Two seconds are added, for starting after inspection was over
+    PlusTwo,
+    /// Did not finish, which counts as slower than any time
+    Dnf,
+}
+
+impl Penalty {
+    /// The penalty for starting the solve after inspecting for this long
+    pub fn for_inspection(seconds: f64) -> Self {
+        if seconds <= INSPECTION_SECONDS {
+            Penalty::None
+        } else if seconds <= LATE_INSPECTION_SECONDS {
+            Penalty::PlusTwo
+        } else {
+            Penalty::Dnf
+        }
+    }
+
+    fn to_text(self) -> &'static str {
+        match self {
+            Penalty::None => "ok",
+            Penalty::PlusTwo => "+2",
+            Penalty::Dnf => "dnf",
+        }
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        match text {
+            "ok" => Some(Penalty::None),
+            "+2" => Some(Penalty::PlusTwo),
+            "dnf" => Some(Penalty::Dnf),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solve {
+    /// From the first turn until the puzzle was solved, without the penalty
+    pub seconds: f64,
+    pub penalty: Penalty,
+    pub turns: usize,
+    /// The scramble's turn names, separated by spaces
+    pub scramble: String,
+}
+
+impl Solve {
+    /// The time that counts, with the penalty added. Infinite for a DNF, so that it is the slowest.
+    pub fn result(&self) -> f64 {
+        match self.penalty {
+            Penalty::None => self.seconds,
+            Penalty::PlusTwo => self.seconds + 2.0,
+            Penalty::Dnf => f64::INFINITY,
+        }
+    }
+
+    /// None if the solve took no time, since there is no rate to give
+    pub fn turns_per_second(&self) -> Option<f64> {
+        (self.seconds > 0.0).then(|| self.turns as f64 / self.seconds)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimerPhase {
+    /// Waiting for the puzzle to be scrambled
+    Idle,
+    /// Looking at the scrambled puzzle, since the time `start`
+    Inspecting { start: f64 },
+    /// Solving since the first turn at the time `start`
+    Solving {
+        start: f64,
+        penalty: Penalty,
+        turns: usize,
+    },
+}
+
+/// Times one solve at a time. It is told the time (in seconds, from any starting point)
+/// whenever something happens, rather than reading a clock, so it works anywhere.
+#[derive(Debug, Clone)]
+pub struct Timer {
+    phase: TimerPhase,
+    scramble: String,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self {
+            phase: TimerPhase::Idle,
+            scramble: String::new(),
+        }
+    }
+
+    pub fn phase(&self) -> TimerPhase {
+        self.phase
+    }
+
+    /// Starts inspecting a puzzle that was just scrambled with these turn names
+    pub fn start_inspection(&mut self, now: f64, scramble: String) {
+        self.phase = TimerPhase::Inspecting { start: now };
+        self.scramble = scramble;
+    }
+
+    /// Stops timing without keeping the solve
+    pub fn cancel(&mut self) {
+        self.phase = TimerPhase::Idle;
+    }
+
+    /// Counts a turn that left the puzzle in `state`. The first turn after inspection starts
+    /// the timer, and the solve is returned once a turn solves the puzzle.
+    pub fn turn(&mut self, now: f64, puzzle: &TwistyPuzzle, state: &PuzzleState) -> Option<Solve> {
+        let (start, penalty, turns) = match self.phase {
+            TimerPhase::Idle => return None,
+            TimerPhase::Inspecting { start } => (now, Penalty::for_inspection(now - start), 1),
+            TimerPhase::Solving {
+                start,
+                penalty,
+                turns,
+            } => (start, penalty, turns + 1),
+        };
+        if !puzzle.is_solved(state) {
+            self.phase = TimerPhase::Solving {
+                start,
+                penalty,
+                turns,
+            };
+            return None;
+        }
+        self.phase = TimerPhase::Idle;
+        Some(Solve {
+            seconds: now - start,
+            penalty,
+            turns,
+            scramble: std::mem::take(&mut self.scramble),
+        })
+    }
+
+    /// What a timer would show: the seconds of inspection left (or the penalty once it's over),
+    /// or the time so far. Nothing while idle.
+    pub fn display(&self, now: f64) -> Option<String> {
+        match self.phase {
+            TimerPhase::Idle => None,
+            TimerPhase::Inspecting { start } => Some(match Penalty::for_inspection(now - start) {
+                Penalty::None => format!("{}", (INSPECTION_SECONDS - (now - start)).ceil()),
+                Penalty::PlusTwo => "+2".to_string(),
+                Penalty::Dnf => "DNF".to_string(),
+            }),
+            TimerPhase::Solving { start, .. } => Some(format_time(now - start)),
+        }
+    }
+}
+
+/// Writes a time like timers do, as `12.34` or `1:02.34`, or `DNF` if it is infinite
+pub fn format_time(seconds: f64) -> String {
+    if !seconds.is_finite() {
+        return "DNF".to_string();
+    }
+    // Rounded before splitting off the minutes, so that 59.999 is 1:00.00 rather than 60.00
+    let hundredths = (seconds * 100.0).round() as u64;
+    let (minutes, hundredths) = (hundredths / 6000, hundredths % 6000);
+    if minutes == 0 {
+        format!("{}.{:02}", hundredths / 100, hundredths % 100)
+    } else {
+        format!(
+            "{}:{:02}.{:02}",
+            minutes,
+            hundredths / 100,
+            hundredths % 100
+        )
+    }
+}
+
+/// Somewhere to save sessions (and other settings, like key bindings), as text under a key
+pub trait Storage {
+    /// The text saved under the key, or None if nothing has been saved there
+    fn get(&self, key: &str) -> Result<Option<String>>;
+    fn set(&mut self, key: &str, value: &str) -> Result<()>;
+}
+
+/// Storage that only lasts as long as it does, for tests and when there is nowhere else
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    values: HashMap<String, String>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.values.get(key).cloned())
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        self.values.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+/// The browser's localStorage, which keeps sessions between visits to the page
+#[cfg(feature = "wasm")]
+pub struct LocalStorage {
+    storage: web_sys::Storage,
+}
+
+#[cfg(feature = "wasm")]
+impl LocalStorage {
+    /// Fails if the browser doesn't allow the page to use localStorage
+    pub fn new() -> Result<Self> {
+        let storage = web_sys::window()
+            .ok_or_else(|| Error::StorageFailed("there is no window".to_string()))?
+            .local_storage()
+            .map_err(|error| Error::StorageFailed(format!("{:?}", error)))?
+            .ok_or_else(|| Error::StorageFailed("localStorage is not available".to_string()))?;
+        Ok(Self { storage })
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        self.storage
+            .get_item(key)
+            .map_err(|error| Error::StorageFailed(format!("{:?}", error)))
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        self.storage
+            .set_item(key, value)
+            .map_err(|error| Error::StorageFailed(format!("{:?}", error)))
+    }
+}
+
+/// Solves that were timed together, usually of one puzzle
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub name: String,
+    pub solves: Vec<Solve>,
+}
+
+impl Session {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            solves: vec![],
+        }
+    }
+
+    /// Loads the session with this name, or starts an empty one if it hasn't been saved
+    pub fn load(storage: &dyn Storage, name: &str) -> Result<Self> {
+        match storage.get(&format!("{}{}", SESSION_KEY_PREFIX, name))? {
+            Some(text) => Self::from_text(name, &text),
+            None => Ok(Self::new(name)),
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) -> Result<()> {
+        storage.set(
+            &format!("{}{}", SESSION_KEY_PREFIX, self.name),
+            &self.to_text(),
+        )
+    }
+
+    /// Reads the solves written in the format described at the top of this module
+    pub fn from_text(name: &str, text: &str) -> Result<Self> {
+        let mut session = Self::new(name);
+        for (line_index, line) in text.lines().enumerate() {
+            let invalid = |message: String| Error::InvalidSession {
+                line: line_index + 1,
+                message,
+            };
+            let mut parts = line.split_whitespace();
+            let Some(seconds) = parts.next() else {
+                continue;
+            };
+            let seconds = seconds
+                .parse()
+                .map_err(|_| invalid(format!("invalid time {}", seconds)))?;
+            let penalty = parts.next().unwrap_or_default();
+            let penalty = Penalty::from_text(penalty)
+                .ok_or_else(|| invalid(format!("invalid penalty {}", penalty)))?;
+            let turns = parts.next().unwrap_or_default();
+            let turns = turns
+                .parse()
+                .map_err(|_| invalid(format!("invalid number of turns {}", turns)))?;
+            session.solves.push(Solve {
+                seconds,
+                penalty,
+                turns,
+                scramble: parts.collect::<Vec<_>>().join(" "),
+            });
+        }
+        Ok(session)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.solves
+            .iter()
+            .map(|solve| {
+                format!(
+                    "{:.3} {} {} {}\n",
+                    solve.seconds,
+                    solve.penalty.to_text(),
+                    solve.turns,
+                    solve.scramble
+                )
+            })
+            .collect()
+    }
+
+    pub fn best(&self) -> Option<f64> {
+        self.solves.iter().map(Solve::result).reduce(f64::min)
+    }
+
+    /// The mean of every result, which is a DNF if any solve is
+    pub fn mean(&self) -> Option<f64> {
+        mean(self.solves.iter().map(Solve::result))
+    }
+
+    /// The average of the last `count` results with the best and worst left out, like the ao5
+    /// and ao12 that speedsolvers use. It's only a DNF if more than one solve is.
+    /// None if there haven't been enough solves.
+    pub fn average_of(&self, count: usize) -> Option<f64> {
+        if count < 3 || self.solves.len() < count {
+            return None;
+        }
+        let mut results: Vec<f64> = self.solves[self.solves.len() - count..]
+            .iter()
+            .map(Solve::result)
+            .collect();
+        results.sort_by(f64::total_cmp);
+        mean(results[1..count - 1].iter().copied())
+    }
+
+    pub fn mean_turns(&self) -> Option<f64> {
+        mean(self.solves.iter().map(|solve| solve.turns as f64))
+    }
+
+    pub fn mean_turns_per_second(&self) -> Option<f64> {
+        mean(self.solves.iter().filter_map(Solve::turns_per_second))
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles;
+    use crate::solver::solver_by_name;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::rc::Rc;
+
+    fn solve(seconds: f64, penalty: Penalty) -> Solve {
+        Solve {
+            seconds,
+            penalty,
+            turns: 20,
+            scramble: "R U".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_timer() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let r = puzzle.turn_names.iter().position(|n| n == "R").unwrap();
+        let scrambled = puzzle.get_derived_state_turn_index(&puzzle.get_initial_state(), r);
+        let mut timer = Timer::new();
+        assert_eq!(timer.turn(0.0, &puzzle, &scrambled), None);
+        assert_eq!(timer.display(0.0), None);
+
+        timer.start_inspection(10.0, "R R".to_string());
+        assert_eq!(timer.display(11.5).as_deref(), Some("14"));
+        assert_eq!(timer.display(26.0).as_deref(), Some("+2"));
+        // Starting late, then solving with the second turn
+        assert_eq!(timer.turn(26.0, &puzzle, &scrambled), None);
+        assert_eq!(timer.display(27.5).as_deref(), Some("1.50"));
+        assert_eq!(
+            timer.turn(30.0, &puzzle, &puzzle.get_initial_state()),
+            Some(Solve {
+                seconds: 4.0,
+                penalty: Penalty::PlusTwo,
+                turns: 2,
+                scramble: "R R".to_string(),
+            })
+        );
+        assert_eq!(timer.phase(), TimerPhase::Idle);
+
+        timer.start_inspection(0.0, String::new());
+        timer.turn(20.0, &puzzle, &scrambled);
+        assert!(matches!(
+            timer.phase(),
+            TimerPhase::Solving {
+                penalty: Penalty::Dnf,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_solver_turns() {
+        let puzzle = Rc::new(puzzles::rubiks_cube_2x2());
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let scramble = puzzle.scramble_turns(20, &mut rng);
+        let mut state = puzzle.get_initial_state();
+        for &turn_index in &scramble {
+            state = puzzle.get_derived_state_turn_index(&state, turn_index);
+        }
+        let mut timer = Timer::new();
+        timer.start_inspection(0.0, String::new());
+        let solver = solver_by_name("phased", "2x2", &puzzle).unwrap().unwrap();
+        // Like the web UI, each of the solver's turns stops the timer instead of counting
+        for (i, turn_index) in solver.solve(state.clone()).unwrap().enumerate() {
+            timer.cancel();
+            state = puzzle.get_derived_state_turn_index(&state, turn_index);
+            assert_eq!(timer.turn(1.0 + i as f64, &puzzle, &state), None);
+        }
+        assert!(puzzle.is_solved(&state));
+        assert_eq!(timer.phase(), TimerPhase::Idle);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut session = Session::new("3x3");
+        assert_eq!(session.mean(), None);
+        for seconds in [10.0, 12.0, 20.0, 11.0] {
+            session.solves.push(solve(seconds, Penalty::None));
+        }
+        assert_eq!(session.average_of(5), None);
+        session.solves.push(solve(9.0, Penalty::PlusTwo));
+        // 10, 12, 20, 11 and 11, without the 10 and the 20
+        assert_eq!(session.average_of(5), Some(34.0 / 3.0));
+        assert_eq!(session.mean(), Some(64.0 / 5.0));
+        assert_eq!(session.best(), Some(10.0));
+        assert_eq!(session.mean_turns(), Some(20.0));
+
+        // One DNF is left out as the worst (with the 11 as the best),
+        // but two make the average a DNF
+        session.solves.push(solve(10.0, Penalty::Dnf));
+        assert_eq!(session.average_of(5), Some(43.0 / 3.0));
+        session.solves.push(solve(10.0, Penalty::Dnf));
+        assert_eq!(session.average_of(5), Some(f64::INFINITY));
+        assert_eq!(format_time(session.average_of(5).unwrap()), "DNF");
+        assert_eq!(format_time(9.876), "9.88");
+        assert_eq!(format_time(62.5), "1:02.50");
+        assert_eq!(format_time(59.999), "1:00.00");
+        assert_eq!(format_time(119.996), "2:00.00");
+
+        // A solve that took no time has no turns per second, and isn't counted in the mean
+        let mut session = Session::new("3x3");
+        session.solves.push(solve(10.0, Penalty::None));
+        session.solves.push(solve(0.0, Penalty::None));
+        assert_eq!(session.solves[0].turns_per_second(), Some(2.0));
+        assert_eq!(session.solves[1].turns_per_second(), None);
+        assert_eq!(session.mean_turns_per_second(), Some(2.0));
+    }
+
+    #[test]
+    fn test_storage() {
+        let mut storage = MemoryStorage::new();
+        assert_eq!(Session::load(&storage, "2x2"), Ok(Session::new("2x2")));
+
+        let mut session = Session::new("2x2");
+        session.solves.push(solve(12.5, Penalty::None));
+        session.solves.push(solve(3.25, Penalty::Dnf));
+        session.save(&mut storage).unwrap();
+        assert_eq!(Session::load(&storage, "2x2"), Ok(session));
+        assert_eq!(
+            Session::load(&storage, "3x3").unwrap().solves,
+            Vec::<Solve>::new()
+        );
+
+        storage
+            .set("twisty-puzzles.session.bad", "12.5 ok 20\n1.0 +3 20")
+            .unwrap();
+        assert_eq!(
+            Session::load(&storage, "bad"),
+            Err(Error::InvalidSession {
+                line: 2,
+                message: "invalid penalty +3".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::io::{self, Read, Write};
+
+use rand::Rng;
+
+use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+
+const MAGIC: &[u8; 4] = b"TWTD";
+const VERSION: u16 = 1;
+
+/// A scrambled state, labelled with how it was scrambled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrainingExample {
+    pub state: PuzzleState,
+    // The number of turns in the scramble,
+    // which is an upper bound on the number of turns needed to solve it
+    pub depth: usize,
+    // The turn that leaves the state with the lowest `TwistyPuzzle::heuristic_distance`,
+    // which is the one that undoes the last scramble turn unless another turn does better.
+    // It is an estimate, since solving the state exactly is far too slow for every example.
+    pub next_turn: usize,
+}
+
+/// A list of training examples for a single puzzle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrainingData {
+    pub num_faces: usize,
+    pub num_turns: usize,
+    pub examples: Vec<TrainingExample>,
+}
+
+impl TrainingData {
+    pub fn new(puzzle: &TwistyPuzzle) -> Self {
+        Self {
+            num_faces: puzzle.get_num_faces(),
+            num_turns: puzzle.turn_names.len(),
+            examples: vec![],
+        }
+    }
+
+    /// Scrambles the puzzle with `TwistyPuzzle::scramble_turns`,
+    /// adding an example for every state along the way,
+    /// labelled with the best next turn from it (see `TrainingExample::next_turn`)
+    pub fn add_random_walk<T: Rng>(&mut self, puzzle: &TwistyPuzzle, depth: usize, rng: &mut T) {
+        let mut state = puzzle.get_initial_state();
+        let turns = puzzle.scramble_turns(depth as u64, rng);
+        for (i, turn_index) in turns.into_iter().enumerate() {
+            state = puzzle.get_derived_state_turn_index(&state, turn_index);
+            self.examples.push(TrainingExample {
+                next_turn: best_next_turn(puzzle, &state, puzzle.inverted_turn_index(turn_index)),
+                state: state.clone(),
+                depth: i + 1,
+            });
+        }
+    }
+
+    /// Writes the compact binary format:
+    /// a header with the magic bytes, version, number of faces, and number of turns,
+    /// followed by each example's face values, depth, and next turn as little endian u16s.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.num_faces as u32).to_le_bytes())?;
+        writer.write_all(&(self.num_turns as u32).to_le_bytes())?;
+        for example in &self.examples {
+            for &value in example.state.iter() {
+                write_u16(writer, value)?;
+            }
+            write_u16(writer, example.depth)?;
+            write_u16(writer, example.next_turn)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a training data file"));
+        }
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        if u16::from_le_bytes(version) != VERSION {
+            return Err(invalid_data("unsupported training data version"));
+        }
+        let mut count = [0; 4];
+        reader.read_exact(&mut count)?;
+        let num_faces = u32::from_le_bytes(count) as usize;
+        reader.read_exact(&mut count)?;
+        let num_turns = u32::from_le_bytes(count) as usize;
+
+        let record_len = (num_faces + 2) * 2;
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        if bytes.len() % record_len != 0 {
+            return Err(invalid_data("truncated training example"));
+        }
+        let examples = bytes
+            .chunks_exact(record_len)
+            .map(|record| {
+                let mut values = record
+                    .chunks_exact(2)
+                    .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize);
+                let state: PuzzleState = values.by_ref().take(num_faces).collect();
+                TrainingExample {
+                    state,
+                    depth: values.next().unwrap(),
+                    next_turn: values.next().unwrap(),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            num_faces,
+            num_turns,
+            examples,
+        })
+    }
+
+    /// Writes one line per example: the face values, then the depth, then the next turn
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let face_columns: Vec<String> = (0..self.num_faces).map(|i| format!("f{}", i)).collect();
+        writeln!(writer, "{},depth,next_turn", face_columns.join(","))?;
+        for example in &self.examples {
+            let values: Vec<String> = example.state.iter().map(|v| v.to_string()).collect();
+            writeln!(
+                writer,
+                "{},{},{}",
+                values.join(","),
+                example.depth,
+                example.next_turn
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The turn that leaves the state closest to solved by the heuristic,
+/// preferring the one that undoes the last scramble turn when it is as good as any
+fn best_next_turn(puzzle: &TwistyPuzzle, state: &PuzzleState, undo_turn: usize) -> usize {
+    let distances: Vec<usize> = (0..puzzle.turn_names.len())
+        .map(|turn_index| {
+            puzzle.heuristic_distance(&puzzle.get_derived_state_turn_index(state, turn_index))
+        })
+        .collect();
+    (0..distances.len())
+        .min_by_key(|&turn_index| (distances[turn_index], turn_index != undo_turn))
+        .unwrap_or(undo_turn)
+}
+
+fn write_u16<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    let value = u16::try_from(value).map_err(|_| invalid_data("value does not fit in a u16"))?;
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::puzzles;
+
+    #[test]
+    fn test_training_data() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut data = TrainingData::new(&puzzle);
+        data.add_random_walk(&puzzle, 10, &mut rng);
+        data.add_random_walk(&puzzle, 10, &mut rng);
+        assert_eq!(data.examples.len(), 20);
+
+        for example in &data.examples {
+            // No turn gets closer to solved by the heuristic than the next turn
+            let distance = |turn_index: usize| {
+                puzzle.heuristic_distance(
+                    &puzzle.get_derived_state_turn_index(&example.state, turn_index),
+                )
+            };
+            let best_distance = (0..puzzle.turn_names.len()).map(distance).min().unwrap();
+            assert_eq!(distance(example.next_turn), best_distance);
+            // One turn from solved, undoing it is the best turn
+            if example.depth == 1 {
+                assert!(puzzle.is_solved(
+                    &puzzle.get_derived_state_turn_index(&example.state, example.next_turn)
+                ));
+            }
+        }
+
+        let mut bytes = vec![];
+        data.write(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 14 + 20 * (24 + 2) * 2);
+        assert_eq!(TrainingData::read(&mut bytes.as_slice()).unwrap(), data);
+
+        bytes.pop();
+        assert!(TrainingData::read(&mut bytes.as_slice()).is_err());
+
+        let mut csv = vec![];
+        data.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 21);
+        assert!(csv.lines().next().unwrap().ends_with("f23,depth,next_turn"));
+    }
+}
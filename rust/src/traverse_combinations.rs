@@ -116,7 +116,7 @@ mod tests {
             },
             &mut |str| {
                 calls.push(str.to_owned());
-                if str.chars().nth(0) == Some('a') {
+                if str.starts_with('a') {
                     TraverseResult::Skip
                 } else {
                     TraverseResult::Continue
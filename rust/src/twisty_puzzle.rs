@@ -1,14 +1,23 @@
+#[cfg(feature = "count-states")]
+use std::cell::Cell;
+use std::cell::OnceCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::f64::consts::TAU;
 
 use crate::bijection::Bijection;
+use crate::error::{Error, Result};
+use crate::permutation_group;
+use crate::piece_distance::PieceDistanceTable;
 use crate::point_in_space_map::PointInSpaceMap;
+use crate::predicates::side_of_plane;
 use crate::rotation3d::Rotation3D;
+use num::BigUint;
 use rand::distributions::Uniform;
 use rand::Rng;
 
 use crate::plane::Plane;
-use crate::polyhedron::{Face, Polyhedron};
+use crate::polyhedron::{Edge, Face, Polyhedron};
 use crate::ray::Ray;
 use crate::vector3d::Vector3D;
 
@@ -39,15 +48,41 @@ impl<'a> CutDefinition<'a> {
 
 type ColorIndex = usize;
 
+/// Decides what the values of a [`PuzzleState`] represent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum StickerMode {
+    /// Each face is only known by its color,
+    /// so faces with the same color are interchangeable
+    Color,
+    /// Each face has its own identity (supercubes and picture puzzles).
+    /// Faces which are centered on the axis of a turn are split up around their center,
+    /// so that the orientation of center pieces is tracked too.
+    Identity,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct PieceFace {
     pub face: Face,
     pub color_index: ColorIndex,
+    /// In identity sticker mode, the index of the face that this sticker started on
+    pub sticker_index: Option<usize>,
+    /// Whether this sticker is marked to make its orientation visible
+    pub orientation_marker: bool,
     /// List of turn indices that move this face
     affecting_turn_indices: Vec<usize>,
 }
 
+impl PieceFace {
+    #[inline]
+    pub fn is_turned_by(&self, turn_index: usize) -> bool {
+        self.affecting_turn_indices.contains(&turn_index)
+    }
+}
+
 #[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 struct PhysicalTurn {
     rotation_amount: f64,
     rotation_axis: Vector3D,
@@ -55,6 +90,7 @@ struct PhysicalTurn {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct Turn {
     // The indices of this vector are the new face indexes.
     // The values are the old face indexes to pull colors from.
@@ -62,6 +98,7 @@ pub(crate) struct Turn {
     physical_turn: PhysicalTurn,
 }
 
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TwistyPuzzle {
     pub faces: Vec<PieceFace>,
     pub(crate) turns: Vec<Turn>,
@@ -70,10 +107,25 @@ pub struct TwistyPuzzle {
     pieces: Vec<Vec<usize>>,
     // Map from face map to symmetry objects
     pub symmetries: HashMap<Bijection, Symmetry>,
+    // Face maps for every orientation of the whole puzzle.
+    // Unlike symmetries, these don't need to map each turn onto another turn.
+    pub rotations: Vec<Bijection>,
     pub piece_types: Vec<PieceType>,
+    sticker_mode: StickerMode,
+    initial_state: PuzzleState,
+    // The uncut shape, which the net view unfolds
+    pub(crate) polyhedron: Polyhedron,
+    // Built the first time a heuristic distance is requested
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    piece_distance_table: OnceCell<PieceDistanceTable>,
+    // Counts the states derived from other states, to measure how much work solvers do
+    #[cfg(feature = "count-states")]
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    num_derived_states: Cell<usize>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct PieceType {
     face_mask: Vec<bool>,
 }
@@ -84,26 +136,63 @@ impl PieceType {
     }
 }
 
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Symmetry {
     pub face_map: Bijection,
     pub turn_map: Bijection,
+    // The indices of this vector are the original colors (or sticker indices).
+    // The values are the colors they become after the symmetry is applied.
+    pub color_map: Bijection,
 }
 
 impl TwistyPuzzle {
-    pub fn new(polyhedron: &Polyhedron, cuts: &[CutDefinition]) -> Self {
+    pub fn new(polyhedron: &Polyhedron, cuts: &[CutDefinition]) -> Result<Self> {
+        Self::new_with_sticker_mode(polyhedron, cuts, StickerMode::Color)
+    }
+
+    pub fn new_with_sticker_mode(
+        polyhedron: &Polyhedron,
+        cuts: &[CutDefinition],
+        sticker_mode: StickerMode,
+    ) -> Result<Self> {
+        if polyhedron.faces.is_empty() {
+            return Err(Error::InvalidGeometry(
+                "the polyhedron has no faces".to_string(),
+            ));
+        }
         let mut physical_turns: Vec<(String, PhysicalTurn)> = vec![];
         let mut inferred_name_i = b'A';
-        let cuts_with_names = cuts.iter().map(|cut| {
-            let cut_name = match cut.name {
-                Some(name) => name.to_string(),
-                None => {
-                    let char = inferred_name_i as char;
-                    inferred_name_i += 1;
-                    char.to_string()
+        let cuts_with_names: Vec<_> = cuts
+            .iter()
+            .map(|cut| {
+                let cut_name = match cut.name {
+                    Some(name) => name.to_string(),
+                    None => {
+                        let char = inferred_name_i as char;
+                        inferred_name_i += 1;
+                        char.to_string()
+                    }
+                };
+                (cut_name, cut)
+            })
+            .collect();
+        // Two cuts in the same place would make pieces that are thinner than the gap between them
+        for (i, (name_a, cut_a)) in cuts_with_names.iter().enumerate() {
+            for (name_b, cut_b) in &cuts_with_names[i + 1..] {
+                if cut_a
+                    .plane
+                    .normal
+                    .to_unit_vector()
+                    .approx_equals(&cut_b.plane.normal.to_unit_vector())
+                    && cut_a.plane.distance_to(&cut_b.plane.point).abs() < 2.0 * CUT_PLANE_THICKNESS
+                {
+                    return Err(Error::InvalidGeometry(format!(
+                        "cuts {} and {} are in the same place",
+                        name_a, name_b
+                    )));
                 }
-            };
-            (cut_name, cut)
-        });
+            }
+        }
         let mut faces: Vec<PieceFace> = polyhedron
             .faces
             .iter()
@@ -111,9 +200,13 @@ impl TwistyPuzzle {
             .map(|(color_index, face)| PieceFace {
                 face: face.clone(),
                 color_index: color_index as _,
+                sticker_index: None,
+                orientation_marker: false,
                 affecting_turn_indices: vec![],
             })
             .collect();
+        // The polyhedron face that each face was cut from, to say which one a cut leaves nothing of
+        let mut polyhedron_face_indices: Vec<usize> = (0..faces.len()).collect();
         for (turn_name, cut) in cuts_with_names {
             let inverted_turn_name = format!("{}'", turn_name);
             let rotation_axis = cut.plane.normal.to_unit_vector();
@@ -136,81 +229,71 @@ impl TwistyPuzzle {
             let forwards_turn_index = physical_turns.len() - 1;
             let reverse_turn_index = physical_turns.len() - 2;
             let mut updated_faces: Vec<PieceFace> = vec![];
+            let mut updated_polyhedron_face_indices: Vec<usize> = vec![];
             let cut_plane_outer = cut.plane.offset(CUT_PLANE_THICKNESS);
-            let cut_plane_inner = cut.plane.offset(-CUT_PLANE_THICKNESS);
-            for PieceFace {
-                face,
-                color_index,
-                affecting_turn_indices,
-            } in &faces
+            // Facing downwards, so that clipping keeps the part below the cut
+            let cut_plane_inner = Plane {
+                normal: -1.0 * cut.plane.normal,
+                ..cut.plane.offset(-CUT_PLANE_THICKNESS)
+            };
+            for (
+                PieceFace {
+                    face,
+                    color_index,
+                    affecting_turn_indices,
+                    ..
+                },
+                &polyhedron_face_index,
+            ) in faces.iter().zip(&polyhedron_face_indices)
             {
-                let mut vertices_above_plane = VertexList::new();
-                let mut vertices_below_plane = VertexList::new();
-                // Pairs of (vertex, is_above_cut_plane)
-                let vertices_with_status: Vec<_> = face
-                    .vertices
-                    .iter()
-                    // Make the last vertex appear again at the end so all edges are included
-                    .chain(std::iter::once(&face.vertices[0]))
-                    .map(|vertex| {
-                        let is_above_cut_plane =
-                            (vertex - cut.plane.point).dot(&cut.plane.normal) > 0.0;
-                        (vertex, is_above_cut_plane)
-                    })
-                    .collect();
-                let edges = vertices_with_status.windows(2);
-                for edge in edges {
-                    let (&vertex_a, a_is_above_plane) = edge[0];
-                    let (&vertex_b, b_is_above_plane) = edge[1];
-                    if a_is_above_plane && b_is_above_plane {
-                        vertices_above_plane.push(vertex_a);
-                    } else if !a_is_above_plane && !b_is_above_plane {
-                        vertices_below_plane.push(vertex_a);
-                    } else {
-                        // This edge crosses the plane
-                        if a_is_above_plane {
-                            vertices_above_plane.push(vertex_a);
-                        } else {
-                            vertices_below_plane.push(vertex_a);
-                        }
-                        let edge_ray = Ray {
-                            point: vertex_a,
-                            direction: vertex_a - &vertex_b,
-                        };
-                        let above_intersection = cut_plane_outer.intersection(&edge_ray);
-                        let below_intersection = cut_plane_inner.intersection(&edge_ray);
-                        vertices_above_plane.push(above_intersection);
-                        vertices_below_plane.push(below_intersection);
-                    }
+                // Parts that are thinner than the gap between the two sides of the cut
+                // (like where the cut only just clips a corner) are left in the gap,
+                // so that cuts through vertices and edges don't make zero-size pieces
+                let face_above_plane = clip_face(face, &cut_plane_outer).filter(|f| !is_sliver(f));
+                let face_below_plane = clip_face(face, &cut_plane_inner).filter(|f| !is_sliver(f));
+                if face_above_plane.is_none() && face_below_plane.is_none() {
+                    return Err(Error::DegeneratePiece {
+                        face_index: polyhedron_face_index,
+                    });
                 }
-                let vertices_above_plane = vertices_above_plane.to_vec();
-                let vertices_below_plane = vertices_below_plane.to_vec();
 
-                if vertices_above_plane.len() > 2 {
+                if let Some(face) = face_above_plane {
                     let mut new_affecting_turn_indices = affecting_turn_indices.clone();
                     new_affecting_turn_indices.push(forwards_turn_index);
                     new_affecting_turn_indices.push(reverse_turn_index);
                     updated_faces.push(PieceFace {
-                        face: Face {
-                            vertices: vertices_above_plane,
-                        },
+                        face,
                         color_index: *color_index,
+                        sticker_index: None,
+                        orientation_marker: false,
                         affecting_turn_indices: new_affecting_turn_indices,
                     });
+                    updated_polyhedron_face_indices.push(polyhedron_face_index);
                 }
-                if vertices_below_plane.len() > 2 {
+                if let Some(face) = face_below_plane {
                     updated_faces.push(PieceFace {
-                        face: Face {
-                            vertices: vertices_below_plane,
-                        },
+                        face,
                         color_index: *color_index,
+                        sticker_index: None,
+                        orientation_marker: false,
                         affecting_turn_indices: affecting_turn_indices.clone(),
                     });
+                    updated_polyhedron_face_indices.push(polyhedron_face_index);
                 }
             }
             faces = updated_faces;
+            polyhedron_face_indices = updated_polyhedron_face_indices;
+        }
+
+        if sticker_mode == StickerMode::Identity {
+            faces = split_centered_faces(faces, &physical_turns);
         }
 
+        let initial_state: PuzzleState = match sticker_mode {
+            StickerMode::Color => faces.iter().map(|face| face.color_index).collect(),
+            StickerMode::Identity => (0..faces.len()).collect(),
+        };
+
         // Pieces decides which physical faces are attached together
         let mut pieces_map: HashMap<_, Vec<usize>> = HashMap::new();
         for (face_i, face) in faces.iter().enumerate() {
@@ -232,8 +315,23 @@ impl TwistyPuzzle {
 
         let mut original_face_centers_map = PointInSpaceMap::new();
         for (i, face) in face_centers.iter().enumerate() {
-            original_face_centers_map.insert(*face, i);
+            if original_face_centers_map.insert(*face, i)?.is_some() {
+                return Err(Error::InvalidGeometry(
+                    "two faces are in the same place".to_string(),
+                ));
+            }
         }
+        // Find the index in the old faces array which corresponds to the new position
+        let face_at = |location: &Vector3D| -> Result<Option<usize>> {
+            Ok(original_face_centers_map.get(location)?.copied())
+        };
+        let symmetry_face_at = |location: &Vector3D| {
+            face_at(location)?.ok_or_else(|| {
+                Error::InvalidGeometry(
+                    "the cuts do not have the same symmetry as the polyhedron".to_string(),
+                )
+            })
+        };
 
         // try out each of the turns to determine the correspondence between pieces
         // and which faces map to which faces after each turn
@@ -254,23 +352,25 @@ impl TwistyPuzzle {
                                     original_location,
                                     &physical_turn.rotation_axis_point,
                                 );
-                                // Find the index in the old faces array
-                                // which corresponds to the new position
-                                *original_face_centers_map.get(&new_location).unwrap()
+                                face_at(&new_location)?.ok_or_else(|| Error::UnmatchedTurnMapping {
+                                    turn_name: turn_name.clone(),
+                                })
                             } else {
                                 // this turn does not affect this face; map to itself
-                                i
+                                Ok(i)
                             }
                         })
-                        .collect(),
+                        .collect::<Result<_>>()?,
                 );
 
                 let turn = Turn {
                     physical_turn,
                     face_map: face_map.invert(),
                 };
-                (turn_name, turn)
+                Ok((turn_name, turn))
             })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
             .unzip();
 
         let top_face = &polyhedron.faces[0];
@@ -281,7 +381,7 @@ impl TwistyPuzzle {
         );
 
         #[cfg(test)]
-        assert!((TAU / face_rotation_angle) % 1.0 < std::f64::EPSILON);
+        assert!((TAU / face_rotation_angle) % 1.0 < f64::EPSILON);
 
         let num_top_rotations = (TAU / face_rotation_angle).round() as usize;
         let top_rotation = Rotation3D::new(&top_face.plane().point, face_rotation_angle);
@@ -293,12 +393,9 @@ impl TwistyPuzzle {
                 .enumerate()
                 .map(|(i, _face)| {
                     let original_location = &face_centers[i];
-                    let new_location = top_rotation.rotate_point_about_origin(original_location);
-                    // Find the index in the old faces array
-                    // which corresponds to the new position
-                    *original_face_centers_map.get(&new_location).unwrap()
+                    symmetry_face_at(&top_rotation.rotate_point_about_origin(original_location))
                 })
-                .collect(),
+                .collect::<Result<_>>()?,
         );
 
         // Face map which mirrors all the faces across a center plane
@@ -313,23 +410,22 @@ impl TwistyPuzzle {
                         -original_location.y,
                         original_location.z,
                     );
-                    // Find the index in the old faces array
-                    // which corresponds to the new position
-                    *original_face_centers_map.get(&new_location).unwrap()
+                    symmetry_face_at(&new_location)
                 })
-                .collect(),
+                .collect::<Result<_>>()?,
         );
 
-        let symmetry_face_maps = polyhedron
+        // Face maps for every orientation that the whole puzzle can be rotated into
+        let rotation_face_maps: Vec<Bijection> = polyhedron
             .faces
             .iter()
-            .flat_map(|new_top_face| {
+            .map(|new_top_face| {
                 // Move the new_top_face to the top,
                 // and then rotate it to align it with the original top face position
                 let rotation_to_top_angle =
                     Vector3D::angle_between(&new_top_face.plane().point, &top_face.plane().point);
                 let rotation_to_top_axis =
-                    if (rotation_to_top_angle - std::f64::consts::PI).abs() > std::f64::EPSILON {
+                    if (rotation_to_top_angle - std::f64::consts::PI).abs() > f64::EPSILON {
                         new_top_face.plane().point.cross(&top_face.plane().point)
                     } else {
                         // Rotation of 180deg (original bottom face becomes top)
@@ -348,8 +444,24 @@ impl TwistyPuzzle {
                     &(&new_vertex_position_unaligned - &top_face.plane().point),
                     &(&top_face.vertices[0] - &top_face.plane().point),
                 );
+                // The angle between them doesn't say which way to turn, so try both.
+                // (Always turning one way misplaces some of the faces of Eitan's star,
+                // so its cuts look like they don't have the polyhedron's symmetry.)
                 let top_alignment_rotation =
-                    Rotation3D::new(top_alignment_rotation_axis, top_alignment_rotation_angle);
+                    [top_alignment_rotation_angle, -top_alignment_rotation_angle]
+                        .iter()
+                        .map(|&angle| Rotation3D::new(top_alignment_rotation_axis, angle))
+                        .find(|rotation| {
+                            rotation
+                                .rotate_point_about_origin(&new_vertex_position_unaligned)
+                                .approx_equals(&top_face.vertices[0])
+                        })
+                        .ok_or_else(|| {
+                            Error::InvalidGeometry(
+                                "the faces of the polyhedron are not all the same shape"
+                                    .to_string(),
+                            )
+                        })?;
                 let combined_rotation =
                     Rotation3D::combine_rotations(&rotation_to_top, &top_alignment_rotation);
 
@@ -365,22 +477,29 @@ impl TwistyPuzzle {
                         .enumerate()
                         .map(|(i, _face)| {
                             let original_location = &face_centers[i];
-                            let new_location =
-                                combined_rotation.rotate_point_about_origin(original_location);
-                            // Find the index in the old faces array
-                            // which corresponds to the new position
-                            *original_face_centers_map.get(&new_location).unwrap()
+                            symmetry_face_at(
+                                &combined_rotation.rotate_point_about_origin(original_location),
+                            )
                         })
-                        .collect(),
+                        .collect::<Result<_>>()?,
                 )
                 .invert();
 
-                (1..num_top_rotations).fold(vec![face_map_to_top], |mut prev_maps, _| {
-                    prev_maps.push(prev_maps.last().unwrap().apply(&top_rotation_face_map));
-                    prev_maps
-                })
+                Ok(
+                    (1..num_top_rotations).fold(vec![face_map_to_top], |mut prev_maps, _| {
+                        prev_maps.push(prev_maps.last().unwrap().apply(&top_rotation_face_map));
+                        prev_maps
+                    }),
+                )
             })
-            .flat_map(|face_map| [mirror_face_map.apply(&face_map), face_map]);
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let symmetry_face_maps = rotation_face_maps
+            .iter()
+            .flat_map(|face_map| [mirror_face_map.apply(face_map), face_map.clone()]);
         // yes, this is wrong (it generates only duplicates)
         // But, we _can_ make mirrored versions of everything
         // (we need to find an axis to mirror through)
@@ -409,7 +528,20 @@ impl TwistyPuzzle {
                         })
                         .collect::<Option<_>>()?,
                 );
-                Some((face_map.clone(), Symmetry { face_map, turn_map }))
+                // The symmetry moves each face to a new position,
+                // so the color of the face takes on the color that was originally at that position
+                let mut color_map = vec![0; initial_state.iter().max().unwrap() + 1];
+                for (face_index, &color_index) in initial_state.iter().enumerate() {
+                    color_map[color_index] = initial_state[face_map.0[face_index]];
+                }
+                Some((
+                    face_map.clone(),
+                    Symmetry {
+                        face_map,
+                        turn_map,
+                        color_map: Bijection(color_map),
+                    },
+                ))
             })
             .collect();
 
@@ -478,14 +610,21 @@ impl TwistyPuzzle {
         // Force deterministic sorting
         face_piece_types.sort_by(|a, b| a.face_mask().cmp(b.face_mask()).reverse());
 
-        Self {
+        Ok(Self {
             faces,
             turns,
             turn_names,
             pieces,
             symmetries,
+            rotations: rotation_face_maps,
             piece_types: face_piece_types,
-        }
+            sticker_mode,
+            initial_state,
+            polyhedron: polyhedron.clone(),
+            piece_distance_table: OnceCell::new(),
+            #[cfg(feature = "count-states")]
+            num_derived_states: Cell::new(0),
+        })
     }
 
     #[inline]
@@ -497,7 +636,7 @@ impl TwistyPuzzle {
     pub fn inverted_turn_index(&self, turn_index: usize) -> usize {
         // If turns array is [a, b, c, d]
         // a and b are inverses, c and d are inverses
-        if turn_index % 2 == 0 {
+        if turn_index.is_multiple_of(2) {
             // Even: get one above, e.g. a -> b, c -> d
             turn_index + 1
         } else {
@@ -511,6 +650,43 @@ impl TwistyPuzzle {
         self.pieces.len()
     }
 
+    /// The face indexes of each piece
+    #[inline]
+    pub(crate) fn pieces(&self) -> &[Vec<usize>] {
+        &self.pieces
+    }
+
+    /// Where each cut goes through the puzzle, as the plane from [`Self::get_turn_cut_plane`]
+    /// along with the turn that it is the plane of. There is a pair of turns for each cut
+    /// (a turn and its inverse), so that is the first turn of each pair.
+    /// Cuts that don't have any faces on their turning side are left out.
+    pub(crate) fn cut_planes(&self) -> Vec<(usize, Plane)> {
+        (0..self.turns.len())
+            .step_by(2)
+            .filter_map(|turn_index| Some((turn_index, self.get_turn_cut_plane(turn_index)?)))
+            .collect()
+    }
+
+    /// The plane of the turn's cut, with the normal pointing towards the part that turns,
+    /// which is the side that the faces that it turns are on.
+    /// Returns None if the turn doesn't move anything.
+    pub(crate) fn get_turn_cut_plane(&self, turn_index: usize) -> Option<Plane> {
+        let physical_turn = &self.turns[turn_index].physical_turn;
+        let point = physical_turn.rotation_axis_point;
+        let turning_face = self
+            .faces
+            .iter()
+            .find(|piece_face| piece_face.is_turned_by(turn_index))?;
+        let axis = physical_turn.rotation_axis.to_unit_vector();
+        let normal =
+            if (Vector3D::from_average(&turning_face.face.vertices) - &point).dot(&axis) > 0.0 {
+                axis
+            } else {
+                -1.0 * axis
+            };
+        Some(Plane { point, normal })
+    }
+
     pub fn get_num_pieces_of_type(&self, piece_type: &PieceType) -> usize {
         self.pieces
             .iter()
@@ -531,7 +707,7 @@ impl TwistyPuzzle {
             .iter()
             .enumerate()
             .map(|(i, &color_index)| {
-                piece_type.face_mask[i] && color_index == self.faces[i].color_index
+                piece_type.face_mask[i] && color_index == self.initial_state[i]
             })
             .collect();
 
@@ -554,24 +730,130 @@ impl TwistyPuzzle {
         )
     }
 
-    // TODO: implement
+    /// Checks whether the puzzle is solved in its original orientation
+    pub fn is_solved(&self, puzzle_state: &PuzzleState) -> bool {
+        *puzzle_state == self.initial_state
+    }
 
-    pub fn get_num_unoriented_pieces_of_type(
-        &self,
-        puzzle_state: &PuzzleState,
-        piece_type: &PieceType,
-    ) -> usize {
-        todo!()
+    /// Counts the solved pieces after factoring out rotations of the whole puzzle,
+    /// using whichever orientation has the most solved pieces.
+    pub fn get_num_solved_pieces_any_orientation(&self, puzzle_state: &PuzzleState) -> usize {
+        self.rotations
+            .iter()
+            .map(|rotation| {
+                let rotated_state = self.get_derived_state(puzzle_state, rotation);
+                self.get_num_solved_pieces(&rotated_state)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Checks whether the puzzle is solved in any orientation,
+    /// so that a puzzle which is solved but rotated as a whole (e.g. a Skewb) counts as solved
+    pub fn is_solved_any_orientation(&self, puzzle_state: &PuzzleState) -> bool {
+        self.rotations.iter().any(|rotation| {
+            let rotated_state = self.get_derived_state(puzzle_state, rotation);
+            self.is_solved(&rotated_state)
+        })
+    }
+
+    fn piece_distance_table(&self) -> &PieceDistanceTable {
+        self.piece_distance_table.get_or_init(|| {
+            let face_maps: Vec<&Bijection> = self.turns.iter().map(|t| &t.face_map).collect();
+            PieceDistanceTable::new(&self.pieces, &face_maps)
+        })
+    }
+
+    /// The minimum number of turns for each piece to be in the correct position
+    /// and oriented correctly, if the rest of the puzzle could be ignored.
+    /// The result is in the same order as the pieces.
+    pub fn get_piece_distances(&self, puzzle_state: &PuzzleState) -> Vec<usize> {
+        self.piece_distance_table()
+            .piece_distances(puzzle_state, &self.initial_state)
+            .collect()
+    }
+
+    /// Estimates the number of turns left to solve the puzzle,
+    /// by summing how far each piece is from being solved.
+    /// This is more informative than counting solved pieces,
+    /// but overestimates since one turn moves many pieces at once.
+    pub fn heuristic_distance(&self, puzzle_state: &PuzzleState) -> usize {
+        self.piece_distance_table()
+            .piece_distances(puzzle_state, &self.initial_state)
+            .sum()
+    }
+
+    /// A lower bound on the number of turns left to solve the puzzle:
+    /// the distance of the piece which is furthest from being solved.
+    pub fn max_piece_distance(&self, puzzle_state: &PuzzleState) -> usize {
+        self.piece_distance_table()
+            .piece_distances(puzzle_state, &self.initial_state)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Moves the whole puzzle according to the symmetry,
+    /// recoloring the faces so that the solved state maps to itself.
+    /// Turning the resulting state by `symmetry.turn_map` of a turn
+    /// is equivalent to applying the symmetry after the original turn.
+    pub fn apply_symmetry(&self, puzzle_state: &PuzzleState, symmetry: &Symmetry) -> PuzzleState {
+        let mut new_state = vec![0; puzzle_state.len()];
+        for (face_index, &color_index) in puzzle_state.iter().enumerate() {
+            new_state[symmetry.face_map.0[face_index]] = symmetry.color_map.0[color_index];
+        }
+        new_state
+    }
+
+    /// Returns the canonical representative of the state under the puzzle symmetries.
+    /// States which are the same up to a symmetry all return the same canonical state,
+    /// so this can be used to dedupe equivalent states in searches and lookup tables.
+    pub fn canonicalize(&self, puzzle_state: &PuzzleState) -> PuzzleState {
+        self.symmetries
+            .values()
+            .map(|symmetry| self.apply_symmetry(puzzle_state, symmetry))
+            .fold(puzzle_state.clone(), |canonical_state, symmetric_state| {
+                if symmetric_state < canonical_state {
+                    symmetric_state
+                } else {
+                    canonical_state
+                }
+            })
+    }
+
+    #[inline]
+    pub fn sticker_mode(&self) -> StickerMode {
+        self.sticker_mode
+    }
+
+    /// Creates the face that is displayed at a position,
+    /// given the value of the puzzle state at that position
+    fn sticker_face(&self, face: Face, state_value: usize, piece_face: &PieceFace) -> PieceFace {
+        let (color_index, sticker_index, orientation_marker) = match self.sticker_mode {
+            StickerMode::Color => (state_value, None, false),
+            StickerMode::Identity => {
+                let sticker = &self.faces[state_value];
+                (
+                    sticker.color_index,
+                    Some(state_value),
+                    sticker.orientation_marker,
+                )
+            }
+        };
+        PieceFace {
+            face,
+            color_index,
+            sticker_index,
+            orientation_marker,
+            affecting_turn_indices: piece_face.affecting_turn_indices.clone(),
+        }
     }
 
     pub fn faces(&self, puzzle_state: &PuzzleState) -> Vec<PieceFace> {
         self.faces
             .iter()
             .enumerate()
-            .map(|(i, piece_face)| PieceFace {
-                face: piece_face.face.clone(),
-                affecting_turn_indices: piece_face.affecting_turn_indices.clone(),
-                color_index: puzzle_state[i],
+            .map(|(i, piece_face)| {
+                self.sticker_face(piece_face.face.clone(), puzzle_state[i], piece_face)
             })
             .collect()
     }
@@ -583,31 +865,192 @@ impl TwistyPuzzle {
         interpolate_amount: f64,
     ) -> Vec<PieceFace> {
         let cut = &self.turns[turn_index];
-        let rotation = Rotation3D::new(
-            &cut.physical_turn.rotation_axis,
-            cut.physical_turn.rotation_amount * interpolate_amount,
-        );
+        let rotation = self.get_turn_rotation(turn_index, interpolate_amount);
         let new_faces = self
             .faces
             .iter()
             .enumerate()
-            .map(|(i, piece_face)| PieceFace {
-                face: if piece_face.affecting_turn_indices.contains(&turn_index) {
+            .map(|(i, piece_face)| {
+                let face = if piece_face.is_turned_by(turn_index) {
                     piece_face
                         .face
-                        .rotate_about_axis(&rotation, &cut.physical_turn.rotation_axis_point)
+                        .rotate(&rotation, &cut.physical_turn.rotation_axis_point)
                 } else {
                     piece_face.face.clone()
-                },
-                affecting_turn_indices: piece_face.affecting_turn_indices.clone(),
-                color_index: puzzle_state[i],
+                };
+                self.sticker_face(face, puzzle_state[i], piece_face)
             })
             .collect();
         new_faces
     }
 
+    /// How the turn rotates the part that it moves, partway through the turn (from 0 to 1),
+    /// about an axis through the turn's `rotation_axis_point`
+    pub(crate) fn get_turn_rotation(
+        &self,
+        turn_index: usize,
+        interpolate_amount: f64,
+    ) -> Rotation3D {
+        let physical_turn = &self.turns[turn_index].physical_turn;
+        Rotation3D::new(
+            &physical_turn.rotation_axis,
+            physical_turn.rotation_amount * interpolate_amount,
+        )
+    }
+
+    /// Which way a point on the part that the turn moves starts moving when the turn begins
+    pub fn get_turn_direction(&self, turn_index: usize, point: &Vector3D) -> Vector3D {
+        let physical_turn = &self.turns[turn_index].physical_turn;
+        physical_turn
+            .rotation_axis
+            .cross(&(point - physical_turn.rotation_axis_point))
+    }
+
+    /// The inside of the puzzle where the turn's cut goes through it, partway through the turn:
+    /// the side of the part that stays still (facing the part that turns),
+    /// and the side of the part that turns (facing the other way, and turned along with it).
+    /// Returns None if the turn doesn't move anything.
+    pub fn get_turn_cross_sections(
+        &self,
+        turn_index: usize,
+        interpolate_amount: f64,
+    ) -> Option<(Face, Face)> {
+        let Plane {
+            point,
+            normal: towards_turning,
+        } = self.get_turn_cut_plane(turn_index)?;
+
+        // Start with a square in the cut plane that is bigger than the puzzle,
+        // and cut away everything that is outside of the puzzle's faces
+        let size = self
+            .faces
+            .iter()
+            .flat_map(|piece_face| &piece_face.face.vertices)
+            .map(|vertex| (vertex - point).magnitude())
+            .fold(0.0, f64::max)
+            * 2.0;
+        let not_parallel = if towards_turning.x.abs() < 0.9 {
+            Vector3D::new(1.0, 0.0, 0.0)
+        } else {
+            Vector3D::new(0.0, 1.0, 0.0)
+        };
+        let u = size * towards_turning.cross(&not_parallel).to_unit_vector();
+        let v = towards_turning.cross(&u);
+        let mut cross_section = Face {
+            vertices: vec![
+                &(&point + &u) + &v,
+                &(&point - &u) + &v,
+                &(&point - &u) - &v,
+                &(&point + &u) - &v,
+            ],
+        };
+        if cross_section.normal().dot(&towards_turning) < 0.0 {
+            cross_section.vertices.reverse();
+        }
+        // The polyhedron's faces are the outside of the puzzle. The faces of the pieces would be
+        // too, but cutting can leave their first vertices in a line, which `plane()` can't handle.
+        for face in &self.polyhedron.faces {
+            let inside = Plane {
+                point: Vector3D::from_average(&face.vertices),
+                normal: -1.0 * face.normal(),
+            };
+            cross_section = clip_face(&cross_section, &inside)?;
+        }
+
+        let rotation = self.get_turn_rotation(turn_index, interpolate_amount);
+        let turning_cross_section = Face {
+            vertices: cross_section.vertices.iter().rev().copied().collect(),
+        }
+        .rotate(&rotation, &point);
+        Some((cross_section, turning_cross_section))
+    }
+
+    /// Saves the puzzle, so it can be loaded without redoing the geometry and symmetry search
+    #[cfg(feature = "serialize")]
+    pub fn write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        crate::serialize::write(writer, self)
+    }
+
+    /// Loads a puzzle saved by `write`, checking that everything in it fits together
+    /// so that a corrupt file is rejected rather than panicking later
+    #[cfg(feature = "serialize")]
+    pub fn read<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let puzzle: Self = crate::serialize::read(reader)?;
+        if !puzzle.is_consistent() {
+            return Err(crate::serialize::invalid_data(
+                "the puzzle's faces, turns and pieces don't fit together",
+            ));
+        }
+        Ok(puzzle)
+    }
+
+    /// Whether every face map, face index and turn index fits the number of faces and turns
+    #[cfg(feature = "serialize")]
+    fn is_consistent(&self) -> bool {
+        let num_faces = self.faces.len();
+        let num_turns = self.turns.len();
+        let num_values = self
+            .initial_state
+            .iter()
+            .max()
+            .map_or(0, |&value| value + 1);
+        self.turn_names.len() == num_turns
+            && self.initial_state.len() == num_faces
+            && self.faces.iter().all(|piece_face| {
+                piece_face.face.vertices.len() >= 3
+                    && piece_face
+                        .affecting_turn_indices
+                        .iter()
+                        .all(|&turn_index| turn_index < num_turns)
+            })
+            && self
+                .turns
+                .iter()
+                .all(|turn| turn.face_map.is_permutation_of(num_faces))
+            && self
+                .pieces
+                .iter()
+                .flatten()
+                .all(|&face_index| face_index < num_faces)
+            && self.symmetries.values().all(|symmetry| {
+                symmetry.face_map.is_permutation_of(num_faces)
+                    && symmetry.turn_map.is_permutation_of(num_turns)
+                    && symmetry.color_map.is_permutation_of(num_values)
+            })
+            && self
+                .rotations
+                .iter()
+                .all(|rotation| rotation.is_permutation_of(num_faces))
+            && self
+                .piece_types
+                .iter()
+                .all(|piece_type| piece_type.face_mask.len() == num_faces)
+    }
+
     pub fn get_initial_state(&self) -> PuzzleState {
-        self.faces.iter().map(|face| face.color_index).collect()
+        self.initial_state.clone()
+    }
+
+    /// Checks that the state has a value for every face,
+    /// and the same values as the solved state (since turning only moves them around)
+    pub fn validate_state(&self, puzzle_state: &PuzzleState) -> Result<()> {
+        if puzzle_state.len() != self.get_num_faces() {
+            return Err(Error::InvalidState(format!(
+                "expected {} faces, but the state has {}",
+                self.get_num_faces(),
+                puzzle_state.len()
+            )));
+        }
+        let mut values = puzzle_state.clone();
+        values.sort_unstable();
+        let mut solved_values = self.initial_state.clone();
+        solved_values.sort_unstable();
+        if values != solved_values {
+            return Err(Error::InvalidState(
+                "the state does not have the same colors as the solved puzzle".to_string(),
+            ));
+        }
+        Ok(())
     }
 
     pub fn get_derived_state(
@@ -615,6 +1058,9 @@ impl TwistyPuzzle {
         previous_state: &PuzzleState,
         face_map: &Bijection,
     ) -> PuzzleState {
+        #[cfg(feature = "count-states")]
+        self.num_derived_states
+            .set(self.num_derived_states.get() + 1);
         face_map
             .0
             .iter()
@@ -622,6 +1068,19 @@ impl TwistyPuzzle {
             .collect()
     }
 
+    /// The number of states derived with `get_derived_state` since the last reset
+    #[cfg(feature = "count-states")]
+    #[inline]
+    pub fn num_derived_states(&self) -> usize {
+        self.num_derived_states.get()
+    }
+
+    #[cfg(feature = "count-states")]
+    #[inline]
+    pub fn reset_num_derived_states(&self) {
+        self.num_derived_states.set(0);
+    }
+
     pub fn get_derived_state_turn_index(
         &self,
         previous_state: &PuzzleState,
@@ -631,7 +1090,6 @@ impl TwistyPuzzle {
         self.get_derived_state(previous_state, face_map)
     }
 
-    #[allow(dead_code)]
     pub fn get_derived_state_from_turn_sequence(
         &self,
         previous_state: &PuzzleState,
@@ -666,10 +1124,117 @@ impl TwistyPuzzle {
 
         state
     }
+
+    /// Picks random turns for a scramble, without undoing the previous turn
+    pub fn scramble_turns<T: Rng>(&self, length: u64, rng: &mut T) -> Vec<usize> {
+        let range = Uniform::new(0, self.turn_names.len());
+        let mut turns: Vec<usize> = vec![];
+        for _ in 0..length {
+            let turn_index = loop {
+                let turn_index = rng.sample(range);
+                if turns.last().map(|&t| self.inverted_turn_index(t)) != Some(turn_index) {
+                    break turn_index;
+                }
+            };
+            turns.push(turn_index);
+        }
+        turns
+    }
+
+    /// The number of arrangements of the faces that can be reached by turning,
+    /// counting faces of the same color as different
+    pub fn group_order(&self) -> BigUint {
+        let face_maps: Vec<Bijection> = self.turns.iter().map(|t| t.face_map.clone()).collect();
+        permutation_group::group_order(&face_maps, self.get_num_faces())
+    }
 }
 
 pub type PuzzleState = Vec<usize>;
 
+/// Splits each face which is centered on the axis of a turn that moves it
+/// into triangles around its center, so turning the face in place is visible in the state.
+/// The first triangle of each split face is marked to show the orientation.
+fn split_centered_faces(
+    faces: Vec<PieceFace>,
+    physical_turns: &[(String, PhysicalTurn)],
+) -> Vec<PieceFace> {
+    faces
+        .into_iter()
+        .flat_map(|piece_face| {
+            let center = Vector3D::from_average(&piece_face.face.vertices);
+            let is_centered_on_axis = piece_face.affecting_turn_indices.iter().any(|&turn_index| {
+                let (_, physical_turn) = &physical_turns[turn_index];
+                let axis_point_to_center = &center - &physical_turn.rotation_axis_point;
+                // The rotation axis is a unit vector,
+                // so this is the distance from the center to the axis
+                axis_point_to_center
+                    .cross(&physical_turn.rotation_axis)
+                    .magnitude()
+                    < 1e-8
+            });
+            if !is_centered_on_axis {
+                return vec![piece_face];
+            }
+            piece_face
+                .face
+                .edges_iter()
+                .enumerate()
+                .map(|(i, Edge(vertex_a, vertex_b))| PieceFace {
+                    face: Face {
+                        vertices: vec![center, vertex_a, vertex_b],
+                    },
+                    color_index: piece_face.color_index,
+                    sticker_index: None,
+                    orientation_marker: i == 0,
+                    affecting_turn_indices: piece_face.affecting_turn_indices.clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Keeps the part of the face that is on the side of the plane that the normal points to,
+/// or None if there isn't enough left to make a polygon
+fn clip_face(face: &Face, plane: &Plane) -> Option<Face> {
+    let mut vertices = VertexList::new();
+    let is_kept: Vec<bool> = face
+        .vertices
+        .iter()
+        .map(|vertex| side_of_plane(vertex, plane) != Ordering::Less)
+        .collect();
+    for i in 0..face.vertices.len() {
+        let j = (i + 1) % face.vertices.len();
+        let (vertex_a, vertex_b) = (&face.vertices[i], &face.vertices[j]);
+        if is_kept[i] {
+            vertices.push(*vertex_a);
+        }
+        if is_kept[i] != is_kept[j] {
+            // This edge crosses the plane
+            let edge_ray = Ray {
+                point: *vertex_a,
+                direction: vertex_a - vertex_b,
+            };
+            vertices.push(plane.intersection(&edge_ray));
+        }
+    }
+    let vertices = vertices.to_vec();
+    if vertices.len() > 2 {
+        Some(Face { vertices })
+    } else {
+        None
+    }
+}
+
+/// Whether the face is too thin to be a real piece face:
+/// twice the area over the perimeter is roughly the face's width at its narrowest
+fn is_sliver(face: &Face) -> bool {
+    let perimeter: f64 = face
+        .edges_iter()
+        .map(|Edge(a, b)| (b - &a).magnitude())
+        .sum();
+    2.0 * face.area() / perimeter < CUT_PLANE_THICKNESS
+}
+
 // A Vec<Vector3D> but it prevents two adjacent items from being equal or approx equal
 // Also prevents the first and last from being equal or approx equal
 struct VertexList {
@@ -702,10 +1267,11 @@ impl VertexList {
 #[cfg(test)]
 mod tests {
     use insta::assert_snapshot;
+    use std::f64::consts::TAU;
 
     use crate::puzzles;
 
-    use super::TwistyPuzzle;
+    use super::{CutDefinition, Error, Plane, Polyhedron, TwistyPuzzle, Vector3D};
 
     #[test]
     fn test_inverted_turn_index() {
@@ -956,6 +1522,282 @@ mod tests {
         assert_eq!(puzzle.symmetries.len(), 120);
     }
 
+    #[test]
+    fn test_apply_symmetry() {
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let solved_state = puzzle.get_initial_state();
+        let turns = [0, 3, 5, 8, 2, 11, 4];
+        let turned_state =
+            puzzle.get_derived_state_from_turn_sequence(&solved_state, turns.iter().cloned());
+        for symmetry in puzzle.symmetries.values() {
+            // The solved state is unchanged by any symmetry
+            assert_eq!(puzzle.apply_symmetry(&solved_state, symmetry), solved_state);
+            // Moving the puzzle after turning is the same as doing the symmetric turns
+            let symmetric_turned_state = puzzle.get_derived_state_from_turn_sequence(
+                &solved_state,
+                turns
+                    .iter()
+                    .map(|&turn_index| symmetry.turn_map.0[turn_index]),
+            );
+            assert_eq!(
+                puzzle.apply_symmetry(&turned_state, symmetry),
+                symmetric_turned_state
+            );
+        }
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        for puzzle in [
+            puzzles::rubiks_cube_3x3(),
+            puzzles::rubiks_supercube_3x3(),
+            puzzles::megaminx(),
+            puzzles::pyraminx(),
+        ] {
+            let solved_state = puzzle.get_initial_state();
+            assert_eq!(puzzle.canonicalize(&solved_state), solved_state);
+
+            // Every single turn is symmetric to the first turn
+            let canonical_turned_state =
+                puzzle.canonicalize(&puzzle.get_derived_state_turn_index(&solved_state, 0));
+            let num_equivalent_turns = (0..puzzle.turns.len())
+                .filter(|&turn_index| {
+                    let state = puzzle.get_derived_state_turn_index(&solved_state, turn_index);
+                    puzzle.canonicalize(&state) == canonical_turned_state
+                })
+                .count();
+            assert_eq!(num_equivalent_turns, puzzle.turns.len());
+
+            let scrambled_state = puzzle.scramble(&solved_state, 20, &mut rng);
+            let canonical_state = puzzle.canonicalize(&scrambled_state);
+            for symmetry in puzzle.symmetries.values() {
+                let symmetric_state = puzzle.apply_symmetry(&scrambled_state, symmetry);
+                assert_eq!(puzzle.canonicalize(&symmetric_state), canonical_state);
+            }
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_write_read() {
+        let puzzle = puzzles::rubiks_supercube_3x3();
+        let mut bytes = vec![];
+        puzzle.write(&mut bytes).unwrap();
+        let loaded = TwistyPuzzle::read(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(loaded.turn_names, puzzle.turn_names);
+        assert_eq!(loaded.symmetries.len(), puzzle.symmetries.len());
+        assert_eq!(loaded.sticker_mode(), puzzle.sticker_mode());
+        let turns = [0, 3, 5, 6, 8];
+        assert_eq!(
+            loaded.get_derived_state_from_turn_sequence(
+                &loaded.get_initial_state(),
+                turns.into_iter()
+            ),
+            puzzle.get_derived_state_from_turn_sequence(
+                &puzzle.get_initial_state(),
+                turns.into_iter()
+            )
+        );
+        // The lazily built tables still work after loading
+        assert_eq!(loaded.heuristic_distance(&loaded.get_initial_state()), 0);
+
+        assert!(TwistyPuzzle::read(&mut &bytes[1..]).is_err());
+        assert!(TwistyPuzzle::read(&mut &bytes[..bytes.len() / 2]).is_err());
+
+        // A turn that moves a face to a face that doesn't exist
+        let mut corrupt = puzzles::rubiks_cube_2x2();
+        corrupt.turns[0].face_map.0[0] = corrupt.faces.len();
+        let mut bytes = vec![];
+        corrupt.write(&mut bytes).unwrap();
+        assert!(TwistyPuzzle::read(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_heuristic_distance() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        for (puzzle, num_pieces_moved_by_turn) in [
+            (puzzles::rubiks_cube_3x3(), 8),
+            // The center piece is rotated in place
+            (puzzles::rubiks_supercube_3x3(), 9),
+            (puzzles::megaminx(), 10),
+            // The 4x4 has several identical-looking centers
+            (puzzles::rubiks_cube_4x4(), 12),
+        ] {
+            let solved_state = puzzle.get_initial_state();
+            assert_eq!(puzzle.heuristic_distance(&solved_state), 0);
+            assert_eq!(puzzle.max_piece_distance(&solved_state), 0);
+
+            let turned_state = puzzle.get_derived_state_turn_index(&solved_state, 0);
+            assert_eq!(
+                puzzle.heuristic_distance(&turned_state),
+                num_pieces_moved_by_turn
+            );
+            assert_eq!(puzzle.max_piece_distance(&turned_state), 1);
+
+            for num_turns in 1..8 {
+                let scrambled_state = puzzle.scramble(&solved_state, num_turns, &mut rng);
+                let max_distance = puzzle.max_piece_distance(&scrambled_state);
+                assert!(max_distance <= num_turns as usize);
+                assert!(puzzle.heuristic_distance(&scrambled_state) >= max_distance);
+            }
+        }
+
+        // Turning the same face twice needs two turns to undo, since there are no half turns
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let state = puzzle
+            .get_derived_state_from_turn_sequence(&puzzle.get_initial_state(), [0, 0].into_iter());
+        assert_eq!(puzzle.max_piece_distance(&state), 2);
+        assert_eq!(puzzle.heuristic_distance(&state), 16);
+    }
+
+    #[test]
+    fn test_group_order() {
+        // The 2x2 only turns three faces, so one corner never moves
+        assert_eq!(
+            puzzles::rubiks_cube_2x2().group_order(),
+            num::BigUint::from(3_674_160u32)
+        );
+        assert_eq!(
+            puzzles::rubiks_cube_3x3().group_order().to_string(),
+            "43252003274489856000"
+        );
+    }
+
+    #[test]
+    fn test_turn_cross_sections() {
+        // Every cut of a cube goes straight through it, so it is as big as a face
+        let puzzle = puzzles::rubiks_cube_3x3();
+        let face_area = puzzle.polyhedron.faces[0].area();
+        for turn_index in 0..puzzle.turns.len() {
+            let (cross_section, turning_cross_section) =
+                puzzle.get_turn_cross_sections(turn_index, 0.5).unwrap();
+            assert!((cross_section.area() - face_area).abs() < 1e-6);
+            assert!((turning_cross_section.area() - face_area).abs() < 1e-6);
+        }
+        // Cuts through the center still have something on both sides
+        let puzzle = puzzles::skewb_diamond();
+        for turn_index in 0..puzzle.turns.len() {
+            let (cross_section, _) = puzzle.get_turn_cross_sections(turn_index, 0.0).unwrap();
+            assert!(cross_section.area() > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_degenerate_cuts() {
+        let cube = Polyhedron::generate(4, 3).unwrap();
+        let top_cut =
+            |offset| CutDefinition::new_infer_name(cube.faces[0].plane().offset(offset), TAU / 4.0);
+        assert!(matches!(
+            TwistyPuzzle::new(&cube, &[top_cut(-0.5), top_cut(-0.5)]),
+            Err(Error::InvalidGeometry(_))
+        ));
+        // The cut lies on the top face, so nothing is left of it
+        assert_eq!(
+            TwistyPuzzle::new(&cube, &[top_cut(0.0)]).err(),
+            Some(Error::DegeneratePiece { face_index: 0 })
+        );
+        // Also after earlier cuts have split the face up
+        let side_cut = CutDefinition::new_infer_name(cube.faces[1].plane(), TAU / 4.0);
+        assert_eq!(
+            TwistyPuzzle::new(&cube, &[top_cut(-0.5), side_cut]).err(),
+            Some(Error::DegeneratePiece { face_index: 1 })
+        );
+        // Cutting right at the corners only leaves slivers,
+        // which are merged into the gap instead of becoming pieces
+        let mut corners: Vec<Vector3D> = vec![];
+        for vertex in cube.faces.iter().flat_map(|face| &face.vertices) {
+            if !corners.iter().any(|corner| corner.approx_equals(vertex)) {
+                corners.push(*vertex);
+            }
+        }
+        let corner_cuts: Vec<_> = corners
+            .iter()
+            .map(|&corner| {
+                CutDefinition::new_infer_name(
+                    Plane {
+                        point: corner,
+                        normal: corner,
+                    },
+                    TAU / 3.0,
+                )
+            })
+            .collect();
+        let puzzle = TwistyPuzzle::new(&cube, &corner_cuts).unwrap();
+        assert_eq!(puzzle.get_num_faces(), 6);
+    }
+
+    #[test]
+    fn test_validate_state() {
+        let puzzle = puzzles::rubiks_cube_2x2();
+        let state = puzzle.get_initial_state();
+        assert_eq!(puzzle.validate_state(&state), Ok(()));
+        assert!(matches!(
+            puzzle.validate_state(&state[1..].to_vec()),
+            Err(Error::InvalidState(_))
+        ));
+        let mut recolored = state.clone();
+        recolored[0] = state.len();
+        assert!(matches!(
+            puzzle.validate_state(&recolored),
+            Err(Error::InvalidState(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_polyhedron() {
+        // Hexagons tile the plane instead of closing up
+        assert!(matches!(
+            Polyhedron::generate(6, 3),
+            Err(Error::InvalidGeometry(_))
+        ));
+    }
+
+    #[test]
+    fn test_solved_any_orientation() {
+        for (puzzle, num_rotations) in [
+            (puzzles::rubiks_cube_3x3(), 24),
+            (puzzles::skewb(), 24),
+            (puzzles::rubiks_supercube_3x3(), 24),
+            // Even though it has few symmetries, the pentultimate can be held in any orientation
+            (puzzles::pentultimate(), 60),
+        ] {
+            let solved_state = puzzle.get_initial_state();
+            assert_eq!(puzzle.rotations.len(), num_rotations);
+
+            let mut num_unrotated = 0;
+            for rotation in &puzzle.rotations {
+                let rotated_state = puzzle.get_derived_state(&solved_state, rotation);
+                if puzzle.is_solved(&rotated_state) {
+                    num_unrotated += 1;
+                }
+                assert!(puzzle.is_solved_any_orientation(&rotated_state));
+                assert_eq!(
+                    puzzle.get_num_solved_pieces_any_orientation(&rotated_state),
+                    puzzle.get_num_pieces()
+                );
+            }
+            // Only the identity rotation leaves the colors where they were
+            assert_eq!(num_unrotated, 1);
+
+            let turned_state = puzzle.get_derived_state_turn_index(&solved_state, 0);
+            assert!(!puzzle.is_solved_any_orientation(&turned_state));
+            assert!(
+                puzzle.get_num_solved_pieces_any_orientation(&turned_state)
+                    >= puzzle.get_num_solved_pieces(&turned_state)
+            );
+        }
+    }
+
     #[test]
     fn test_piece_types() {
         fn count_piece_types_with_n_faces(puzzle: &TwistyPuzzle, n: usize) -> usize {
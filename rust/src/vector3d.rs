@@ -1,6 +1,7 @@
 use crate::ray::Ray;
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector3D {
     pub x: f64,
     pub y: f64,
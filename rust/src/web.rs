@@ -0,0 +1,1186 @@
+//! The browser UI, which draws the puzzle on a canvas and turns it with buttons, the keyboard,
+//! or by dragging across its stickers (dragging anywhere else orbits the camera).
+//! Every turn goes into a move history, which can be undone, redone, and played back.
+//! In timer mode, scrambling starts inspection and solves are timed and saved to localStorage.
+//! The net view shows every sticker at once, unfolded flat in the corner.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::history::{MoveHistory, Playback};
+use crate::keymap::{KeyAction, KeyMap, KeyPress};
+use crate::net::{draw_net, Net};
+use crate::picking::{pick_face, pick_turn};
+use crate::puzzles;
+use crate::puzzles::PuzzleInfo;
+use crate::render::{draw_puzzle, Camera, ColorScheme, Renderer};
+use crate::rotation3d::Rotation3D;
+use crate::solver::{solver_by_name, AnyScrambleSolver, AnySolver, ALL_SOLVERS};
+use crate::timer::{format_time, LocalStorage, MemoryStorage, Session, Storage, Timer};
+use crate::twisty_puzzle::{PuzzleState, TwistyPuzzle};
+use crate::vector3d::Vector3D;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::console;
+
+static mut CURSOR_START_POSITION: (i32, i32) = (0, 0);
+static mut CURSOR_DOWN: bool = false;
+static mut ORBIT_START_CAMERA: Option<Camera> = None;
+// Set while the cursor is dragging a sticker rather than orbiting the camera
+static mut STICKER_DRAG_START: Option<(i32, i32)> = None;
+static mut STICKER_DRAG_TURNED: bool = false;
+
+// How far the cursor has to be dragged across a sticker before it turns the puzzle
+const STICKER_DRAG_DISTANCE: f64 = 10.0;
+
+fn cursor_location_to_3d(width: i32, height: i32, (cursor_x, cursor_y): (i32, i32)) -> Vector3D {
+    // Assuming: camera is on the z-axis pointing towards the origin, with y as the up direction
+    let min_viewport_dimension = std::cmp::min(width, height);
+    let sphere_radius = 0.4 * min_viewport_dimension as f64;
+
+    // x and y relative to the center of the canvas
+    let x = cursor_x as f64 - (width as f64 / 2.0);
+    let y = cursor_y as f64 - (height as f64 / 2.0);
+    // Sphere: z^2 + x^2 + y^2 = radius^2
+    let z_eq_sq = sphere_radius * sphere_radius - x * x - y * y;
+    let z = if z_eq_sq >= 0.0 { z_eq_sq.sqrt() } else { 0.0 };
+    Vector3D { x, y, z }
+}
+
+fn compute_camera_position_from_orbit(
+    width: i32,
+    height: i32,
+    initial_camera: Camera,
+    orbit_start_cursor: (i32, i32),
+    orbit_end_cursor: (i32, i32),
+) -> Camera {
+    let start_cursor_vector = cursor_location_to_3d(width, height, orbit_start_cursor);
+    let end_cursor_vector = cursor_location_to_3d(width, height, orbit_end_cursor);
+
+    if start_cursor_vector == end_cursor_vector {
+        return initial_camera;
+    }
+
+    let rotation_axis = start_cursor_vector
+        .cross(&end_cursor_vector)
+        .to_unit_vector();
+    let rotation_d_theta = start_cursor_vector.dot(&end_cursor_vector)
+        / (start_cursor_vector.magnitude() * end_cursor_vector.magnitude());
+
+    let rotation = Rotation3D::new(&rotation_axis, rotation_d_theta);
+    initial_camera.rotate(&rotation)
+}
+
+const DEFAULT_PUZZLE: &str = "3x3";
+const DEFAULT_SOLVER: &str = "phased";
+// The speeds that the move history can be played back at
+const PLAYBACK_TURNS_PER_SECOND: [f64; 5] = [1.0, 2.0, 4.0, 8.0, 16.0];
+const DEFAULT_PLAYBACK_TURNS_PER_SECOND: f64 = 4.0;
+const NET_BACKGROUND_COLOR: &str = "#303030";
+
+/// The chosen solver, which is set up the first time the puzzle is solved,
+/// since for some solvers that means discovering metamoves, which would hold up the page
+enum SolverState {
+    Unloaded,
+    /// The solver doesn't support the puzzle, or couldn't be set up for it
+    Unavailable,
+    Ready(Box<dyn AnySolver>),
+}
+
+struct State {
+    puzzle_info: &'static PuzzleInfo,
+    solver_name: &'static str,
+    solver: SolverState,
+    scramble_solver: Option<Box<dyn AnyScrambleSolver>>,
+    is_solving: bool,
+    puzzle_state: PuzzleState,
+    puzzle: Rc<TwistyPuzzle>,
+    turn_queue: VecDeque<usize>,
+    turn_progress: f64,
+    keymap: KeyMap,
+    history: MoveHistory,
+    playback: Playback,
+    timer_mode: bool,
+    timer: Timer,
+    // The puzzle's solves, which are saved after each one
+    session: Session,
+    storage: Box<dyn Storage>,
+    show_net: bool,
+    net: Net,
+    color_scheme: ColorScheme,
+}
+
+impl State {
+    fn new(puzzle_info: &'static PuzzleInfo, solver_name: &'static str) -> Self {
+        let puzzle = Rc::new((puzzle_info.constructor)());
+        let storage: Box<dyn Storage> = match LocalStorage::new() {
+            Ok(storage) => Box::new(storage),
+            Err(error) => {
+                console_log!("solves won't be saved: {}", error);
+                Box::new(MemoryStorage::new())
+            }
+        };
+        let session = Session::load(&*storage, puzzle_info.name).unwrap_or_else(|error| {
+            console_log!("could not load the saved solves: {}", error);
+            Session::new(puzzle_info.name)
+        });
+        let keymap = KeyMap::load(&*storage, puzzle_info.name, &puzzle).unwrap_or_else(|error| {
+            console_log!("could not load the saved key bindings: {}", error);
+            KeyMap::default_for(&puzzle)
+        });
+        let mut state = Self {
+            puzzle_info,
+            solver_name,
+            solver: SolverState::Unloaded,
+            scramble_solver: None,
+            is_solving: false,
+            puzzle_state: puzzle.get_initial_state(),
+            keymap,
+            history: MoveHistory::new(puzzle.get_initial_state()),
+            playback: Playback::new(DEFAULT_PLAYBACK_TURNS_PER_SECOND),
+            timer_mode: false,
+            timer: Timer::new(),
+            session,
+            storage,
+            show_net: false,
+            net: Net::new(&puzzle),
+            color_scheme: ColorScheme::default(),
+            puzzle,
+            turn_queue: VecDeque::new(),
+            turn_progress: 0.0,
+        };
+        state.set_solver(solver_name);
+        state
+    }
+
+    fn set_solver(&mut self, solver_name: &'static str) {
+        self.solver_name = solver_name;
+        self.solver = SolverState::Unloaded;
+        self.scramble_solver = None;
+        self.is_solving = false;
+    }
+
+    /// The chosen solver for the puzzle, which is set up the first time it's needed
+    fn solver(&mut self) -> Option<&dyn AnySolver> {
+        if let SolverState::Unloaded = self.solver {
+            let (solver_name, puzzle_name) = (self.solver_name, self.puzzle_info.name);
+            self.solver = match solver_by_name(solver_name, puzzle_name, &self.puzzle) {
+                Some(Ok(solver)) => SolverState::Ready(solver),
+                Some(Err(error)) => {
+                    console_log!("could not set up the {} solver: {}", solver_name, error);
+                    SolverState::Unavailable
+                }
+                None => SolverState::Unavailable,
+            };
+        }
+        match &self.solver {
+            SolverState::Ready(solver) => Some(solver.as_ref()),
+            SolverState::Unloaded | SolverState::Unavailable => None,
+        }
+    }
+
+    /// Adds the turn to the history and starts showing it
+    fn make_turn(&mut self, turn_index: usize) {
+        self.history.push(&self.puzzle, turn_index);
+        self.turn_queue.push_back(turn_index);
+        self.time_turn();
+    }
+
+    /// Adds a turn that the solver chose. Solving with help isn't a timed solve,
+    /// so the turn stops the timer rather than starting or finishing a solve.
+    fn make_solver_turn(&mut self, turn_index: usize) {
+        self.timer.cancel();
+        self.history.push(&self.puzzle, turn_index);
+        self.turn_queue.push_back(turn_index);
+    }
+
+    /// Lets the timer know about a turn, which may start or finish a solve
+    fn time_turn(&mut self) {
+        let Some(solve) = self
+            .timer
+            .turn(now_seconds(), &self.puzzle, self.history.state())
+        else {
+            return;
+        };
+        self.session.solves.push(solve);
+        if let Err(error) = self.session.save(&mut *self.storage) {
+            console_log!("could not save the solve: {}", error);
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(turn_index) = self.playback.step_back(&mut self.history) {
+            self.is_solving = false;
+            self.turn_queue
+                .push_back(self.puzzle.inverted_turn_index(turn_index));
+            self.time_turn();
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(turn_index) = self.playback.step_forward(&mut self.history) {
+            self.is_solving = false;
+            self.turn_queue.push_back(turn_index);
+            self.time_turn();
+        }
+    }
+
+    fn toggle_playback(&mut self) {
+        if self.playback.is_playing() {
+            self.playback.pause();
+            return;
+        }
+        // Playing from the end starts over
+        if self.history.position() == self.history.len() {
+            self.jump_to(0);
+        }
+        self.is_solving = false;
+        self.playback.play();
+    }
+
+    fn toggle_timer_mode(&mut self) {
+        self.timer_mode = !self.timer_mode;
+        self.timer.cancel();
+    }
+
+    /// Forgets the puzzle's solves so far
+    fn new_session(&mut self) {
+        self.session = Session::new(self.puzzle_info.name);
+        if let Err(error) = self.session.save(&mut *self.storage) {
+            console_log!("could not save the session: {}", error);
+        }
+    }
+
+    /// Shows the turns that are due to be played back after this much time
+    fn advance_playback(&mut self, seconds: f64) {
+        let turns = self.playback.advance(&mut self.history, seconds);
+        self.turn_queue.extend(turns);
+    }
+
+    /// Goes straight to a point in the history, without showing the turns in between.
+    /// This doesn't count as solving, so it stops the timer.
+    fn jump_to(&mut self, position: usize) {
+        self.timer.cancel();
+        self.playback.pause();
+        self.is_solving = false;
+        self.turn_queue.clear();
+        self.turn_progress = 0.0;
+        self.puzzle_state = self.history.jump_to(position).clone();
+    }
+
+    /// Starts a new history from the state, like after scrambling
+    fn start_history(&mut self, history: MoveHistory) {
+        self.history = history;
+        self.jump_to(self.history.position());
+    }
+}
+
+#[wasm_bindgen]
+pub fn start() {
+    console_error_panic_hook::set_once();
+    let result = init();
+    if let Err(err) = result {
+        console::error_1(&err);
+    }
+}
+
+#[allow(clippy::let_unit_value)]
+fn init() -> Result<(), JsValue> {
+    let window = web_sys::window().unwrap();
+    let document = window.document().unwrap();
+    let app_el = document.query_selector("#app").unwrap().unwrap();
+    let buttons_div = document
+        .create_element("div")?
+        .dyn_into::<web_sys::HtmlDivElement>()?;
+    buttons_div.set_class_name("buttons");
+    app_el.append_child(&buttons_div)?;
+
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<web_sys::HtmlCanvasElement>()?;
+    app_el.append_child(&canvas)?;
+
+    let canvas_ctx = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
+
+    let canvas = Rc::new(canvas);
+    let canvas_ctx = Rc::new(canvas_ctx);
+
+    let puzzle_info = puzzles::info_by_name(DEFAULT_PUZZLE).unwrap();
+    let state = Rc::new(RefCell::new(State::new(puzzle_info, DEFAULT_SOLVER)));
+
+    let width = Rc::new(Cell::new(canvas.client_width()));
+    let height = Rc::new(Cell::new(canvas.client_height()));
+
+    let puzzle_select = document
+        .create_element("select")?
+        .dyn_into::<web_sys::HtmlSelectElement>()?;
+    for info in puzzles::ALL_PUZZLES {
+        let option = document
+            .create_element("option")?
+            .dyn_into::<web_sys::HtmlOptionElement>()?;
+        option.set_value(info.name);
+        option.set_text(info.display_name);
+        option.set_title(&format!(
+            "{}, turning around its {}",
+            info.shape,
+            match info.turn_axis {
+                puzzles::TurnAxis::Faces => "faces",
+                puzzles::TurnAxis::Vertices => "vertices",
+            }
+        ));
+        puzzle_select.append_child(&option)?;
+    }
+    puzzle_select.set_value(DEFAULT_PUZZLE);
+    buttons_div.append_child(&puzzle_select)?;
+
+    let solver_select = document
+        .create_element("select")?
+        .dyn_into::<web_sys::HtmlSelectElement>()?;
+    for &solver_name in ALL_SOLVERS {
+        let option = document
+            .create_element("option")?
+            .dyn_into::<web_sys::HtmlOptionElement>()?;
+        option.set_value(solver_name);
+        option.set_text(solver_name);
+        solver_select.append_child(&option)?;
+    }
+    solver_select.set_value(DEFAULT_SOLVER);
+    buttons_div.append_child(&solver_select)?;
+
+    // Filled in for each puzzle, since each one has its own turns
+    let turn_buttons_div = document
+        .create_element("div")?
+        .dyn_into::<web_sys::HtmlDivElement>()?;
+    turn_buttons_div.set_class_name("turn-buttons");
+    buttons_div.append_child(&turn_buttons_div)?;
+    add_turn_buttons(&document, &turn_buttons_div, &state)?;
+
+    // The key bindings can be edited here, in the format that the keymap module reads
+    let keymap_text = document
+        .create_element("textarea")?
+        .dyn_into::<web_sys::HtmlTextAreaElement>()?;
+    keymap_text.set_class_name("keymap");
+    {
+        let state = state.borrow();
+        keymap_text.set_value(&state.keymap.to_text(&state.puzzle));
+    }
+
+    {
+        let state = state.clone();
+        let document = document.clone();
+        let puzzle_select_el = puzzle_select.clone();
+        let turn_buttons_div = turn_buttons_div.clone();
+        let keymap_text = keymap_text.clone();
+        let handle_change = move || {
+            let Some(puzzle_info) = puzzles::info_by_name(&puzzle_select_el.value()) else {
+                return;
+            };
+            {
+                let mut state = state.borrow_mut();
+                let (timer_mode, show_net) = (state.timer_mode, state.show_net);
+                let color_scheme = state.color_scheme.clone();
+                *state = State::new(puzzle_info, state.solver_name);
+                state.timer_mode = timer_mode;
+                state.show_net = show_net;
+                state.color_scheme = color_scheme;
+                keymap_text.set_value(&state.keymap.to_text(&state.puzzle));
+            }
+            if let Err(err) = add_turn_buttons(&document, &turn_buttons_div, &state) {
+                console::error_1(&err);
+            }
+        };
+
+        let change_listener = Closure::wrap(Box::new(handle_change) as Box<dyn FnMut()>);
+        puzzle_select
+            .add_event_listener_with_callback("change", change_listener.as_ref().unchecked_ref())?;
+        change_listener.forget();
+    }
+
+    {
+        let state = state.clone();
+        let solver_select_el = solver_select.clone();
+        let handle_change = move || {
+            let value = solver_select_el.value();
+            if let Some(&solver_name) = ALL_SOLVERS.iter().find(|&&name| name == value) {
+                state.borrow_mut().set_solver(solver_name);
+            }
+        };
+
+        let change_listener = Closure::wrap(Box::new(handle_change) as Box<dyn FnMut()>);
+        solver_select
+            .add_event_listener_with_callback("change", change_listener.as_ref().unchecked_ref())?;
+        change_listener.forget();
+    }
+
+    fn solve_next_step(state: &mut State) -> bool {
+        let puzzle_state = state.puzzle_state.clone();
+        let up_to_date = matches!(
+            &state.scramble_solver,
+            Some(scramble_solver) if *scramble_solver.get_state() == puzzle_state
+        );
+        let Some(solver) = state.solver() else {
+            console_log!(
+                "the {} solver isn't available for this puzzle",
+                state.solver_name
+            );
+            state.is_solving = false;
+            return false;
+        };
+        if !up_to_date {
+            match solver.solve(puzzle_state) {
+                Ok(scramble_solver) => state.scramble_solver = Some(scramble_solver),
+                Err(error) => {
+                    console_log!("could not solve: {}", error);
+                    state.is_solving = false;
+                    return false;
+                }
+            }
+        }
+        let scramble_solver = state.scramble_solver.as_mut().unwrap();
+        let turn_index = scramble_solver.next();
+        if let Some(turn_index) = turn_index {
+            state.make_solver_turn(turn_index);
+            true
+        } else {
+            state.is_solving = false;
+            false
+        }
+    }
+
+    {
+        let solve_button = document
+            .create_element("button")?
+            .dyn_into::<web_sys::HtmlButtonElement>()?;
+        solve_button.set_inner_text("Solve Step");
+        buttons_div.append_child(&solve_button)?;
+
+        let canvas_ctx = canvas_ctx.clone();
+        let state = state.clone();
+        let width = width.clone();
+        let height = height.clone();
+        let handle_click = move || {
+            let mut state = state.borrow_mut();
+            if solve_next_step(&mut state) {
+                render(&state, &canvas_ctx, width.get(), height.get(), 0, 0, false);
+            }
+        };
+
+        let click_listener = Closure::wrap(Box::new(handle_click) as Box<dyn FnMut()>);
+        solve_button
+            .add_event_listener_with_callback("click", click_listener.as_ref().unchecked_ref())?;
+        click_listener.forget();
+    }
+
+    {
+        let solve_button = document
+            .create_element("button")?
+            .dyn_into::<web_sys::HtmlButtonElement>()?;
+        solve_button.set_inner_text("Solve");
+        buttons_div.append_child(&solve_button)?;
+
+        let canvas_ctx = canvas_ctx.clone();
+        let state = state.clone();
+        let width = width.clone();
+        let height = height.clone();
+        let handle_click = move || {
+            let mut state = state.borrow_mut();
+            state.is_solving = true;
+            render(&state, &canvas_ctx, width.get(), height.get(), 0, 0, false);
+            solve_next_step(&mut state);
+        };
+
+        let click_listener = Closure::wrap(Box::new(handle_click) as Box<dyn FnMut()>);
+        solve_button
+            .add_event_listener_with_callback("click", click_listener.as_ref().unchecked_ref())?;
+        click_listener.forget();
+    }
+
+    {
+        let scramble_button = document
+            .create_element("button")?
+            .dyn_into::<web_sys::HtmlButtonElement>()?;
+        scramble_button.set_inner_text("Scramble");
+        buttons_div.append_child(&scramble_button)?;
+        let mut rng = rand::thread_rng();
+
+        let canvas_ctx = canvas_ctx.clone();
+        let state = state.clone();
+        let width = width.clone();
+        let height = height.clone();
+        let handle_click = move || {
+            let mut state = state.borrow_mut();
+            // Scrambling from solved means the history holds the whole reconstruction
+            let scramble = state.puzzle.scramble_turns(200, &mut rng);
+            let history = MoveHistory::reconstruction(&state.puzzle, &scramble, &[]);
+            state.start_history(history);
+            if state.timer_mode {
+                let scramble_names: Vec<&str> = scramble
+                    .iter()
+                    .map(|&turn_index| state.puzzle.turn_names[turn_index].as_str())
+                    .collect();
+                let scramble_text = scramble_names.join(" ");
+                state.timer.start_inspection(now_seconds(), scramble_text);
+            }
+            render(&state, &canvas_ctx, width.get(), height.get(), 0, 0, false);
+        };
+
+        let click_listener = Closure::wrap(Box::new(handle_click) as Box<dyn FnMut()>);
+        scramble_button
+            .add_event_listener_with_callback("click", click_listener.as_ref().unchecked_ref())?;
+        click_listener.forget();
+    }
+
+    {
+        let reset_button = document
+            .create_element("button")?
+            .dyn_into::<web_sys::HtmlButtonElement>()?;
+        reset_button.set_inner_text("Reset");
+        buttons_div.append_child(&reset_button)?;
+
+        let canvas_ctx = canvas_ctx.clone();
+        let state = state.clone();
+        let width = width.clone();
+        let height = height.clone();
+        let handle_click = move || {
+            let mut state = state.borrow_mut();
+            let history = MoveHistory::new(state.puzzle.get_initial_state());
+            state.start_history(history);
+            render(&state, &canvas_ctx, width.get(), height.get(), 0, 0, false);
+        };
+
+        let click_listener = Closure::wrap(Box::new(handle_click) as Box<dyn FnMut()>);
+        reset_button
+            .add_event_listener_with_callback("click", click_listener.as_ref().unchecked_ref())?;
+        click_listener.forget();
+    }
+
+    let history_slider = add_history_controls(&document, &buttons_div, &state)?;
+    add_timer_controls(&document, &buttons_div, &state)?;
+
+    {
+        buttons_div.append_child(&keymap_text)?;
+
+        let state = state.clone();
+        let keymap_text_el = keymap_text.clone();
+        let handle_change = move || {
+            let mut state = state.borrow_mut();
+            let state = &mut *state;
+            let text = keymap_text_el.value();
+            let mut keymap = KeyMap::default_for(&state.puzzle);
+            match keymap.apply_overrides(&state.puzzle, &text) {
+                Ok(()) => state.keymap = keymap,
+                Err(error) => {
+                    console_log!("could not read key bindings: {}", error);
+                    return;
+                }
+            }
+            // Saved so that they are still there after switching puzzles, or coming back later
+            if let Err(error) =
+                KeyMap::save_overrides(&mut *state.storage, state.puzzle_info.name, &text)
+            {
+                console_log!("could not save the key bindings: {}", error);
+            }
+        };
+
+        let change_listener = Closure::wrap(Box::new(handle_change) as Box<dyn FnMut()>);
+        keymap_text
+            .add_event_listener_with_callback("change", change_listener.as_ref().unchecked_ref())?;
+        change_listener.forget();
+    }
+
+    {
+        // In the format that ColorScheme::parse reads, or empty for the usual colors
+        let colors_input = document
+            .create_element("input")?
+            .dyn_into::<web_sys::HtmlInputElement>()?;
+        colors_input.set_type("text");
+        colors_input.set_placeholder("Colors, like white, #1976d2, ...");
+        colors_input.set_title("Sticker colors, in the order of the puzzle's colors");
+        buttons_div.append_child(&colors_input)?;
+
+        let state = state.clone();
+        let colors_input_el = colors_input.clone();
+        let handle_change = move || {
+            let text = colors_input_el.value();
+            let color_scheme = if text.trim().is_empty() {
+                Ok(ColorScheme::default())
+            } else {
+                ColorScheme::parse(&text)
+            };
+            match color_scheme {
+                Ok(color_scheme) => state.borrow_mut().color_scheme = color_scheme,
+                Err(error) => {
+                    console_log!("could not read the colors: {}", error);
+                }
+            }
+        };
+
+        let change_listener = Closure::wrap(Box::new(handle_change) as Box<dyn FnMut()>);
+        colors_input
+            .add_event_listener_with_callback("change", change_listener.as_ref().unchecked_ref())?;
+        change_listener.forget();
+    }
+
+    {
+        let state = state.clone();
+
+        let handle_key_event = move |event: web_sys::KeyboardEvent| {
+            // Typing in the bindings or the colors shouldn't turn the puzzle,
+            // and holding a key turns it once
+            let is_editing = event.target().is_some_and(|target| {
+                target.has_type::<web_sys::HtmlTextAreaElement>()
+                    || target
+                        .dyn_into::<web_sys::HtmlInputElement>()
+                        .is_ok_and(|input| input.type_() == "text")
+            });
+            if is_editing || event.repeat() {
+                return;
+            }
+            let key_press = KeyPress::new(&event.key(), event.shift_key(), event.ctrl_key());
+            let mut state = state.borrow_mut();
+            let Some(action) = state.keymap.get(&key_press) else {
+                return;
+            };
+            event.prevent_default();
+            match action {
+                KeyAction::Turn(turn_index) => state.make_turn(turn_index),
+                KeyAction::Rotate(turn_index) => {
+                    // Moving the camera the opposite way makes the puzzle look rotated
+                    let inverse = state.puzzle.inverted_turn_index(turn_index);
+                    let rotation = state.puzzle.get_turn_rotation(inverse, 1.0);
+                    unsafe {
+                        let camera: Camera = ORBIT_START_CAMERA.unwrap_or_default();
+                        ORBIT_START_CAMERA = Some(camera.rotate(&rotation));
+                    }
+                }
+                KeyAction::Undo => state.undo(),
+                KeyAction::Redo => state.redo(),
+            }
+        };
+
+        let key_listener = Closure::wrap(Box::new(handle_key_event) as Box<dyn FnMut(_)>);
+        window
+            .add_event_listener_with_callback("keydown", key_listener.as_ref().unchecked_ref())?;
+        key_listener.forget();
+    }
+
+    {
+        let canvas_ctx = canvas_ctx.clone();
+        let state = state.clone();
+        let width = width.clone();
+        let height = height.clone();
+
+        let update_width = move || {
+            width.set(canvas.client_width());
+            height.set(canvas.client_height());
+            canvas.set_width(width.get() as _);
+            canvas.set_height(height.get() as _);
+
+            render(
+                &state.borrow(),
+                &canvas_ctx,
+                width.get(),
+                height.get(),
+                0,
+                0,
+                false,
+            );
+        };
+
+        update_width();
+
+        let resize_listener = Closure::wrap(Box::new(update_width) as Box<dyn FnMut()>);
+        window
+            .add_event_listener_with_callback("resize", resize_listener.as_ref().unchecked_ref())?;
+        resize_listener.forget();
+    }
+
+    {
+        let canvas_ctx = canvas_ctx.clone();
+        let state = state.clone();
+        let width = width.clone();
+        let height = height.clone();
+
+        let handle_mouse_event = move |event: web_sys::MouseEvent| {
+            let x = event.offset_x();
+            let y = event.offset_y();
+            let cursor_down = event.buttons() == 1;
+            if drag_sticker(
+                &mut state.borrow_mut(),
+                width.get(),
+                height.get(),
+                x,
+                y,
+                cursor_down,
+            ) {
+                return;
+            }
+            render(
+                &state.borrow(),
+                &canvas_ctx,
+                width.get(),
+                height.get(),
+                x,
+                y,
+                cursor_down,
+            );
+        };
+
+        let mouse_listener = Closure::wrap(Box::new(handle_mouse_event) as Box<dyn FnMut(_)>);
+        window.add_event_listener_with_callback(
+            "mousedown",
+            mouse_listener.as_ref().unchecked_ref(),
+        )?;
+        window
+            .add_event_listener_with_callback("mouseup", mouse_listener.as_ref().unchecked_ref())?;
+        window.add_event_listener_with_callback(
+            "mousemove",
+            mouse_listener.as_ref().unchecked_ref(),
+        )?;
+        mouse_listener.forget();
+    }
+
+    {
+        let mut last_time = now_seconds();
+        let rerender = move || {
+            let mut state = state.borrow_mut();
+            let time = now_seconds();
+            state.advance_playback(time - last_time);
+            last_time = time;
+            history_slider.set_max(&state.history.len().to_string());
+            history_slider.set_value(&state.history.position().to_string());
+
+            if !state.turn_queue.is_empty() {
+                if state.turn_progress >= 1.0 {
+                    state.puzzle_state = state
+                        .puzzle
+                        .get_derived_state_turn_index(&state.puzzle_state, state.turn_queue[0]);
+                    state.turn_queue.pop_front();
+                    state.turn_progress = 0.0;
+                    if state.is_solving && state.turn_queue.is_empty() {
+                        solve_next_step(&mut state);
+                    }
+                } else {
+                    state.turn_progress = f64::min(state.turn_progress + 0.15, 1.0);
+                }
+            }
+            if !unsafe { CURSOR_DOWN } {
+                render(&state, &canvas_ctx, width.get(), height.get(), 0, 0, false);
+            }
+        };
+
+        let time_listener = Closure::wrap(Box::new(rerender) as Box<dyn FnMut()>);
+        window.set_interval_with_callback_and_timeout_and_arguments_0(
+            time_listener.as_ref().unchecked_ref(),
+            7,
+        )?;
+        time_listener.forget();
+    }
+
+    Ok(())
+}
+
+/// Turns the puzzle when the cursor is pressed on a sticker and dragged across it.
+/// Returns whether the cursor is dragging a sticker, in which case it doesn't orbit the camera.
+fn drag_sticker(
+    state: &mut State,
+    width: i32,
+    height: i32,
+    cursor_x: i32,
+    cursor_y: i32,
+    cursor_down: bool,
+) -> bool {
+    // Picking works relative to the center of the canvas, like the camera does
+    let to_image_center = |(x, y): (i32, i32)| ((x - width / 2) as f64, (y - height / 2) as f64);
+    let camera: Camera = (unsafe { ORBIT_START_CAMERA }).unwrap_or_default();
+
+    unsafe {
+        match STICKER_DRAG_START {
+            None => {
+                if !CURSOR_DOWN
+                    && cursor_down
+                    && pick_face(
+                        &camera,
+                        &state.puzzle.faces,
+                        to_image_center((cursor_x, cursor_y)),
+                    )
+                    .is_some()
+                {
+                    // Just pressed cursor on a sticker
+                    STICKER_DRAG_START = Some((cursor_x, cursor_y));
+                    STICKER_DRAG_TURNED = false;
+                    return true;
+                }
+                false
+            }
+            Some(start) => {
+                if !cursor_down {
+                    // Just released cursor
+                    STICKER_DRAG_START = None;
+                } else if !STICKER_DRAG_TURNED
+                    && f64::from(cursor_x - start.0).hypot(f64::from(cursor_y - start.1))
+                        >= STICKER_DRAG_DISTANCE
+                {
+                    // Only one turn per drag, even if the cursor keeps going
+                    STICKER_DRAG_TURNED = true;
+                    if let Some(turn_index) = pick_turn(
+                        &camera,
+                        &state.puzzle,
+                        to_image_center(start),
+                        to_image_center((cursor_x, cursor_y)),
+                    ) {
+                        state.make_turn(turn_index);
+                    }
+                }
+                true
+            }
+        }
+    }
+}
+
+/// Adds buttons to undo, redo, and play back the move history, a slider to jump through it,
+/// and a choice of playback speeds. Returns the slider, which follows the history as it changes.
+fn add_history_controls(
+    document: &web_sys::Document,
+    buttons_div: &web_sys::HtmlDivElement,
+    state: &Rc<RefCell<State>>,
+) -> Result<web_sys::HtmlInputElement, JsValue> {
+    let buttons = [
+        ("Undo", State::undo as fn(&mut State)),
+        ("Play/Pause", State::toggle_playback),
+        ("Redo", State::redo),
+    ];
+    for (text, action) in buttons {
+        let button = document
+            .create_element("button")?
+            .dyn_into::<web_sys::HtmlButtonElement>()?;
+        button.set_inner_text(text);
+        buttons_div.append_child(&button)?;
+
+        let state = state.clone();
+        let handle_click = move || action(&mut state.borrow_mut());
+
+        let click_listener = Closure::wrap(Box::new(handle_click) as Box<dyn FnMut()>);
+        button
+            .add_event_listener_with_callback("click", click_listener.as_ref().unchecked_ref())?;
+        click_listener.forget();
+    }
+
+    let history_slider = document
+        .create_element("input")?
+        .dyn_into::<web_sys::HtmlInputElement>()?;
+    history_slider.set_type("range");
+    history_slider.set_min("0");
+    history_slider.set_title("Move history");
+    buttons_div.append_child(&history_slider)?;
+    {
+        let state = state.clone();
+        let history_slider_el = history_slider.clone();
+        let handle_input = move || {
+            if let Ok(position) = history_slider_el.value().parse() {
+                state.borrow_mut().jump_to(position);
+            }
+        };
+
+        let input_listener = Closure::wrap(Box::new(handle_input) as Box<dyn FnMut()>);
+        history_slider
+            .add_event_listener_with_callback("input", input_listener.as_ref().unchecked_ref())?;
+        input_listener.forget();
+    }
+
+    let speed_select = document
+        .create_element("select")?
+        .dyn_into::<web_sys::HtmlSelectElement>()?;
+    for turns_per_second in PLAYBACK_TURNS_PER_SECOND {
+        let option = document
+            .create_element("option")?
+            .dyn_into::<web_sys::HtmlOptionElement>()?;
+        option.set_value(&turns_per_second.to_string());
+        option.set_text(&format!("{} turns/s", turns_per_second));
+        speed_select.append_child(&option)?;
+    }
+    speed_select.set_value(&DEFAULT_PLAYBACK_TURNS_PER_SECOND.to_string());
+    buttons_div.append_child(&speed_select)?;
+    {
+        let state = state.clone();
+        let speed_select_el = speed_select.clone();
+        let handle_change = move || {
+            if let Ok(turns_per_second) = speed_select_el.value().parse() {
+                state
+                    .borrow_mut()
+                    .playback
+                    .set_turns_per_second(turns_per_second);
+            }
+        };
+
+        let change_listener = Closure::wrap(Box::new(handle_change) as Box<dyn FnMut()>);
+        speed_select
+            .add_event_listener_with_callback("change", change_listener.as_ref().unchecked_ref())?;
+        change_listener.forget();
+    }
+
+    Ok(history_slider)
+}
+
+/// Adds buttons to turn timer mode on and off, to start a new session of solves,
+/// and to show or hide the net
+fn add_timer_controls(
+    document: &web_sys::Document,
+    buttons_div: &web_sys::HtmlDivElement,
+    state: &Rc<RefCell<State>>,
+) -> Result<(), JsValue> {
+    let buttons = [
+        ("Timer", State::toggle_timer_mode as fn(&mut State)),
+        ("New Session", State::new_session),
+        ("Net", |state| state.show_net = !state.show_net),
+    ];
+    for (text, action) in buttons {
+        let button = document
+            .create_element("button")?
+            .dyn_into::<web_sys::HtmlButtonElement>()?;
+        button.set_inner_text(text);
+        buttons_div.append_child(&button)?;
+
+        let state = state.clone();
+        let handle_click = move || action(&mut state.borrow_mut());
+
+        let click_listener = Closure::wrap(Box::new(handle_click) as Box<dyn FnMut()>);
+        button
+            .add_event_listener_with_callback("click", click_listener.as_ref().unchecked_ref())?;
+        click_listener.forget();
+    }
+    Ok(())
+}
+
+/// The time in seconds, from when the page was opened
+fn now_seconds() -> f64 {
+    web_sys::window().unwrap().performance().unwrap().now() / 1000.0
+}
+
+/// Replaces the buttons for the turns with ones for the current puzzle's turns
+fn add_turn_buttons(
+    document: &web_sys::Document,
+    turn_buttons_div: &web_sys::HtmlDivElement,
+    state: &Rc<RefCell<State>>,
+) -> Result<(), JsValue> {
+    turn_buttons_div.set_inner_html("");
+    let mut cuts_list: Vec<_> = state
+        .borrow()
+        .puzzle
+        .turn_names_iter()
+        .cloned()
+        .enumerate()
+        .collect();
+    cuts_list.sort();
+    for (cut_index, cut_name) in cuts_list {
+        let button = document
+            .create_element("button")?
+            .dyn_into::<web_sys::HtmlButtonElement>()?;
+        button.set_inner_text(&cut_name);
+        turn_buttons_div.append_child(&button)?;
+
+        let state = state.clone();
+        // The puzzle is drawn again on the next tick, which shows the turn starting
+        let handle_click = move || {
+            state.borrow_mut().make_turn(cut_index);
+        };
+
+        let click_listener = Closure::wrap(Box::new(handle_click) as Box<dyn FnMut()>);
+        button
+            .add_event_listener_with_callback("click", click_listener.as_ref().unchecked_ref())?;
+        click_listener.forget();
+    }
+    Ok(())
+}
+
+fn render(
+    state: &State,
+    canvas_ctx: &web_sys::CanvasRenderingContext2d,
+    width: i32,
+    height: i32,
+    cursor_x: i32,
+    cursor_y: i32,
+    cursor_down: bool,
+) {
+    let mut camera: Camera = (unsafe { ORBIT_START_CAMERA }).unwrap_or_default();
+
+    unsafe {
+        if !CURSOR_DOWN && cursor_down {
+            // Just pressed cursor
+            CURSOR_DOWN = true;
+            CURSOR_START_POSITION = (cursor_x, cursor_y);
+            ORBIT_START_CAMERA = Some(compute_camera_position_from_orbit(
+                width,
+                height,
+                camera,
+                CURSOR_START_POSITION,
+                (cursor_x, cursor_y),
+            ));
+        }
+        if CURSOR_DOWN && !cursor_down {
+            // Just released cursor
+            CURSOR_DOWN = false;
+            ORBIT_START_CAMERA = Some(compute_camera_position_from_orbit(
+                width,
+                height,
+                camera,
+                CURSOR_START_POSITION,
+                (cursor_x, cursor_y),
+            ));
+        }
+        if CURSOR_DOWN {
+            camera = compute_camera_position_from_orbit(
+                width,
+                height,
+                camera,
+                CURSOR_START_POSITION,
+                (cursor_x, cursor_y),
+            )
+        }
+    }
+
+    let mut renderer = CanvasRenderer {
+        canvas_ctx,
+        width: width.into(),
+        height: height.into(),
+    };
+    draw_puzzle(
+        &mut renderer,
+        &state.puzzle,
+        &state.puzzle_state,
+        &state.color_scheme,
+        state
+            .turn_queue
+            .front()
+            .map(|&turn_index| (turn_index, state.turn_progress)),
+        &camera,
+        width.into(),
+        height.into(),
+    );
+    renderer.fill_text(
+        &format!(
+            "{:.1}% solved",
+            state.puzzle.get_num_solved_pieces(&state.puzzle_state) as f64
+                / state.puzzle.get_num_pieces() as f64
+                * 100.0
+        ),
+        10.0,
+        50.0,
+        30.0,
+        "#ffffff",
+    );
+    if state.timer_mode {
+        draw_timer(&mut renderer, state);
+    }
+    if state.show_net {
+        // In the bottom right corner, on a panel that sets it apart from the 3D view
+        let size = 0.4 * f64::from(width.min(height));
+        let left = f64::from(width) - size - 10.0;
+        let top = f64::from(height) - size - 10.0;
+        renderer.fill_polygon(
+            &[
+                (left, top),
+                (left + size, top),
+                (left + size, top + size),
+                (left, top + size),
+            ],
+            NET_BACKGROUND_COLOR,
+        );
+        draw_net(
+            &mut renderer,
+            &state.puzzle,
+            &state.puzzle_state,
+            &state.net,
+            &state.color_scheme,
+            left,
+            top,
+            size,
+            size,
+        );
+    }
+}
+
+/// Draws the time (or the inspection countdown) and the statistics of the session
+fn draw_timer(renderer: &mut CanvasRenderer, state: &State) {
+    let session = &state.session;
+    let time = match state.timer.display(now_seconds()) {
+        Some(time) => time,
+        None => session
+            .solves
+            .last()
+            .map_or("Scramble to start".to_string(), |solve| {
+                format_time(solve.result())
+            }),
+    };
+    renderer.fill_text(&time, 10.0, 110.0, 50.0, "#ffffff");
+
+    let format_statistic = |value: Option<f64>| value.map_or("-".to_string(), format_time);
+    let mut lines = vec![
+        format!(
+            "ao5: {}  ao12: {}",
+            format_statistic(session.average_of(5)),
+            format_statistic(session.average_of(12))
+        ),
+        format!(
+            "mean: {}  best: {}  ({} solves)",
+            format_statistic(session.mean()),
+            format_statistic(session.best()),
+            session.solves.len()
+        ),
+    ];
+    if let Some(solve) = session.solves.last() {
+        let turns_per_second = solve
+            .turns_per_second()
+            .map_or("-".to_string(), |turns_per_second| {
+                format!("{:.2}", turns_per_second)
+            });
+        lines.push(format!(
+            "last: {} turns, {} TPS",
+            solve.turns, turns_per_second
+        ));
+    }
+    if let (Some(turns), Some(turns_per_second)) =
+        (session.mean_turns(), session.mean_turns_per_second())
+    {
+        lines.push(format!(
+            "mean: {:.1} turns, {:.2} TPS",
+            turns, turns_per_second
+        ));
+    }
+    for (i, line) in lines.iter().enumerate() {
+        renderer.fill_text(line, 10.0, 145.0 + 25.0 * i as f64, 20.0, "#ffffff");
+    }
+}
+
+struct CanvasRenderer<'a> {
+    canvas_ctx: &'a web_sys::CanvasRenderingContext2d,
+    width: f64,
+    height: f64,
+}
+
+impl Renderer for CanvasRenderer<'_> {
+    fn fill_background(&mut self, color: &str) {
+        self.canvas_ctx.set_fill_style_str(color);
+        self.canvas_ctx.fill_rect(0.0, 0.0, self.width, self.height);
+    }
+
+    fn fill_polygon(&mut self, points: &[(f64, f64)], color: &str) {
+        self.canvas_ctx.set_fill_style_str(color);
+        self.canvas_ctx.begin_path();
+        for &(x, y) in points {
+            self.canvas_ctx.line_to(x, y);
+        }
+        self.canvas_ctx.close_path();
+        self.canvas_ctx.fill();
+    }
+
+    fn fill_text(&mut self, text: &str, x: f64, y: f64, font_size: f64, color: &str) {
+        self.canvas_ctx.set_fill_style_str(color);
+        self.canvas_ctx.set_font(&format!("{}px Arial", font_size));
+        self.canvas_ctx.fill_text(text, x, y).unwrap();
+    }
+}